@@ -0,0 +1,7 @@
+(Square in UFrame 1, relative to where the tool starts)
+G21 G91 G17
+G1 Z100 F1800
+G1 X30 Y100
+G1 Z-100
+G1 X-30 Y-100
+M30
@@ -1,6 +1,5 @@
-use fanuc_rmi::{drivers::{FanucDriver, FanucDriverConfig}, Configuration, FrcError, Position};
+use fanuc_rmi::{drivers::{FanucDriver, FanucDriverConfig, RecoveryPolicy}, gcode::GcodeLoader, instructions::Move, FrcError, PacketEnum, Position};
 // use fanuc_rmi::{Configuration, Position};
-use std::error::Error;
 
 
 #[tokio::main]
@@ -24,136 +23,52 @@ async fn main() -> Result<(), FrcError > {
 
 
     // driver.get_status().await?;
-    let res = driver.initialize().await;
-    if res.is_err() {
-        println!("Already Initialized");
-        driver.abort().await?;
-        driver.initialize().await?;
+    if let Err(e) = driver.initialize().await {
+        println!("Initialize failed: {}", e);
+        let report = driver.recover(&e, &RecoveryPolicy::default()).await;
+        println!("Recovery: {:?}", report);
+        if !report.recovered {
+            return Err(e);
+        }
     };
 
-    driver.start_program().await?;
+    // The position comes back in the active user frame and tool, so the
+    // program runs in those too.
+    let (configuration, start) = driver.read_cartesian_position(1).await?;
+    let loader = GcodeLoader::new()
+        .configuration(configuration)
+        .orientation(start.w, start.p, start.r)
+        .start(&start);
+    let program = driver.load_gcode(concat!(env!("CARGO_MANIFEST_DIR"), "/square.nc"), loader)?;
+    let last_sequence_id = program
+        .iter()
+        .filter_map(|packet| match packet {
+            PacketEnum::Instruction(instruction) => Some(instruction.get_sequence_id()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    driver.start_program(program).await?;
 
-    let dist:f32 = 100.0;
+    let dist: f32 = 100.0;
     let speed: u16 = 31;
-    driver.linear_relative(
-        1,    
-        Configuration {
-            u_tool_number: 1,
-            u_frame_number: 1,
-            front: 1,
-            up: 1,
-            left: 1,
-            glip: 1,
-            turn4: 1,
-            turn5: 1,
-            turn6: 1,
-        },
-        Position {
-            x: 0.0,
-            y: 0.0,
-            z: dist.clone(),
-            w: 0.0,
-            p: 0.0,
-            r: 0.0,
-            ext1: 0.0,
-            ext2: 0.0,
-            ext3: 0.0,
-        },
-        fanuc_rmi::SpeedType::MMSec,
-        speed.clone(),
-        fanuc_rmi::TermType::FINE,
-        1,
-    ).await?;
-    
-    driver.linear_relative(
-        2,    
-        Configuration {
-            u_tool_number: 1,
-            u_frame_number: 1,
-            front: 1,
-            up: 1,
-            left: 1,
-            glip: 1,
-            turn4: 1,
-            turn5: 1,
-            turn6: 1,
-        },
-        Position {
-            x: 30.0,
-            y: dist.clone(),
-            z: 0.0,
-            w: 0.0,
-            p: 0.0,
-            r: 0.0,
-            ext1: 0.0,
-            ext2: 0.0,
-            ext3: 0.0,
-        },
-        fanuc_rmi::SpeedType::MMSec,
-        speed.clone(),
-        fanuc_rmi::TermType::FINE,
-        1,
-    ).await?;
+    let offsets = [
+        Position::new(0.0, 0.0, dist, 0.0, 0.0, 0.0),
+        Position::new(30.0, dist, 0.0, 0.0, 0.0, 0.0),
+        Position::new(0.0, 0.0, -dist, 0.0, 0.0, 0.0),
+        Position::new(-30.0, -dist, 0.0, 0.0, 0.0, 0.0),
+    ];
+    // Sequence IDs carry on from the program's.
+    for (sequence_id, offset) in (last_sequence_id + 1..).zip(offsets) {
+        let motion = Move::linear()
+            .by(offset)
+            .speed_mm_s(speed)
+            .fine()
+            .config(configuration)
+            .build(sequence_id)?;
+        driver.run_instruction(motion).await?;
+    }
 
-    driver.linear_relative(
-        3,    
-        Configuration {
-            u_tool_number: 1,
-            u_frame_number: 1,
-            front: 1,
-            up: 1,
-            left: 1,
-            glip: 1,
-            turn4: 1,
-            turn5: 1,
-            turn6: 1,
-        },
-        Position {
-            x: 0.0,
-            y: 0.0,
-            z: -dist.clone(),
-            w: 0.0,
-            p: 0.0,
-            r: 0.0,
-            ext1: 0.0,
-            ext2: 0.0,
-            ext3: 0.0,
-        },
-        fanuc_rmi::SpeedType::MMSec,
-        speed.clone(),
-        fanuc_rmi::TermType::FINE,
-        1,
-    ).await?;
-
-    driver.linear_relative(
-        4,    
-        Configuration {
-            u_tool_number: 1,
-            u_frame_number: 1,
-            front: 1,
-            up: 1,
-            left: 1,
-            glip: 1,
-            turn4: 1,
-            turn5: 1,
-            turn6: 1,
-        },
-        Position {
-            x: -30.0,
-            y: -dist.clone(),
-            z: 0.0,
-            w: 0.0,
-            p: 0.0,
-            r: 0.0,
-            ext1: 0.0,
-            ext2: 0.0,
-            ext3: 0.0,
-        },
-        fanuc_rmi::SpeedType::MMSec,
-        speed.clone(),
-        fanuc_rmi::TermType::FINE,
-        1,
-    ).await?;
     driver.abort().await?;
     driver.disconnect().await?;
 
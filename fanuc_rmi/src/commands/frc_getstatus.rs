@@ -18,6 +18,8 @@ pub struct FrcGetStatusResponse {
     pub single_step_mode: i8,
     #[serde(rename = "NumberUTool")]
     pub number_utool: i8,
+    #[serde(rename = "NextSequenceID", default)]
+    pub next_sequence_id: u32,
     #[serde(rename = "NumberUFrame")]
     pub number_uframe: i8,
 }
\ No newline at end of file
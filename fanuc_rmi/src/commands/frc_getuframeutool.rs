@@ -8,13 +8,9 @@ pub struct FrcGetUFrameUTool {
 
 
 impl FrcGetUFrameUTool{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>) -> Self {
+    pub fn new(groupentered: Option<u8>) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
         }
 
     }
@@ -24,12 +20,12 @@ impl FrcGetUFrameUTool{
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcGetUFrameUToolResponse { 
     #[serde(rename = "UFrameNumber")]
-    uframe_number: u8,
+    pub uframe_number: u8,
     #[serde(rename = "UToolNumber")]
-    utool_number: u8,
+    pub utool_number: u8,
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "Group")]
-    pub group: u16,
+    pub group: u8,
 
 }
\ No newline at end of file
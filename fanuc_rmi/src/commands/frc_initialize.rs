@@ -8,11 +8,8 @@ pub struct FrcInitialize {
 
 
 impl FrcInitialize{
-    fn new(groupmask: Option<u8>) -> Self {
-        let groupmask = match groupmask {
-            Some(gm) => gm,
-            None => 1
-        };
+    pub fn new(groupmask: Option<u8>) -> Self {
+        let groupmask = groupmask.unwrap_or(1);
 
         Self {
             group_mask: groupmask
@@ -33,6 +30,6 @@ pub struct FrcInitializeResponse {
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "GroupMask")]
-    pub group_mask: u16,
+    pub group_mask: u8,
 
 }
\ No newline at end of file
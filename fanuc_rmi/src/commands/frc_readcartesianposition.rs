@@ -9,13 +9,10 @@ pub struct FrcReadCartesianPosition {
 
 
 impl FrcReadCartesianPosition{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, ) -> Self {
+    pub fn new(groupentered: Option<u8>) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },        }
+            group: groupentered.unwrap_or(1),
+        }
 
     }
 }
@@ -31,8 +28,5 @@ pub struct FrcReadCartesianPositionResponse {
     #[serde(rename = "Position")]
     pub pos: Position,
     #[serde(rename = "Group")]
-    group: u8,
-
-
-
+    pub group: u8,
 }
\ No newline at end of file
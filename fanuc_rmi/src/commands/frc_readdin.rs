@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::packets::OnOff;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcReadDIN{
@@ -8,8 +9,7 @@ pub struct FrcReadDIN{
 
 
 impl FrcReadDIN{
-    #[allow(unused)]
-    fn new(port: u16) -> Self {
+    pub fn new(port: u16) -> Self {
         Self {
             port_num: port
         }
@@ -25,6 +25,6 @@ pub struct FrcReadDINResponse {
     #[serde(rename = "PortNumber")]
     pub port_number: u16,
     #[serde(rename = "PortValue")]
-    pub port_value: u8,
+    pub port_value: OnOff,
 
 }
\ No newline at end of file
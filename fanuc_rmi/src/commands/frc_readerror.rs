@@ -9,11 +9,8 @@ pub struct FrcReadError {
 
 
 impl FrcReadError{
-    fn new(count1: Option<u8>) -> Self {
-        let count1 = match count1 {
-            Some(gm) => gm,
-            None => 1
-        };
+    pub fn new(count1: Option<u8>) -> Self {
+        let count1 = count1.unwrap_or(1);
         Self {
             count: count1
         }
@@ -31,9 +28,145 @@ impl Default for FrcReadError {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcReadErrorResponse {   
     #[serde(rename = "ErrorID")]
-    error_id: u16,
+    pub error_id: u32,
     #[serde(rename = "Count")]
-    count: u8,
+    pub count: u8,
     #[serde(rename = "ErrorData")]
-    error_data: String
-}
\ No newline at end of file
+    pub error_data: String
+}
+
+impl FrcReadErrorResponse {
+    /// Parses `ErrorData`, or `None` when the controller had no alarm at that position.
+    pub fn alarm(&self) -> Option<Alarm> {
+        if self.error_data.trim().is_empty() {
+            return None;
+        }
+        Some(Alarm::parse(self.count, &self.error_data))
+    }
+}
+
+/// The subsystem that raised an alarm, taken from the prefix of the alarm code.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AlarmFacility {
+    /// Servo and drive alarms (`SRVO`).
+    Servo,
+    /// Motion planning alarms (`MOTN`).
+    Motion,
+    /// TP interpreter alarms (`INTP`).
+    Interpreter,
+    /// System alarms (`SYST`).
+    System,
+    /// Process I/O alarms (`PRIO`).
+    ProcessIO,
+    /// Host communication alarms (`HOST`).
+    Host,
+    /// Any other facility, with its prefix as sent by the controller.
+    Other(String),
+}
+
+impl AlarmFacility {
+    fn from_prefix(prefix: &str) -> Self {
+        match prefix {
+            "SRVO" => AlarmFacility::Servo,
+            "MOTN" => AlarmFacility::Motion,
+            "INTP" => AlarmFacility::Interpreter,
+            "SYST" => AlarmFacility::System,
+            "PRIO" => AlarmFacility::ProcessIO,
+            "HOST" => AlarmFacility::Host,
+            other => AlarmFacility::Other(other.to_string()),
+        }
+    }
+}
+
+/// FANUC alarm severities, from least to most disruptive.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AlarmSeverity {
+    Warn,
+    PauseLocal,
+    PauseGlobal,
+    StopLocal,
+    StopGlobal,
+    Servo,
+    AbortLocal,
+    AbortGlobal,
+    Servo2,
+    System,
+}
+
+impl AlarmSeverity {
+    fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "WARN" => Some(AlarmSeverity::Warn),
+            "PAUSE.L" => Some(AlarmSeverity::PauseLocal),
+            "PAUSE.G" => Some(AlarmSeverity::PauseGlobal),
+            "STOP.L" => Some(AlarmSeverity::StopLocal),
+            "STOP.G" => Some(AlarmSeverity::StopGlobal),
+            "SERVO" => Some(AlarmSeverity::Servo),
+            "ABORT.L" => Some(AlarmSeverity::AbortLocal),
+            "ABORT.G" => Some(AlarmSeverity::AbortGlobal),
+            "SERVO2" => Some(AlarmSeverity::Servo2),
+            "SYSTEM" => Some(AlarmSeverity::System),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of the controller alarm history, decoded from `ErrorData` such as
+/// `"SRVO-002 Teach pendant E-stop"`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alarm {
+    /// Position in the alarm history, 1 being the most recent.
+    pub index: u8,
+    pub facility: AlarmFacility,
+    pub number: u16,
+    /// Only present when the controller includes it in `ErrorData`.
+    pub severity: Option<AlarmSeverity>,
+    pub message: String,
+    /// `ErrorData` exactly as received.
+    pub raw: String,
+}
+
+impl Alarm {
+    /// Decodes an `ErrorData` string; text that does not start with an
+    /// alarm code is kept whole as the message under `AlarmFacility::Other("")`.
+    pub fn parse(index: u8, error_data: &str) -> Self {
+        let raw = error_data.to_string();
+        let text = error_data.trim();
+        let (code, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+
+        let parsed_code = code.split_once('-').and_then(|(prefix, number)| {
+            let valid_prefix = !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_alphabetic());
+            match (valid_prefix, number.parse::<u16>()) {
+                (true, Ok(number)) => Some((AlarmFacility::from_prefix(&prefix.to_ascii_uppercase()), number)),
+                _ => None,
+            }
+        });
+
+        let Some((facility, number)) = parsed_code else {
+            return Self {
+                index,
+                facility: AlarmFacility::Other(String::new()),
+                number: 0,
+                severity: None,
+                message: text.to_string(),
+                raw,
+            };
+        };
+
+        let rest = rest.trim_start();
+        let (token, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let (severity, message) = match AlarmSeverity::from_token(token) {
+            Some(severity) => (Some(severity), after.trim()),
+            None => (None, rest.trim()),
+        };
+
+        Self {
+            index,
+            facility,
+            number,
+            severity,
+            message: message.to_string(),
+            raw,
+        }
+    }
+}
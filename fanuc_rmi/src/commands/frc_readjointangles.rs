@@ -8,13 +8,9 @@ pub struct FrcReadJointAngles{
 }
 
 impl FrcReadJointAngles{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>) -> Self {
+    pub fn new(groupentered: Option<u8>) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
         }
     }
 }
@@ -24,7 +20,7 @@ pub struct FrcReadJointAnglesResponse {
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "TimeTag")]
-    pub time_tag: i16,
+    pub time_tag: u32,
     #[serde(rename = "JointAngles")]
     pub joint_angles: JointAngles,
     #[serde(rename = "Group")]
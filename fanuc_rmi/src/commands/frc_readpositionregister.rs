@@ -12,13 +12,9 @@ pub struct FrcReadPositionRegister {
 
 
 impl FrcReadPositionRegister{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, register:u16) -> Self {
+    pub fn new(groupentered: Option<u8>, register:u16) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
             register_number: register
         }
 
@@ -30,13 +26,13 @@ pub struct FrcReadPositionRegisterResponse {
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "RegisterNumber")]
-    pub register_number: i16,
+    pub register_number: u16,
     #[serde(rename = "Configuration")]
     pub config: Configuration,
     #[serde(rename = "Position")]
     pub position: Position,
     #[serde(rename = "Group")]
-    pub group: i16,
+    pub group: u8,
 
 
 }
\ No newline at end of file
@@ -4,20 +4,16 @@ use crate::FrameData;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcReadUFrameData {
     #[serde(rename = "FrameNumber")]
-    frame_number: i8,    
+    frame_number: u8,    
     #[serde(rename = "Group")]
     group: u8,
 }
 
 
 impl FrcReadUFrameData{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, frame:i8) -> Self {
+    pub fn new(groupentered: Option<u8>, frame: u8) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
             frame_number: frame,
         }
 
@@ -28,12 +24,12 @@ impl FrcReadUFrameData{
 pub struct FrcReadUFrameDataResponse { 
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
-    #[serde(rename = "UFrameNumber")]
-    pub uframe_number: i8,
+    #[serde(rename = "FrameNumber")]
+    pub frame_number: u8,
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
     #[serde(rename = "Frame")]
-    frame: FrameData,
+    pub frame: FrameData,
 
 
 }
\ No newline at end of file
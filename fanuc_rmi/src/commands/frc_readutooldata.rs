@@ -3,22 +3,18 @@ use crate::FrameData;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcReadUToolData {
-    #[serde(rename = "FrameNumber")]
-    frame_number: i8,    
+    #[serde(rename = "ToolNumber")]
+    tool_number: u8,
     #[serde(rename = "Group")]
     group: u8,
 }
 
 
 impl FrcReadUToolData{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, framenum:i8) -> Self {
+    pub fn new(groupentered: Option<u8>, toolnum: u8) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
-            frame_number: framenum
+            group: groupentered.unwrap_or(1),
+            tool_number: toolnum
             }
 
     }
@@ -28,10 +24,10 @@ impl FrcReadUToolData{
 pub struct FrcReadUToolDataResponse { 
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
-    #[serde(rename = "UToolNumber")]
-    utool_number: u8,
+    #[serde(rename = "ToolNumber")]
+    pub tool_number: u8,
     #[serde(rename = "Frame")]
-    frame: FrameData,
+    pub frame: FrameData,
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
 }
\ No newline at end of file
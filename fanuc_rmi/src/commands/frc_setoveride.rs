@@ -5,14 +5,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSetOverride {
     #[serde(rename = "Value")]
-    value: u8,
+    pub value: u8,
 }
 
 
 
 impl FrcSetOverride{
-    #[allow(unused)]
-    fn new(val: u8) -> Self {
+    pub fn new(val: u8) -> Self {
         Self {
             value: val
         }
@@ -24,7 +23,7 @@ impl FrcSetOverride{
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSetOverrideResponse {   
     #[serde(rename = "ErrorID")]
-    error_id: u16,
+    pub error_id: u32,
 }
 
 // #[derive(Serialize, Deserialize, Debug)]
@@ -3,22 +3,18 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSetUFrameUTool {
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
     #[serde(rename = "UFrameNumber")]
-    uframe_number: u8,
+    pub uframe_number: u8,
     #[serde(rename = "UToolNumber")]
-    utool_number: u8,
+    pub utool_number: u8,
 }
 
 
 impl FrcSetUFrameUTool{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, tool_num: u8, frame_num: u8 ) -> Self {
+    pub fn new(groupentered: Option<u8>, tool_num: u8, frame_num: u8 ) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
             utool_number: tool_num,
             uframe_number: frame_num
         }
@@ -33,6 +29,6 @@ pub struct FrcSetUFrameUToolResponse {
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "Group")]
-    pub group: u16,
+    pub group: u8,
 
 }
\ No newline at end of file
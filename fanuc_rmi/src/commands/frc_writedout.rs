@@ -1,17 +1,17 @@
 use serde::{Deserialize, Serialize};
+use crate::packets::OnOff;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcWriteDOUT{
     #[serde(rename = "PortNumber")]
     pub port_number: u16,
     #[serde(rename = "PortValue")]
-    pub port_value: u8,
+    pub port_value: OnOff,
 }
 
 
 impl FrcWriteDOUT{
-    #[allow(unused)]
-    fn new(port_num: u16,port_val: u8) -> Self {
+    pub fn new(port_num: u16,port_val: OnOff) -> Self {
         Self {
             port_number: port_num,
             port_value: port_val
@@ -7,7 +7,7 @@ pub struct FrcWritePositionRegister {
     #[serde(rename = "RegisterNumber")]
     register_number: u16,
     #[serde(rename = "Configuration")]
-    congifuration: Configuration,
+    configuration: Configuration,
     #[serde(rename = "Position")]
     pub position: Position,
     #[serde(rename = "Group")]
@@ -16,16 +16,12 @@ pub struct FrcWritePositionRegister {
 
 
 impl FrcWritePositionRegister{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, register:u16, config:Configuration , pos:Position) -> Self {
+    pub fn new(groupentered: Option<u8>, register:u16, config:Configuration , pos:Position) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
             register_number: register,
             position: pos,
-            congifuration: config
+            configuration: config
         }
 
     }
@@ -4,22 +4,18 @@ use crate::FrameData;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcWriteUFrameData {
     #[serde(rename = "FrameNumber")]
-    frame_number: i8,    
+    pub frame_number: u8,
     #[serde(rename = "Frame")]
-    frame: FrameData,
+    pub frame: FrameData,
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
 }
 
 
 impl FrcWriteUFrameData{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, framenum:i8, framespecs:FrameData) -> Self {
+    pub fn new(groupentered: Option<u8>, framenum: u8, framespecs:FrameData) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
             frame_number: framenum,
             frame: framespecs
         }
@@ -32,5 +28,5 @@ pub struct FrcWriteUFrameDataResponse {
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
 }
\ No newline at end of file
@@ -4,22 +4,18 @@ use crate::FrameData;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcWriteUToolData {
     #[serde(rename = "ToolNumber")]
-    tool_number: i8,    
+    pub tool_number: u8,
     #[serde(rename = "Frame")]
-    frame: FrameData,
+    pub frame: FrameData,
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
 }
 
 
 impl FrcWriteUToolData{
-    #[allow(unused)]
-    fn new(groupentered: Option<u8>, toolnum:i8, framespecs:FrameData) -> Self {
+    pub fn new(groupentered: Option<u8>, toolnum: u8, framespecs:FrameData) -> Self {
         Self {
-            group: match groupentered {
-                Some(gm) => gm,
-                None => 1
-            },
+            group: groupentered.unwrap_or(1),
             tool_number: toolnum,
             frame: framespecs
         }
@@ -32,5 +28,5 @@ pub struct FrcWriteUToolDataResponse {
     #[serde(rename = "ErrorID")]
     pub error_id: u32,
     #[serde(rename = "Group")]
-    group: u8,
+    pub group: u8,
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::FrcError;
+
+/// User frame numbers the controller accepts; 0 is the world frame.
+pub const UFRAME_RANGE: RangeInclusive<u8> = 0..=9;
+/// User tool numbers the controller accepts.
+pub const UTOOL_RANGE: RangeInclusive<u8> = 1..=10;
+/// Turn numbers the controller's `CONFIG` type can hold.
+pub const TURN_RANGE: RangeInclusive<i8> = -8..=7;
+
+/// Declares a two-state configuration flag that the RMI encodes as `1`/`0`.
+macro_rules! config_flag {
+    ($(#[$doc:meta])* $name:ident { $set:ident, $unset:ident }) => {
+        $(#[$doc])*
+        #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[serde(try_from = "u8", into = "u8")]
+        pub enum $name {
+            $set,
+            $unset,
+        }
+
+        impl From<$name> for u8 {
+            fn from(value: $name) -> u8 {
+                match value {
+                    $name::$set => 1,
+                    $name::$unset => 0,
+                }
+            }
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = String;
+
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                match value {
+                    1 => Ok($name::$set),
+                    0 => Ok($name::$unset),
+                    other => Err(format!("{} must be 0 or 1, got {}", stringify!($name), other)),
+                }
+            }
+        }
+    };
+}
+
+config_flag!(
+    /// Whether the wrist center is in front of or behind the J1 axis.
+    FrontBack { Front, Back }
+);
+config_flag!(
+    /// Elbow above or below the line from J2 to the wrist center.
+    UpDown { Up, Down }
+);
+config_flag!(
+    /// Arm to the left or right of the J1 axis; only meaningful on some arms.
+    LeftRight { Left, Right }
+);
+config_flag!(
+    /// Wrist flip, i.e. the sign of J5.
+    FlipNoFlip { Flip, NoFlip }
+);
+
+/// Arm configuration and frame selection sent with Cartesian motion.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "PascalCase")]
+pub struct Configuration {
+    pub u_tool_number: u8,
+    pub u_frame_number: u8,
+    pub front: FrontBack,
+    pub up: UpDown,
+    pub left: LeftRight,
+    pub flip: FlipNoFlip,
+    pub turn4: i8,
+    pub turn5: i8,
+    pub turn6: i8,
+}
+
+impl Default for Configuration {
+    /// `NUT 0, 0, 0` in the world frame with tool 1.
+    fn default() -> Self {
+        Self {
+            u_tool_number: 1,
+            u_frame_number: 0,
+            front: FrontBack::Front,
+            up: UpDown::Up,
+            left: LeftRight::Right,
+            flip: FlipNoFlip::NoFlip,
+            turn4: 0,
+            turn5: 0,
+            turn6: 0,
+        }
+    }
+}
+
+impl Configuration {
+    /// Checks the values the controller would otherwise reject with
+    /// `InvalidUToolNumber`, `InvalidUFrameNumber` or `InvalidPositionData`.
+    pub fn validate(&self) -> Result<(), FrcError> {
+        if !UTOOL_RANGE.contains(&self.u_tool_number) {
+            return Err(FrcError::InvalidConfiguration(format!(
+                "UToolNumber {} is outside {:?}",
+                self.u_tool_number, UTOOL_RANGE
+            )));
+        }
+        if !UFRAME_RANGE.contains(&self.u_frame_number) {
+            return Err(FrcError::InvalidConfiguration(format!(
+                "UFrameNumber {} is outside {:?}",
+                self.u_frame_number, UFRAME_RANGE
+            )));
+        }
+        for (name, turn) in [("Turn4", self.turn4), ("Turn5", self.turn5), ("Turn6", self.turn6)] {
+            if !TURN_RANGE.contains(&turn) {
+                return Err(FrcError::InvalidConfiguration(format!("{} {} is outside {:?}", name, turn, TURN_RANGE)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Configuration {
+    /// Formats the flags the way the teach pendant shows them, e.g. `NUT 0, 0, 0`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flip = if self.flip == FlipNoFlip::Flip { 'F' } else { 'N' };
+        let left = if self.left == LeftRight::Left { "L" } else { "" };
+        let up = if self.up == UpDown::Up { 'U' } else { 'D' };
+        let front = if self.front == FrontBack::Front { 'T' } else { 'B' };
+        write!(f, "{}{}{}{} {}, {}, {}", flip, left, up, front, self.turn4, self.turn5, self.turn6)
+    }
+}
@@ -1,24 +1,38 @@
-use serde::Deserialize;
-// use serde::Serialize;
-use tokio::sync::mpsc;
-use std::fmt::format;
-use std::{error::Error, io, sync::Arc, time::Duration};
+use tokio::sync::{broadcast, oneshot};
+use std::{sync::Arc, time::{Duration, Instant}};
 use tokio::{ net::TcpStream, sync::Mutex, time::sleep};
 use tokio::io::{ AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf, split};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
 
-use crate::{packets::*, FanucErrorCode};
+use crate::packets::*;
 use crate::instructions::*;
 use crate::commands::*;
 use crate::PacketEnum;
-use crate::{Configuration, Position, SpeedType, TermType, FrcError };
-use std::marker::Send;
+use crate::gcode::GcodeLoader;
+use crate::program::ProgramFile;
+use crate::{validate_group, Configuration, GroupMask, JointAngles, Position, Speed, Termination, FrcError };
+use super::{ProgramControl, SessionSettings};
 
 #[derive(Debug,Clone)]
 pub struct  FanucDriverConfig {
     addr: String,
     port: u32,
     max_messages: usize,
+    parse_mode: ParseMode,
+}
+
+impl FanucDriverConfig {
+    /// Controller at `addr`, taking the `FRC_Connect` handshake on `port`.
+    pub fn new(addr: impl Into<String>, port: u32) -> Self {
+        Self { addr: addr.into(), port, ..Self::default() }
+    }
+
+    /// How responses the crate does not fully model are handled; lenient by default.
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = parse_mode;
+        self
+    }
 }
 
 
@@ -30,17 +44,82 @@ impl Default for FanucDriverConfig {
         Self {
             addr,
             port,
-            max_messages
+            max_messages,
+            parse_mode: ParseMode::default(),
+        }
+    }
+}
+
+/// Asynchronous notifications from the controller, delivered to every
+/// receiver returned by [`FanucDriver::subscribe`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriverEvent {
+    /// `FRC_SystemFault` was received; all pending instructions were failed.
+    SystemFault { sequence_id: u32 },
+    /// `FRC_Terminate` was received; the controller is closing the session.
+    Terminated,
+    /// The socket closed without a terminate notification.
+    Disconnected,
+    /// A response this crate has no variant for was kept in [`ParseMode::Lenient`].
+    UnknownPacket { name: String, raw: serde_json::Value },
+}
+
+/// Number of entries the controller keeps in its alarm history.
+const MAX_ALARM_HISTORY: u8 = 100;
+
+type Reply<T> = oneshot::Sender<Result<T, FrcError>>;
+
+/// Callers waiting on a response from the reader task.
+///
+/// Commands and communications are answered in the order they were sent, so
+/// they are queued; instructions are matched back by sequence ID.
+#[derive(Debug, Default)]
+pub(super) struct PendingResponses {
+    communications: VecDeque<Reply<CommunicationResponse>>,
+    commands: VecDeque<Reply<CommandResponse>>,
+    pub(super) instructions: HashMap<u32, Reply<InstructionResponse>>,
+    /// Set once the reader task has stopped; nothing sent after that is answered.
+    closed: bool,
+}
+
+impl PendingResponses {
+    fn check_open(&self) -> Result<(), FrcError> {
+        if self.closed {
+            return Err(FrcError::Disconnected());
+        }
+        Ok(())
+    }
+
+    fn fail_instructions(&mut self, err: impl Fn() -> FrcError) {
+        for (_, reply) in self.instructions.drain() {
+            let _ = reply.send(Err(err()));
         }
     }
+
+    fn fail_all(&mut self, err: impl Fn() -> FrcError) {
+        for reply in self.communications.drain(..) {
+            let _ = reply.send(Err(err()));
+        }
+        for reply in self.commands.drain(..) {
+            let _ = reply.send(Err(err()));
+        }
+        self.fail_instructions(err);
+    }
 }
 
 #[derive( Debug, Clone)]
 pub struct FanucDriver {
     pub config: FanucDriverConfig,
+    /// Port handed out by the controller in the `FRC_Connect` handshake; it
+    /// identifies this RMI session in logs and tracing spans.
+    pub connection_id: u32,
     pub messages: Arc<Mutex<VecDeque<String>>>,
     write_half: Arc<Mutex<WriteHalf<TcpStream>>>,
-    read_half: Arc<Mutex<ReadHalf<TcpStream>>>,
+    pub(super) pending: Arc<Mutex<PendingResponses>>,
+    events: broadcast::Sender<DriverEvent>,
+    settings: Arc<Mutex<SessionSettings>>,
+    /// Dropped with the last handle, which stops the reader task and closes the socket.
+    _reader_shutdown: Arc<oneshot::Sender<()>>,
 }
 
 // Static assertion to ensure FanucDriver is Send
@@ -50,6 +129,7 @@ pub struct FanucDriver {
 // };
 
 impl FanucDriver {
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(addr = %config.addr, port = config.port)))]
     pub async fn connect(config: FanucDriverConfig) -> Result<FanucDriver, FrcError> {
         let init_addr = format!("{}:{}",&config.addr, &config.port);
         let mut stream = connect_with_retries(&init_addr, 3).await?;
@@ -84,22 +164,21 @@ impl FanucDriver {
         }
 
         let response = String::from_utf8_lossy(&buffer[..n]);
-        
-        println!("Sent: {}\nReceived: {}", &packet, &response);
 
-        let res: CommunicationResponse  = match serde_json::from_str::<CommunicationResponse>(&response) {
-            Ok(response_packet) => response_packet,
-            Err(e) => {
-                let e = FrcError::Serialization(format!("Could not parse response: {}", e));
-                return Err(e)
-            }
+        #[cfg(feature="logging")]
+        println!("Sent: {}\nReceived: {}", &packet, &response);
+        #[cfg(feature="tracing")]
+        tracing::debug!(sent = %packet.trim_end(), received = %response.trim_end(), "FRC_Connect handshake");
+
+        let value = serde_json::from_str(&response).map_err(|e| FrcError::Serialization(format!("Could not parse response: {}", e)))?;
+        let res = decode_reply::<CommunicationResponse>(value, config.parse_mode)?;
+        error_of(&res)?;
+        let CommunicationResponse::FrcConnect(res) = res else {
+            return Err(FrcError::UnrecognizedPacket);
         };
-
-
-        let mut new_port = 0;
-        match res {
-            CommunicationResponse::FrcConnect(res) => new_port = res.port_number,
-            _ => ()
+        let new_port = match u16::try_from(res.port_number) {
+            Ok(port) if port != 0 => res.port_number,
+            _ => return Err(FrcError::Serialization(format!("FRC_Connect returned port {}", res.port_number))),
         };
 
         drop(stream);
@@ -107,67 +186,77 @@ impl FanucDriver {
         let stream = connect_with_retries(&init_addr, 3).await?;        
 
         let (read_half, write_half) = split(stream);
-        let read_half = Arc::new(Mutex::new(read_half));
         let write_half = Arc::new(Mutex::new(write_half));
         let messages: Arc<Mutex<VecDeque<String>>> = Arc::new(Mutex::new(VecDeque::new()));
         let mut msg = messages.lock().await;
         msg.push_back("Connected".to_string());
         drop(msg);
 
-        Ok(Self {
+        #[cfg(feature="tracing")]
+        tracing::info!(connection_id = new_port, "connected");
+
+        let (events, _) = broadcast::channel(16);
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let driver = Self {
             config,
+            connection_id: new_port,
             messages,
             write_half,
-            read_half,
-        })
-    }
+            pending: Arc::new(Mutex::new(PendingResponses::default())),
+            events,
+            settings: Arc::new(Mutex::new(SessionSettings::default())),
+            _reader_shutdown: Arc::new(shutdown_tx),
+        };
 
-    async fn log_message<T: Into<String>>(&self, message:T){
-        let message = message.into();
-        let messages = self.messages.clone();
-        let mut messages = messages.lock().await;
+        let reader = ResponseReader {
+            connection_id: driver.connection_id,
+            max_messages: driver.config.max_messages,
+            parse_mode: driver.config.parse_mode,
+            messages: driver.messages.clone(),
+            pending: driver.pending.clone(),
+            events: driver.events.clone(),
+        };
+        tokio::spawn(reader.run(read_half, shutdown_rx));
 
-        #[cfg(feature="logging")]
-        println!("{}", &message);
+        Ok(driver)
+    }
 
-        loop{
-            if messages.len() >= self.config.max_messages {
-                messages.pop_front();
-            }
-            else{break}
-        }
-        messages.push_back(message);
+    /// Returns a receiver for unsolicited controller notifications such as `FRC_SystemFault`.
+    pub fn subscribe(&self) -> broadcast::Receiver<DriverEvent> {
+        self.events.subscribe()
+    }
+
+    pub(super) async fn log_message<T: Into<String>>(&self, message:T){
+        record_message(&self.messages, self.config.max_messages, self.connection_id, message.into()).await;
     }
 
 
+    /// Initializes RMI for the groups of the last [`initialize_groups`](Self::initialize_groups), or group 1.
     pub async fn initialize(&self) -> Result<(), FrcError> {
+        let mask = self.session_settings().await.group_mask.unwrap_or_default();
+        self.initialize_groups(mask).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_Initialize", group_mask = mask.bits())))]
+    pub async fn initialize_groups(&self, mask: GroupMask) -> Result<(), FrcError> {
 
-        let packet = Command::FrcInitialize(FrcInitialize::default());
+        let packet = Command::FrcInitialize(FrcInitialize::new(Some(mask.bits())));
         
         let packet = match serde_json::to_string(&packet) {
             Ok(serialized_packet) => serialized_packet + "\r\n",
             Err(_) => return Err(FrcError::Serialization("Initalize packet didnt serialize correctly".to_string())),
         };
 
-        if let Err(e) = self.send_packet(packet.clone()).await {
-            self.log_message(e.to_string()).await;
-            return Err(e);
-        }; 
-
-        let response = self.recieve::<CommandResponse>().await?;
-
-        if let CommandResponse::FrcInitialize(ref res) = response {
-            if res.error_id != 0 {
-                self.log_message(format!("Error ID: {}", res.error_id)).await;
-                let error_code = FanucErrorCode::try_from(res.error_id).unwrap_or(FanucErrorCode::UnrecognizedFrcError);
-                return Err(FrcError::FanucErrorCode(error_code));
-            }
+        let CommandResponse::FrcInitialize(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
         };
+        self.settings.lock().await.group_mask = Some(mask);
         Ok(())
 
     }
     
         
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_Abort")))]
     pub async fn abort(&self) -> Result<(), FrcError> {
 
         let packet = Command::FrcAbort {};
@@ -177,19 +266,47 @@ impl FanucDriver {
             Err(_) => return Err(FrcError::Serialization("Abort packet didnt serialize correctly".to_string())),
         };
 
-        self.send_packet(packet.clone()).await?;
-        let response = self.recieve::<CommandResponse>().await?;
+        let CommandResponse::FrcAbort(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        Ok(())
+    }
+
+    /// Holds the running instructions with `FRC_Pause`; [`resume`](Self::resume) carries on.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_Pause")))]
+    pub async fn pause(&self) -> Result<(), FrcError> {
 
-        if let CommandResponse::FrcAbort(ref res) = response {
-            if res.error_id != 0 {
-                self.log_message(format!("Error ID: {}", res.error_id)).await;
-                let error_code = FanucErrorCode::try_from(res.error_id).unwrap_or(FanucErrorCode::UnrecognizedFrcError);
-                return Err(FrcError::FanucErrorCode(error_code));            
-            }
-        }
+        let packet = Command::FrcPause {};
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("Pause packet didnt serialize correctly".to_string())),
+        };
+
+        let CommandResponse::FrcPause(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
         Ok(())
     }
 
+    /// Continues after [`pause`](Self::pause) with `FRC_Continue`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_Continue")))]
+    pub async fn resume(&self) -> Result<(), FrcError> {
+
+        let packet = Command::FrcContinue {};
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("Continue packet didnt serialize correctly".to_string())),
+        };
+
+        let CommandResponse::FrcContinue(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_GetStatus")))]
     pub async fn get_status(&self) -> Result<(), FrcError> {
 
         let packet = Command::FrcGetStatus {};
@@ -199,18 +316,201 @@ impl FanucDriver {
             Err(_) => return Err(FrcError::Serialization("get_status packet didnt serialize correctly".to_string())),
         };
 
-        self.send_packet(packet.clone()).await?;
-        let response = self.recieve::<CommandResponse>().await?;        
-        if let CommandResponse::FrcGetStatus(ref res) = response {
-            if res.error_id != 0 {
-                self.log_message(format!("Error ID: {}", res.error_id)).await;
-                let error_code = FanucErrorCode::try_from(res.error_id).unwrap_or(FanucErrorCode::UnrecognizedFrcError);
-                return Err(FrcError::FanucErrorCode(error_code)); 
+        let CommandResponse::FrcGetStatus(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_Reset")))]
+    pub async fn reset(&self) -> Result<(), FrcError> {
+
+        let packet = Command::FrcReset {};
+        
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("Reset packet didnt serialize correctly".to_string())),
+        };
+
+        let CommandResponse::FrcReset(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        Ok(())
+    }
+
+    /// Reads the most recent alarm from the controller as the raw `ErrorData` text.
+    pub async fn read_error(&self) -> Result<String, FrcError> {
+        Ok(self.read_error_at(1).await?.error_data)
+    }
+
+    /// Reads the `count` most recent alarms, newest first, stopping early once
+    /// the controller's history runs out.
+    pub async fn read_alarms(&self, count: u8) -> Result<Vec<Alarm>, FrcError> {
+        let mut alarms = Vec::new();
+        for index in 1..=count {
+            match self.read_error_at(index).await?.alarm() {
+                Some(alarm) => alarms.push(alarm),
+                None => break,
             }
         }
+        Ok(alarms)
+    }
+
+    /// Reads the whole recent alarm history the controller keeps.
+    pub async fn read_alarm_history(&self) -> Result<Vec<Alarm>, FrcError> {
+        self.read_alarms(MAX_ALARM_HISTORY).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_ReadError", index)))]
+    async fn read_error_at(&self, index: u8) -> Result<FrcReadErrorResponse, FrcError> {
+
+        let packet = Command::FrcReadError(FrcReadError::new(Some(index)));
+        
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("ReadError packet didnt serialize correctly".to_string())),
+        };
+
+        match self.send_command(packet).await? {
+            CommandResponse::FrcReadError(res) => Ok(res),
+            _ => Err(FrcError::UnrecognizedPacket),
+        }
+    }
+
+    /// Selects the active user frame and user tool for group 1.
+    pub async fn set_uframe_utool(&self, uframe_number: u8, utool_number: u8) -> Result<(), FrcError> {
+        self.set_group_uframe_utool(1, uframe_number, utool_number).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_SetUFrameUTool", group)))]
+    pub async fn set_group_uframe_utool(&self, group: u8, uframe_number: u8, utool_number: u8) -> Result<(), FrcError> {
+        validate_group(group)?;
+
+        let packet = Command::FrcSetUFrameUTool(FrcSetUFrameUTool::new(Some(group), utool_number, uframe_number));
+        
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("SetUFrameUTool packet didnt serialize correctly".to_string())),
+        };
+
+        let CommandResponse::FrcSetUFrameUTool(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        self.settings.lock().await.frames.insert(group, (uframe_number, utool_number));
+        Ok(())
+    }
+
+    /// The active `(uframe, utool)` of `group`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_GetUFrameUTool", group)))]
+    pub async fn get_uframe_utool(&self, group: u8) -> Result<(u8, u8), FrcError> {
+        validate_group(group)?;
+
+        let packet = Command::FrcGetUFrameUTool(FrcGetUFrameUTool::new(Some(group)));
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("GetUFrameUTool packet didnt serialize correctly".to_string())),
+        };
+
+        match self.send_command(packet).await? {
+            CommandResponse::FrcGetUFrameUTool(res) => Ok((res.uframe_number, res.utool_number)),
+            _ => Err(FrcError::UnrecognizedPacket),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_ReadJointAngles", group)))]
+    pub async fn read_joint_angles(&self, group: u8) -> Result<JointAngles, FrcError> {
+        validate_group(group)?;
+
+        let packet = Command::FrcReadJointAngles(FrcReadJointAngles::new(Some(group)));
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("ReadJointAngles packet didnt serialize correctly".to_string())),
+        };
+
+        match self.send_command(packet).await? {
+            CommandResponse::FrcReadJointAngles(res) => Ok(res.joint_angles),
+            _ => Err(FrcError::UnrecognizedPacket),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_ReadCartesianPosition", group)))]
+    pub async fn read_cartesian_position(&self, group: u8) -> Result<(Configuration, Position), FrcError> {
+        validate_group(group)?;
+
+        let packet = Command::FrcReadCartesianPosition(FrcReadCartesianPosition::new(Some(group)));
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("ReadCartesianPosition packet didnt serialize correctly".to_string())),
+        };
+
+        match self.send_command(packet).await? {
+            CommandResponse::FrcReadCartesianPosition(res) => Ok((res.config, res.pos)),
+            _ => Err(FrcError::UnrecognizedPacket),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_ReadPositionRegister", group, register)))]
+    pub async fn read_position_register(&self, group: u8, register: u16) -> Result<(Configuration, Position), FrcError> {
+        validate_group(group)?;
+
+        let packet = Command::FrcReadPositionRegister(FrcReadPositionRegister::new(Some(group), register));
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("ReadPositionRegister packet didnt serialize correctly".to_string())),
+        };
+
+        match self.send_command(packet).await? {
+            CommandResponse::FrcReadPositionRegister(res) => Ok((res.config, res.position)),
+            _ => Err(FrcError::UnrecognizedPacket),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_WritePositionRegister", group, register)))]
+    pub async fn write_position_register(&self, group: u8, register: u16, config: Configuration, position: Position) -> Result<(), FrcError> {
+        validate_group(group)?;
+        config.validate()?;
+
+        let packet = Command::FrcWritePositionRegister(FrcWritePositionRegister::new(Some(group), register, config, position));
+
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("WritePositionRegister packet didnt serialize correctly".to_string())),
+        };
+
+        let CommandResponse::FrcWritePositionRegister(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        Ok(())
+    }
+
+    /// Sets the speed override, in percent.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_SetOverride")))]
+    pub async fn set_override(&self, value: u8) -> Result<(), FrcError> {
+
+        let packet = Command::FrcSetOverride(FrcSetOverride::new(value));
+        
+        let packet = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(_) => return Err(FrcError::Serialization("SetOverride packet didnt serialize correctly".to_string())),
+        };
+
+        let CommandResponse::FrcSetOverride(_) = self.send_command(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
+        self.settings.lock().await.override_value = Some(value);
         Ok(())
     }
 
+    /// Frame, tool and override values last applied through this driver.
+    pub(crate) async fn session_settings(&self) -> SessionSettings {
+        self.settings.lock().await.clone()
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, command = "FRC_Disconnect")))]
     pub async fn disconnect(&self) -> Result<(), FrcError> {
 
         let packet = Communication::FrcDisconnect {};
@@ -220,22 +520,15 @@ impl FanucDriver {
             Err(_) => return Err(FrcError::Serialization("Disconnect packet didnt serialize correctly".to_string())),
         };
 
-        self.send_packet(packet.clone()).await?;
-        let response = self.recieve::<CommunicationResponse>().await?;        
-        if let CommunicationResponse::FrcDisconnect(ref res) = response {
-            if res.error_id != 0 {
-                self.log_message(format!("Error ID: {}", res.error_id)).await;
-                let error_code = FanucErrorCode::try_from(res.error_id).unwrap_or(FanucErrorCode::UnrecognizedFrcError);
-                return Err(FrcError::FanucErrorCode(error_code));             }
-        }
+        let CommunicationResponse::FrcDisconnect(_) = self.send_communication(packet).await? else {
+            return Err(FrcError::UnrecognizedPacket);
+        };
 
         Ok(())
 
     }
 
-    async fn send_packet(&self, packet: String) -> Result<(), FrcError> {      
-            let mut stream = self.write_half.lock().await;
-
+    async fn write_locked(&self, stream: &mut WriteHalf<TcpStream>, packet: String) -> Result<(), FrcError> {
             if let Err(e) = stream.write_all(packet.as_bytes()).await {
                 let err = FrcError::FailedToSend(format!("{}",e));
                 self.log_message(err.to_string()).await;
@@ -245,268 +538,355 @@ impl FanucDriver {
             Ok(())
     }
 
-    async fn recieve<T>(&self) -> Result<T, FrcError>
-        where
-            T: for<'a> Deserialize<'a> + std::fmt::Debug,
-        {
-            
-            let mut buffer = vec![0; 2048];
-            let mut stream = self.read_half.lock().await;
-
-            let n: usize = match stream.read(&mut buffer).await{
-                Ok(n)=> n,
-                Err(e) => {
-                    let err = FrcError::FailedToRecieve(format!("{}",e));
-                    self.log_message(err.to_string()).await;
-                    return Err(err);
-                }
-            };
-
-
-            if n == 0 {
-                let e = FrcError::Disconnected();
+    /// Logs and returns the error for a non-zero `ErrorID`, which `Unknown`
+    /// replies may carry as well.
+    async fn check_error_id<T: Response>(&self, response: T) -> Result<T, FrcError> {
+        match error_of(&response) {
+            Ok(()) => Ok(response),
+            Err(e) => {
                 self.log_message(e.to_string()).await;
-                return Err(e);
+                Err(e)
             }
+        }
+    }
 
-            let response = String::from_utf8_lossy(&buffer[..n]);
+    /// Sends a command packet and waits for the reader task to hand back its
+    /// reply, failing on a non-zero `ErrorID`.
+    pub(super) async fn send_command(&self, packet: String) -> Result<CommandResponse, FrcError> {
+        let start = Instant::now();
+        let (tx, rx) = oneshot::channel();
+        {
+            // Hold the writer while queueing so replies line up with send order.
+            let mut stream = self.write_half.lock().await;
+            {
+                let mut pending = self.pending.lock().await;
+                pending.check_open()?;
+                pending.commands.push_back(tx);
+            }
+            if let Err(e) = self.write_locked(&mut stream, packet).await {
+                self.pending.lock().await.commands.pop_back();
+                return Err(e);
+            }
+        }
+        let response = rx.await.unwrap_or(Err(FrcError::Disconnected()))?;
+        self.log_latency(start);
+        self.check_error_id(response).await
+    }
 
-            self.log_message(format!("Received: {}", &response)).await;
+    /// Sends a communication packet and waits for the reader task to hand back
+    /// its reply, failing on a non-zero `ErrorID`.
+    pub(super) async fn send_communication(&self, packet: String) -> Result<CommunicationResponse, FrcError> {
+        let start = Instant::now();
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut stream = self.write_half.lock().await;
+            {
+                let mut pending = self.pending.lock().await;
+                pending.check_open()?;
+                pending.communications.push_back(tx);
+            }
+            if let Err(e) = self.write_locked(&mut stream, packet).await {
+                self.pending.lock().await.communications.pop_back();
+                return Err(e);
+            }
+        }
+        let response = rx.await.unwrap_or(Err(FrcError::Disconnected()))?;
+        self.log_latency(start);
+        self.check_error_id(response).await
+    }
 
-            // Parse JSON response
-            match serde_json::from_str::<T>(&response) {
-                Ok(response_packet) => Ok(response_packet),
-                Err(e) => {
-                    let e = FrcError::Serialization(format!("Could not parse response: {}", e));
-                    self.log_message(e.to_string()).await;
-                    return Err(e);
-                }
+    /// Sends an instruction packet and returns a future that resolves once the
+    /// controller reports the instruction with `sequence_id` as done.
+    ///
+    /// A sequence ID that is already in flight is refused before anything is
+    /// written, as the controller would answer it with `InvalidSequenceIDNumber`
+    /// and its response could not be told apart from the first one's.
+    pub(super) async fn send_instruction(
+        &self,
+        sequence_id: u32,
+        packet: String,
+    ) -> Result<oneshot::Receiver<Result<InstructionResponse, FrcError>>, FrcError> {
+        let (tx, rx) = oneshot::channel();
+        let mut stream = self.write_half.lock().await;
+        {
+            let mut pending = self.pending.lock().await;
+            pending.check_open()?;
+            if pending.instructions.contains_key(&sequence_id) {
+                return Err(FrcError::DuplicateSequenceId(sequence_id));
             }
+            pending.instructions.insert(sequence_id, tx);
+        }
+        if let Err(e) = self.write_locked(&mut stream, packet).await {
+            self.pending.lock().await.instructions.remove(&sequence_id);
+            return Err(e);
         }
+        Ok(rx)
+    }
+
+    /// Reports the round trip through `tracing` or the `logging` feature only,
+    /// so latencies do not push real entries out of `messages`.
+    #[cfg_attr(not(any(feature = "tracing", feature = "logging")), allow(unused_variables))]
+    fn log_latency(&self, start: Instant) {
+        let latency = start.elapsed();
+
+        #[cfg(feature="tracing")]
+        tracing::debug!(connection_id = self.connection_id, latency_ms = latency.as_secs_f64() * 1000.0, "round trip");
+        #[cfg(feature="logging")]
+        println!("Round trip took {:?}", latency);
+    }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id, instruction = "FRC_LinearRelative", sequence_id = sequenceid)))]
     pub async fn linear_relative(
         &self,
         sequenceid: u32,    
         config: Configuration,
         pos: Position,
-        speed_t: SpeedType,
-        speed: u16,
-        term_t: TermType,
-        term_va: u8,
+        speed: Speed,
+        termination: Termination,
 
     ) -> Result<(), FrcError> {
-        let packet = Instruction::FrcLinearRelative(FrcLinearRelative::new(
+        self.run_instruction(FrcLinearRelative::new(
             sequenceid,    
             config,
             pos,
-            speed_t,
             speed,
-            term_t,
-            term_va,
+            termination,
 
-        ));
-        
-        let packet = match serde_json::to_string(&packet) {
+        )).await
+    }
+
+    /// Sends one instruction and waits until the controller has executed it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id)))]
+    pub async fn run_instruction(&self, instruction: impl Into<Instruction>) -> Result<(), FrcError> {
+        let instruction = instruction.into();
+        instruction.validate()?;
+        let sequence_id = instruction.get_sequence_id();
+
+        let packet = match serde_json::to_string(&instruction) {
             Ok(serialized_packet) => serialized_packet + "\r\n",
-            Err(_) => return Err(FrcError::Serialization("linear motion packet didnt serialize correctly".to_string())),
+            Err(_) => return Err(FrcError::Serialization("instruction packet didnt serialize correctly".to_string())),
         };
 
-        self.send_packet(packet.clone()).await?;
-        let response = self.recieve::<InstructionResponse>().await?;
-        if let InstructionResponse::FrcLinearRelative(ref res) = response {
-            if res.error_id != 0 {
-                self.log_message(format!("Error ID: {}", res.error_id)).await;
-                let error_code = FanucErrorCode::try_from(res.error_id).unwrap_or(FanucErrorCode::UnrecognizedFrcError);
-                return Err(FrcError::FanucErrorCode(error_code)); 
-            }
+        let start = Instant::now();
+        let response = self.send_instruction(sequence_id, packet).await?;
+        let response = response.await.unwrap_or(Err(FrcError::Disconnected()))?;
+        self.log_latency(start);
+        if response.get_error_id() != 0 {
+            self.log_message(format!("Error ID: {}", response.get_error_id())).await;
+            return Err(FrcError::from_error_id(response.get_error_id(), response.get_name(), Some(response.get_sequence_id())));
         }
         Ok(())
+    }
 
+    /// Reads a G-code file into a program queue; see [`GcodeLoader`] for the supported subset.
+    pub fn load_gcode(&self, path: impl AsRef<Path>, mut loader: GcodeLoader) -> Result<VecDeque<PacketEnum>, FrcError> {
+        loader.load_file(path)
     }
 
-    pub fn load_gcode(&self) -> Result<VecDeque<PacketEnum>, FrcError> {
-        let mut queue: VecDeque<PacketEnum> = VecDeque::new();
-        queue.push_back(PacketEnum::Instruction(Instruction::FrcLinearRelative(FrcLinearRelative::new(
-            1,    
-                Configuration {
-                    u_tool_number: 1, u_frame_number: 1, front: 1, up: 1, left: 1, glip: 1, turn4: 1, turn5: 1, turn6: 1,
-                },
-                Position { x: 0.0, y: 0.0, z: 100.0, w: 0.0, p: 0.0, r: 0.0, ext1: 0.0, ext2: 0.0, ext3: 0.0,
-                },
-                SpeedType::MMSec,
-                30,
-                TermType::FINE,
-                1,
-        ))));
-        queue.push_back(PacketEnum::Instruction(Instruction::FrcLinearRelative(FrcLinearRelative::new(
-            2,    
-            Configuration {
-                u_tool_number: 1, u_frame_number: 1, front: 1, up: 1, left: 1, glip: 1, turn4: 1, turn5: 1, turn6: 1,
-            },
-            Position { x: 30.0, y: 100.0, z: 0.0, w: 0.0, p: 0.0, r: 0.0, ext1: 0.0, ext2: 0.0, ext3: 0.0,
-            },
-            SpeedType::MMSec,
-            30,
-            TermType::FINE,
-            1,
-        ))));
-        queue.push_back(PacketEnum::Instruction(Instruction::FrcLinearRelative(FrcLinearRelative::new(
-                3,    
-                Configuration { u_tool_number: 1, u_frame_number: 1, front: 1, up: 1, left: 1, glip: 1, turn4: 1, turn5: 1, turn6: 1,
-                },
-                Position { x: 0.0, y: 0.0, z: -100.0, w: 0.0, p: 0.0, r: 0.0, ext1: 0.0, ext2: 0.0, ext3: 0.0,
-                },
-                SpeedType::MMSec,
-                30,
-                TermType::FINE,
-                1,
-        ))));
-        queue.push_back(PacketEnum::Instruction(Instruction::FrcLinearRelative(FrcLinearRelative::new(
-                4,    
-                Configuration { u_tool_number: 1, u_frame_number: 1, front: 1, up: 1, left: 1, glip: 1, turn4: 1, turn5: 1, turn6: 1,
-                },
-                Position { x: -30.0, y: -100.0, z: 0.0, w: 0.0, p: 0.0, r: 0.0, ext1: 0.0, ext2: 0.0, ext3: 0.0,
-                },
-                SpeedType::MMSec,
-                30,
-                TermType::FINE,
-                1,
-        ))));
-        Ok(queue)
-    }
-
-    pub async fn start_program(&self) -> Result<(), FrcError> {
-
-        let mut queue = self.load_gcode()?; // Handle synchronous load_gcode
-        let (tx, rx) = mpsc::channel(100); // Create a channel with a buffer size of 100
-
-        //spins up 2 async concurent functions
-        let (res1, res2) = tokio::join!(
-            self.send_queue(&mut queue, tx),
-            self.read_queue_responses(rx)
-        );
-        
-        match res1 {
-            Ok(_) => self.log_message("send_queue completed successfully").await,
-            Err(e) => self.log_message(format!("send_queue failed: {}", e)).await,
-        }
+    /// Reads a program file into a program queue numbered from 1; see [`ProgramFile`] for the format.
+    pub fn load_program(&self, path: impl AsRef<Path>) -> Result<VecDeque<PacketEnum>, FrcError> {
+        ProgramFile::load(path)?.to_queue(1)
+    }
 
-        match res2 {
-            Ok(_) => self.log_message("read_queue_responses completed successfully").await,
-            Err(e) => self.log_message(format!("read_queue_responses failed: {}", e)).await,
+    /// Runs a program queue to the end, returning the first error; see
+    /// [`run_program`](Self::run_program) for progress and pause, resume or cancel.
+    pub async fn start_program(&self, queue: VecDeque<PacketEnum>) -> Result<(), FrcError> {
+        let summary = self.run_program(queue, &ProgramControl::new()).await;
+        self.log_message(format!("Program finished, {} instructions completed", summary.completed.len())).await;
+        match summary.errors.into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(()),
         }
-
-        Ok(())
     }
+}
 
-    async fn send_queue(&self, queue: &mut VecDeque<PacketEnum>, tx: mpsc::Sender<u32>)-> Result<(), FrcError>{
-        while !queue.is_empty() {
-            let packet = queue.pop_front();
-            
-            //all this match statement does is extract the sequence id from the packet about to be sent, it may not be neccesary later
-            let sequence_id = match &packet.as_ref().unwrap() {
-                PacketEnum::Instruction(instruction) => {
-                    match instruction {
-                        Instruction::FrcLinearRelative(packet) => packet.sequence_id,
-                        // Handle other instruction types similarly if needed
-                        _ => 0, // Use a default value if sequence_id is not applicable
-                    }
-                },
-                _ => 0, // Use a default value for non-instruction packets
-            };
 
-            tx.send(sequence_id).await.expect("Failed to send message");
+/// The part of the driver the reader task needs. It holds no write half, so
+/// the socket closes once every [`FanucDriver`] handle is dropped.
+struct ResponseReader {
+    connection_id: u32,
+    max_messages: usize,
+    parse_mode: ParseMode,
+    messages: Arc<Mutex<VecDeque<String>>>,
+    pending: Arc<Mutex<PendingResponses>>,
+    events: broadcast::Sender<DriverEvent>,
+}
+
+impl ResponseReader {
+    async fn log_message<T: Into<String>>(&self, message: T) {
+        record_message(&self.messages, self.max_messages, self.connection_id, message.into()).await;
+    }
+
+    /// Owns the read half for the lifetime of the connection, splitting the
+    /// stream into lines and routing each packet to whoever is waiting on it.
+    async fn run(self, mut reader: ReadHalf<TcpStream>, mut shutdown: oneshot::Receiver<()>) {
+        let mut buffer = vec![0; 2048];
+        let mut temp_buffer = Vec::new();
 
-            let packet = match serde_json::to_string(&packet) {
-                Ok(serialized_packet) => serialized_packet + "\r\n",
+        loop {
+            let read = tokio::select! {
+                read = reader.read(&mut buffer) => read,
+                // Every driver handle is gone; dropping the read half closes the socket.
+                _ = &mut shutdown => break,
+            };
+            let n = match read {
+                Ok(0) => break, // Connection closed
+                Ok(n) => n,
                 Err(e) => {
-                    self.log_message(format!("Failed to serialize a packet: {}", e)).await;
+                    self.log_message(format!("Failed to read from stream: {}", e)).await;
                     break;
                 }
             };
-            self.send_packet(packet).await?;
-        
-            // sleep(Duration::from_millis(1)).await;
-            
-        }
-        self.log_message("Sent all packets").await;
 
-        //when 0 is sent it shuts  off the recciever system so we wait one sec so that the response can be sent back and processed
-        sleep(Duration::from_secs(1)).await;
+            // Append new data to temp_buffer
+            temp_buffer.extend_from_slice(&buffer[..n]);
 
-        tx.send(0).await.expect("Failed to send end message");
+            while let Some(pos) = temp_buffer.iter().position(|&x| x == b'\n') {
+                // Split the buffer into the current message and the rest
+                let line: Vec<u8> = temp_buffer.drain(..=pos).collect();
+                let response_str = String::from_utf8_lossy(&line);
+                let response_str = response_str.trim();
+                if response_str.is_empty() {
+                    continue;
+                }
+                self.log_message(format!("Received: {}", response_str)).await;
+                self.route_response(response_str).await;
+            }
+        }
 
-        Ok(())
+        self.log_message(FrcError::Disconnected().to_string()).await;
+        let mut pending = self.pending.lock().await;
+        pending.closed = true;
+        pending.fail_all(FrcError::Disconnected);
+        drop(pending);
+        let _ = self.events.send(DriverEvent::Disconnected);
     }
-    
-
-    async fn read_queue_responses(&self, mut rx: mpsc::Receiver<u32>) -> Result<(), FrcError> {
-        
-        let mut reader = self.read_half.lock().await;
 
-        let mut numbers_to_look_for: VecDeque<u32> = VecDeque::new();
-        let mut buffer = vec![0; 2048];
-        let mut temp_buffer = Vec::new();
+    async fn route_response(&self, response_str: &str) {
+        let value: serde_json::Value = match serde_json::from_str(response_str) {
+            Ok(value) => value,
+            Err(e) => {
+                self.log_message(format!("Could not parse response: {}", e)).await;
+                return;
+            }
+        };
 
-        loop {
-            tokio::select! {
-                result = reader.read(&mut buffer) => {
-                    match result {
-                        Ok(0) => break, // Connection closed
-                        Ok(n) => {
-                            // Append new data to temp_buffer
-                            temp_buffer.extend_from_slice(&buffer[..n]);
-
-                            while let Some(pos) = temp_buffer.iter().position(|&x| x == b'\n') {
-                                // Split the buffer into the current message and the rest
-                                let request: Vec<u8> = temp_buffer.drain(..=pos).collect();
-                                // Remove the newline character
-                                let request = &request[..request.len() - 1];
-
-                                let response_str = String::from_utf8_lossy(request);
-                                self.log_message(response_str.clone()).await;
-
-                                let response_packet: Option<InstructionResponse> = match serde_json::from_str::<InstructionResponse>(&response_str) {
-                                    Ok(response_packet) => Some(response_packet),
-                                    Err(e) => {
-                                        self.log_message(format!("Could not parse response: {}", e)).await;
-                                        None
-                                    }
-                                };
-
-                                if let Some(response_packet) = response_packet {
-                                    let sequence_id = response_packet.get_sequence_id();
-                                    self.log_message(format!("Found matching id: {}", sequence_id)).await;
-                                    numbers_to_look_for.retain(|&x| x != sequence_id);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            self.log_message(format!("Failed to read from stream: {}", e)).await;
-                        }
-                    }
-                },
-                Some(message) = rx.recv() => {
-                    if message == 0 {
-                        break;
-                    } else {
-                        numbers_to_look_for.push_back(message);
+        let mode = self.parse_mode;
+        if value.get("Communication").is_some() {
+            match self.parse_response::<CommunicationResponse>(value, mode).await {
+                Ok(CommunicationResponse::FrcSystemFault(fault)) => self.handle_system_fault(fault.sequence_id).await,
+                Ok(CommunicationResponse::FrcTerminate(_)) => self.handle_terminate().await,
+                response => {
+                    if let Some(reply) = self.pending.lock().await.communications.pop_front() {
+                        let _ = reply.send(response);
                     }
                 }
             }
+        } else if value.get("Command").is_some() {
+            let response = self.parse_response::<CommandResponse>(value, mode).await;
+            if let Some(reply) = self.pending.lock().await.commands.pop_front() {
+                let _ = reply.send(response);
+            }
+        } else if value.get("Instruction").is_some() {
+            let sequence_id = value.get("SequenceID").and_then(|id| id.as_u64()).map(|id| id as u32);
+            let response = self.parse_response::<InstructionResponse>(value, mode).await;
+            let reply = match sequence_id {
+                Some(sequence_id) => self.pending.lock().await.instructions.remove(&sequence_id),
+                None => None,
+            };
+            match reply {
+                Some(reply) => { let _ = reply.send(response); }
+                None => self.log_message(format!("No pending instruction for sequence id {:?}", sequence_id)).await,
+            }
+        } else {
+            self.log_message(format!("Unrecognized packet: {}", response_str)).await;
         }
-        
-        Ok(())
     }
 
+    /// Decodes a response, logging failures and announcing `Unknown` packets.
+    async fn parse_response<T: Response>(&self, value: serde_json::Value, mode: ParseMode) -> Result<T, FrcError> {
+        let response = decode_reply::<T>(value, mode);
+        match response {
+            Ok(ref packet) => {
+                if let Some((name, raw)) = packet.as_unknown() {
+                    self.log_message(format!("Unknown packet {}: {}", name, raw)).await;
+                    let _ = self.events.send(DriverEvent::UnknownPacket { name: name.to_string(), raw: raw.clone() });
+                }
+            }
+            Err(ref e) => self.log_message(e.to_string()).await,
+        }
+        response
+    }
+
+    async fn handle_system_fault(&self, sequence_id: u32) {
+        #[cfg(feature="tracing")]
+        tracing::error!(connection_id = self.connection_id, sequence_id, "FRC_SystemFault");
+        self.log_message(format!("System fault at sequence id {}", sequence_id)).await;
+
+        self.pending.lock().await.fail_instructions(|| FrcError::SystemFault(sequence_id));
+        let _ = self.events.send(DriverEvent::SystemFault { sequence_id });
+    }
+
+    async fn handle_terminate(&self) {
+        #[cfg(feature="tracing")]
+        tracing::warn!(connection_id = self.connection_id, "FRC_Terminate");
+        self.log_message("Controller terminated the connection").await;
+
+        self.pending.lock().await.fail_all(|| FrcError::Terminated);
+        let _ = self.events.send(DriverEvent::Terminated);
+    }
+}
+
+/// Decodes a reply, reporting the controller's `ErrorID` rather than the
+/// decode failure when an error reply leaves out the packet's data fields.
+fn decode_reply<T: Response>(value: serde_json::Value, mode: ParseMode) -> Result<T, FrcError> {
+    let error_id = value.get("ErrorID").and_then(|id| id.as_u64()).unwrap_or(0) as u32;
+    if error_id == 0 {
+        return T::parse(value, mode);
+    }
+    let name = value.get(T::TAG).and_then(|name| name.as_str()).unwrap_or_default().to_string();
+    let sequence_id = value.get("SequenceID").and_then(|id| id.as_u64()).map(|id| id as u32);
+    T::parse(value, mode).map_err(|_| FrcError::from_error_id(error_id, &name, sequence_id))
+}
+
+/// Turns a non-zero `ErrorID` in a command or communication response into an error.
+fn error_of<T: Response>(response: &T) -> Result<(), FrcError> {
+    let value = match response.as_unknown() {
+        Some((_, raw)) => raw.clone(),
+        None => serde_json::to_value(response).map_err(|e| FrcError::Serialization(e.to_string()))?,
+    };
+    match value.get("ErrorID").and_then(|id| id.as_u64()).unwrap_or(0) {
+        0 => Ok(()),
+        error_id => {
+            let name = value.get(T::TAG).and_then(|name| name.as_str()).unwrap_or_default();
+            Err(FrcError::from_error_id(error_id as u32, name, None))
+        }
+    }
 }
 
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+async fn record_message(messages: &Mutex<VecDeque<String>>, max_messages: usize, connection_id: u32, message: String) {
+    let mut messages = messages.lock().await;
+
+    #[cfg(feature="logging")]
+    println!("{}", &message);
+    #[cfg(feature="tracing")]
+    tracing::debug!(connection_id, "{}", &message);
+
+    while messages.len() >= max_messages {
+        messages.pop_front();
+    }
+    messages.push_back(message);
+}
 
 async fn connect_with_retries(addr: &str, retries: u32) -> Result<TcpStream, FrcError> {
     for attempt in 0..retries {
         match TcpStream::connect(addr).await {
             Ok(stream) => return Ok(stream),
             Err(e) => {
+                #[cfg(feature="tracing")]
+                tracing::warn!(addr, attempt = attempt + 1, error = %e, "failed to connect");
+                #[cfg(not(feature="tracing"))]
                 eprintln!("Failed to connect (attempt {}): {}", attempt + 1, e);
                 if attempt + 1 == retries {
                     return Err(FrcError::Disconnected());
@@ -515,5 +895,5 @@ async fn connect_with_retries(addr: &str, retries: u32) -> Result<TcpStream, Frc
             }
         }
     }
-    return Err(FrcError::Disconnected())
-}
\ No newline at end of file
+    Err(FrcError::Disconnected())
+}
@@ -1,2 +1,6 @@
 mod driver;
+mod recovery;
+mod run;
 pub use driver::*;
+pub use recovery::*;
+pub use run::*;
@@ -0,0 +1,168 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{FanucErrorClass, FanucErrorCode, FrcError, GroupMask};
+use super::FanucDriver;
+
+/// A single corrective action the driver can take after an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryStep {
+    /// `FRC_ReadError`, captured in [`RecoveryReport::alarm`].
+    ReadError,
+    /// `FRC_Reset`
+    Reset,
+    /// `FRC_Abort`
+    Abort,
+    /// `FRC_Initialize`
+    Initialize,
+    /// Re-send the last `FRC_SetUFrameUTool` made through this driver for each group.
+    RestoreFrames,
+    /// Re-send the last `FRC_SetOverride` made through this driver.
+    RestoreOverride,
+}
+
+impl RecoveryStep {
+    /// Whether the step changes controller state; [`ReadError`](Self::ReadError) only looks.
+    pub fn changes_state(&self) -> bool {
+        !matches!(self, RecoveryStep::ReadError)
+    }
+}
+
+/// Which [`RecoveryStep`]s to run for a given error.
+///
+/// Steps are picked by exact error code first, then by the code's
+/// [`FanucErrorClass`]. A system fault is treated like
+/// [`FanucErrorClass::ControllerState`].
+#[derive(Debug, Clone)]
+pub struct RecoveryPolicy {
+    pub by_class: HashMap<FanucErrorClass, Vec<RecoveryStep>>,
+    pub by_code: HashMap<FanucErrorCode, Vec<RecoveryStep>>,
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        use RecoveryStep::*;
+
+        let mut by_class = HashMap::new();
+        by_class.insert(FanucErrorClass::InvalidRequest, vec![ReadError]);
+        by_class.insert(FanucErrorClass::Busy, vec![]);
+        by_class.insert(
+            FanucErrorClass::ControllerState,
+            vec![ReadError, Reset, Abort, Initialize, RestoreFrames, RestoreOverride],
+        );
+        by_class.insert(FanucErrorClass::Fatal, vec![ReadError]);
+        by_class.insert(FanucErrorClass::Unknown, vec![ReadError]);
+
+        // RMI is simply not running yet, so there is nothing to reset.
+        let mut by_code = HashMap::new();
+        by_code.insert(FanucErrorCode::RMINotRunning, vec![Initialize, RestoreFrames, RestoreOverride]);
+
+        Self { by_class, by_code }
+    }
+}
+
+impl RecoveryPolicy {
+    /// Overrides the steps run for one specific error code.
+    pub fn with_steps(mut self, code: FanucErrorCode, steps: Vec<RecoveryStep>) -> Self {
+        self.by_code.insert(code, steps);
+        self
+    }
+
+    pub fn steps_for(&self, error: &FrcError) -> &[RecoveryStep] {
+        let class = match error {
+            FrcError::FanucErrorCode { code, .. } => {
+                if let Some(steps) = self.by_code.get(code) {
+                    return steps;
+                }
+                code.class()
+            }
+            FrcError::SystemFault(_) => FanucErrorClass::ControllerState,
+            _ => return &[],
+        };
+        self.by_class.get(&class).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+#[derive(Debug)]
+pub struct RecoveryAction {
+    pub step: RecoveryStep,
+    pub result: Result<(), FrcError>,
+}
+
+/// What [`FanucDriver::recover`] did and whether the controller is usable again.
+#[derive(Debug)]
+pub struct RecoveryReport {
+    pub code: Option<FanucErrorCode>,
+    /// Steps in the order they ran; recovery stops at the first failed step.
+    pub actions: Vec<RecoveryAction>,
+    /// `ErrorData` from `FRC_ReadError`, if that step ran.
+    pub alarm: Option<String>,
+    /// Whether the failed packet can be sent again: every step succeeded and at
+    /// least one of them changed controller state, or the error was
+    /// [`FanucErrorClass::Busy`], which only needs a retry.
+    pub recovered: bool,
+    pub requires_power_cycle: bool,
+}
+
+/// Values re-applied by [`RecoveryStep::RestoreFrames`] and [`RecoveryStep::RestoreOverride`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionSettings {
+    /// `(uframe, utool)` per group.
+    pub(crate) frames: BTreeMap<u8, (u8, u8)>,
+    /// Mask of the last `FRC_Initialize`, reused when recovery initializes again.
+    pub(crate) group_mask: Option<GroupMask>,
+    pub(crate) override_value: Option<u8>,
+}
+
+impl FanucDriver {
+    /// Runs the steps `policy` prescribes for `error` and reports what happened.
+    ///
+    /// Errors that need a power cycle are never reported as recovered, and
+    /// neither are errors whose steps only read the controller, such as a
+    /// rejected packet that has to be fixed before it is sent again.
+    pub async fn recover(&self, error: &FrcError, policy: &RecoveryPolicy) -> RecoveryReport {
+        let steps = policy.steps_for(error);
+        let mut report = RecoveryReport {
+            code: error.error_code().copied(),
+            actions: Vec::new(),
+            alarm: None,
+            recovered: false,
+            requires_power_cycle: error.requires_power_cycle(),
+        };
+
+        for &step in steps {
+            let result = self.run_recovery_step(step, &mut report).await;
+            let failed = result.is_err();
+            report.actions.push(RecoveryAction { step, result });
+            if failed {
+                return report;
+            }
+        }
+
+        let busy = report.code.map(|code| code.class()) == Some(FanucErrorClass::Busy);
+        let changed_state = steps.iter().any(RecoveryStep::changes_state);
+        report.recovered = !report.requires_power_cycle && (busy || changed_state);
+        report
+    }
+
+    async fn run_recovery_step(&self, step: RecoveryStep, report: &mut RecoveryReport) -> Result<(), FrcError> {
+        match step {
+            RecoveryStep::ReadError => {
+                report.alarm = Some(self.read_error().await?);
+                Ok(())
+            }
+            RecoveryStep::Reset => self.reset().await,
+            RecoveryStep::Abort => self.abort().await,
+            RecoveryStep::Initialize => self.initialize().await,
+            RecoveryStep::RestoreFrames => {
+                for (group, (uframe, utool)) in self.session_settings().await.frames {
+                    self.set_group_uframe_utool(group, uframe, utool).await?;
+                }
+                Ok(())
+            }
+            RecoveryStep::RestoreOverride => match self.session_settings().await.override_value {
+                Some(value) => self.set_override(value).await,
+                None => Ok(()),
+            },
+        }
+    }
+}
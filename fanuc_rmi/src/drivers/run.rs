@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::pin::{pin, Pin};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use futures_core::Stream;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+
+use crate::packets::InstructionResponse;
+use crate::{FrcError, PacketEnum};
+
+use super::FanucDriver;
+
+/// Instructions sent ahead of the one the controller is running; the
+/// controller buffers no more than this.
+pub const MAX_IN_FLIGHT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Request {
+    Run,
+    Pause,
+    Cancel,
+}
+
+/// Pauses, resumes or cancels a [`FanucDriver::run_program`] or
+/// [`FanucDriver::run_stream`] from another
+/// task and reports its progress.
+///
+/// Clones share the same run. Once cancelled, a control cancels every run it
+/// is passed to, so use a new one per run.
+#[derive(Debug, Clone)]
+pub struct ProgramControl {
+    request: Arc<watch::Sender<Request>>,
+    progress: broadcast::Sender<ProgramProgress>,
+}
+
+impl Default for ProgramControl {
+    fn default() -> Self {
+        let (request, _) = watch::channel(Request::Run);
+        let (progress, _) = broadcast::channel(64);
+        Self { request: Arc::new(request), progress }
+    }
+}
+
+impl ProgramControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Holds motion with `FRC_Pause` and stops sending further packets.
+    pub fn pause(&self) {
+        self.request.send_if_modified(|request| {
+            let running = *request == Request::Run;
+            if running {
+                *request = Request::Pause;
+            }
+            running
+        });
+    }
+
+    /// Sends `FRC_Continue` after [`pause`](Self::pause).
+    pub fn resume(&self) {
+        self.request.send_if_modified(|request| {
+            let paused = *request == Request::Pause;
+            if paused {
+                *request = Request::Run;
+            }
+            paused
+        });
+    }
+
+    /// Stops the run with `FRC_Abort`; it cannot be resumed.
+    pub fn cancel(&self) {
+        self.request.send_replace(Request::Cancel);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.request.borrow() == Request::Cancel
+    }
+
+    /// Progress of the runs using this control.
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgramProgress> {
+        self.progress.subscribe()
+    }
+
+    fn report(&self, progress: ProgramProgress) {
+        let _ = self.progress.send(progress);
+    }
+}
+
+/// What a running program reports through [`ProgramControl::subscribe`].
+/// `step` is the index of the packet in the program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgramProgress {
+    Sent { step: usize, sequence_id: Option<u32> },
+    /// The controller finished an instruction, or answered a command.
+    Completed { step: usize, sequence_id: Option<u32> },
+    Paused,
+    Resumed,
+    Cancelled,
+    /// The run stopped at this packet.
+    Failed { step: usize, message: String },
+}
+
+/// Outcome of [`FanucDriver::run_program`].
+#[derive(Debug, Default)]
+pub struct ProgramSummary {
+    /// Packets sent to the controller.
+    pub sent: usize,
+    /// Sequence IDs of the instructions the controller finished, in order.
+    pub completed: Vec<u32>,
+    /// Errors with the step they belong to; `None` for `FRC_Pause`,
+    /// `FRC_Continue` and `FRC_Abort`.
+    pub errors: Vec<(Option<usize>, FrcError)>,
+    pub cancelled: bool,
+    /// Whether `FRC_Abort` was sent to stop queued motion, on cancel or after
+    /// an error. A failed abort is listed in `errors`.
+    pub aborted: bool,
+}
+
+impl ProgramSummary {
+    /// Whether the whole program ran without errors.
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty() && !self.cancelled
+    }
+}
+
+/// Packets sent into the [`ProgramStream`] returned with it, to be run by
+/// [`FanucDriver::run_stream`]. Sending waits while `buffer` packets are
+/// already waiting for the controller; dropping every sender ends the program.
+pub fn program_channel(buffer: usize) -> (mpsc::Sender<PacketEnum>, ProgramStream) {
+    let (sender, receiver) = mpsc::channel(buffer);
+    (sender, ProgramStream { receiver })
+}
+
+/// The receiving half of [`program_channel`].
+#[derive(Debug)]
+pub struct ProgramStream {
+    receiver: mpsc::Receiver<PacketEnum>,
+}
+
+impl Stream for ProgramStream {
+    type Item = PacketEnum;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PacketEnum>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Presents an iterator as a [`Stream`] that is always ready.
+struct Iter<I>(I);
+
+impl<I: Iterator + Unpin> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.0.next())
+    }
+}
+
+/// An instruction sent but not yet finished.
+struct InFlight {
+    step: usize,
+    sequence_id: u32,
+    sent_at: Instant,
+    response: oneshot::Receiver<Result<InstructionResponse, FrcError>>,
+}
+
+impl FanucDriver {
+    /// Runs `program` to its end, the first error, or until `control` cancels it.
+    /// An error or a cancel aborts whatever motion is still queued.
+    ///
+    /// Up to [`MAX_IN_FLIGHT`] instructions are kept queued on the controller
+    /// so motions can blend. Commands and communications wait until every
+    /// instruction before them has finished, so they take effect at their place
+    /// in the program.
+    ///
+    /// ```no_run
+    /// # async fn run(driver: fanuc_rmi::drivers::FanucDriver, queue: std::collections::VecDeque<fanuc_rmi::PacketEnum>) {
+    /// use fanuc_rmi::drivers::ProgramControl;
+    ///
+    /// let control = ProgramControl::new();
+    /// let stop = control.clone();
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    ///     stop.cancel();
+    /// });
+    /// let summary = driver.run_program(queue, &control).await;
+    /// println!("{} instructions done, cancelled: {}", summary.completed.len(), summary.cancelled);
+    /// # }
+    /// ```
+    pub async fn run_program<I>(&self, program: I, control: &ProgramControl) -> ProgramSummary
+    where
+        I: IntoIterator<Item = PacketEnum>,
+        I::IntoIter: Unpin,
+    {
+        self.run_stream(Iter(program.into_iter()), control).await
+    }
+
+    /// Like [`run_program`](Self::run_program), but pulls packets from `program`
+    /// as they are needed, so they can be generated while the robot moves.
+    ///
+    /// The next packet is only requested once there is room for it on the
+    /// controller, which holds back a producer feeding [`program_channel`].
+    /// The run ends when the stream does.
+    ///
+    /// ```no_run
+    /// # async fn run(driver: fanuc_rmi::drivers::FanucDriver) {
+    /// use fanuc_rmi::drivers::{program_channel, ProgramControl};
+    /// use fanuc_rmi::instructions::Move;
+    /// use fanuc_rmi::{PacketEnum, Position};
+    ///
+    /// let (sender, program) = program_channel(16);
+    /// tokio::spawn(async move {
+    ///     for sequence_id in 1..=1000 {
+    ///         let step = Position::new(0.5, 0.0, 0.0, 0.0, 0.0, 0.0);
+    ///         let motion = Move::linear().by(step).speed_mm_s(50).cnt(100).build(sequence_id).unwrap();
+    ///         // Waits while the controller is behind.
+    ///         if sender.send(PacketEnum::Instruction(motion.into())).await.is_err() {
+    ///             break;
+    ///         }
+    ///     }
+    /// });
+    /// let summary = driver.run_stream(program, &ProgramControl::new()).await;
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(connection_id = self.connection_id)))]
+    pub async fn run_stream<S>(&self, program: S, control: &ProgramControl) -> ProgramSummary
+    where
+        S: Stream<Item = PacketEnum>,
+    {
+        let mut program = pin!(program);
+        let mut steps = 0..;
+        let mut next: Option<(usize, PacketEnum)> = None;
+        let mut exhausted = false;
+        let mut requests = control.request.subscribe();
+        let mut in_flight: VecDeque<InFlight> = VecDeque::new();
+        let mut summary = ProgramSummary::default();
+        let mut paused = false;
+
+        loop {
+            let request = *requests.borrow_and_update();
+            match request {
+                Request::Cancel => {
+                    self.abort_run(&mut summary).await;
+                    summary.cancelled = true;
+                    control.report(ProgramProgress::Cancelled);
+                    break;
+                }
+                Request::Pause if !paused => {
+                    if let Err(e) = self.pause().await {
+                        summary.errors.push((None, e));
+                    }
+                    paused = true;
+                    control.report(ProgramProgress::Paused);
+                }
+                Request::Run if paused => {
+                    if let Err(e) = self.resume().await {
+                        summary.errors.push((None, e));
+                    }
+                    paused = false;
+                    control.report(ProgramProgress::Resumed);
+                }
+                _ => {}
+            }
+
+            if exhausted && next.is_none() && in_flight.is_empty() {
+                break;
+            }
+            let can_pull = !paused && !exhausted && next.is_none() && in_flight.len() < MAX_IN_FLIGHT;
+            let can_send = !paused
+                && match next {
+                    Some((_, PacketEnum::Instruction(_))) => in_flight.len() < MAX_IN_FLIGHT,
+                    Some(_) => in_flight.is_empty(),
+                    None => false,
+                };
+
+            tokio::select! {
+                biased;
+                _ = requests.changed() => {}
+                response = async { (&mut in_flight.front_mut().expect("branch is enabled").response).await }, if !in_flight.is_empty() => {
+                    let done = in_flight.pop_front().expect("branch is enabled");
+                    let result = response.unwrap_or(Err(FrcError::Disconnected())).and_then(|response| {
+                        match response.get_error_id() {
+                            0 => Ok(()),
+                            error_id => Err(FrcError::from_error_id(error_id, response.get_name(), Some(done.sequence_id))),
+                        }
+                    });
+                    match result {
+                        Ok(()) => {
+                            self.log_message(format!("Found matching id: {} after {:?}", done.sequence_id, done.sent_at.elapsed())).await;
+                            summary.completed.push(done.sequence_id);
+                            control.report(ProgramProgress::Completed { step: done.step, sequence_id: Some(done.sequence_id) });
+                        }
+                        Err(e) => {
+                            self.fail_run(done.step, e, &mut summary, control).await;
+                            break;
+                        }
+                    }
+                }
+                _ = std::future::ready(()), if can_send => {
+                    let (step, packet) = next.take().expect("branch is enabled");
+                    if let Err(e) = self.send_program_packet(step, packet, &mut in_flight, control).await {
+                        self.fail_run(step, e, &mut summary, control).await;
+                        break;
+                    }
+                    summary.sent += 1;
+                }
+                packet = poll_fn(|cx| program.as_mut().poll_next(cx)), if can_pull => {
+                    match packet {
+                        Some(packet) => next = Some((steps.next().expect("steps are unbounded"), packet)),
+                        None => exhausted = true,
+                    }
+                }
+            }
+        }
+
+        // Nothing will answer these once the run has stopped early.
+        let mut pending = self.pending.lock().await;
+        for done in in_flight {
+            pending.instructions.remove(&done.sequence_id);
+        }
+        summary
+    }
+
+    async fn send_program_packet(
+        &self,
+        step: usize,
+        packet: PacketEnum,
+        in_flight: &mut VecDeque<InFlight>,
+        control: &ProgramControl,
+    ) -> Result<(), FrcError> {
+        if let PacketEnum::Instruction(ref instruction) = packet {
+            instruction.validate()?;
+        }
+        let serialized = match serde_json::to_string(&packet) {
+            Ok(serialized_packet) => serialized_packet + "\r\n",
+            Err(e) => return Err(FrcError::Serialization(format!("program packet didnt serialize correctly: {}", e))),
+        };
+
+        match packet {
+            PacketEnum::Instruction(instruction) => {
+                let sequence_id = instruction.get_sequence_id();
+                let response = self.send_instruction(sequence_id, serialized).await?;
+                in_flight.push_back(InFlight { step, sequence_id, sent_at: Instant::now(), response });
+                control.report(ProgramProgress::Sent { step, sequence_id: Some(sequence_id) });
+            }
+            PacketEnum::Command(_) => {
+                control.report(ProgramProgress::Sent { step, sequence_id: None });
+                self.send_command(serialized).await?;
+                control.report(ProgramProgress::Completed { step, sequence_id: None });
+            }
+            PacketEnum::Communication(_) => {
+                control.report(ProgramProgress::Sent { step, sequence_id: None });
+                self.send_communication(serialized).await?;
+                control.report(ProgramProgress::Completed { step, sequence_id: None });
+            }
+        }
+        Ok(())
+    }
+
+    async fn fail_run(&self, step: usize, error: FrcError, summary: &mut ProgramSummary, control: &ProgramControl) {
+        self.log_message(format!("Program stopped at step {}: {}", step, error)).await;
+        control.report(ProgramProgress::Failed { step, message: error.to_string() });
+        summary.errors.push((Some(step), error));
+        self.abort_run(summary).await;
+    }
+
+    async fn abort_run(&self, summary: &mut ProgramSummary) {
+        if let Err(e) = self.abort().await {
+            summary.errors.push((None, e));
+        }
+        summary.aborted = true;
+    }
+}
+
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::FrcWriteDOUT;
+use crate::instructions::{FrcCall, FrcSetPayLoad, FrcSetUTool, FrcWaitDIN, FrcWaitTime};
+use crate::packets::{Command, OnOff};
+use crate::PacketEnum;
+
+/// What an `M` or `T` code does when a G-code program is loaded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CodeAction {
+    /// Digital output. Followed by a motion, it switches as that motion
+    /// starts, through the motion's port output, so blending carries on.
+    /// Otherwise, or with [`GcodeLoader::outputs_on_motion`](super::GcodeLoader::outputs_on_motion)
+    /// off, it is sent as `FRC_WriteDOUT`, which runs once every earlier
+    /// motion has finished: the robot stops there instead of blending through.
+    WriteDout { port: u16, value: OnOff },
+    WaitDin { port: u32, value: OnOff },
+    /// Dwell in seconds.
+    WaitTime(f32),
+    SetUTool(u8),
+    SetPayload(u8),
+    /// Runs a TP program on the controller.
+    Call(String),
+}
+
+impl CodeAction {
+    pub fn to_packet(&self) -> PacketEnum {
+        match self {
+            CodeAction::WriteDout { port, value } => PacketEnum::Command(Command::FrcWriteDOUT(FrcWriteDOUT::new(*port, *value))),
+            CodeAction::WaitDin { port, value } => PacketEnum::Instruction(FrcWaitDIN::new(0, *port, *value).into()),
+            CodeAction::WaitTime(seconds) => PacketEnum::Instruction(FrcWaitTime::new(0, *seconds).into()),
+            CodeAction::SetUTool(tool) => PacketEnum::Instruction(FrcSetUTool::new(0, *tool).into()),
+            CodeAction::SetPayload(schedule) => PacketEnum::Instruction(FrcSetPayLoad::new(0, *schedule).into()),
+            CodeAction::Call(program) => PacketEnum::Instruction(FrcCall::new(0, program.clone()).into()),
+        }
+    }
+}
+
+/// Actions for the `M` and `T` codes of a process, for [`GcodeLoader::codes`](super::GcodeLoader::codes).
+///
+/// Codes without an entry are rejected when the program is loaded, except
+/// `M2` and `M30`, which only end the program.
+///
+/// ```
+/// use fanuc_rmi::gcode::{CodeAction, CodeMap, GcodeLoader};
+/// use fanuc_rmi::packets::OnOff;
+///
+/// let codes = CodeMap::new()
+///     .mcode(3, [CodeAction::WriteDout { port: 1, value: OnOff::ON }, CodeAction::WaitTime(0.2)])
+///     .mcode(5, [CodeAction::WriteDout { port: 1, value: OnOff::OFF }])
+///     .tcode(2, [CodeAction::SetUTool(2), CodeAction::SetPayload(2)]);
+/// let queue = GcodeLoader::new()
+///     .codes(codes)
+///     .load_str("T2\nG1 X10 F600\nM3\nG1 X20\nM5\nM30")
+///     .unwrap();
+/// assert_eq!(queue.len(), 7);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CodeMap {
+    #[serde(default)]
+    pub mcodes: HashMap<u16, Vec<CodeAction>>,
+    #[serde(default)]
+    pub tcodes: HashMap<u16, Vec<CodeAction>>,
+}
+
+impl CodeMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mcode(mut self, code: u16, actions: impl IntoIterator<Item = CodeAction>) -> Self {
+        self.mcodes.insert(code, actions.into_iter().collect());
+        self
+    }
+
+    pub fn tcode(mut self, code: u16, actions: impl IntoIterator<Item = CodeAction>) -> Self {
+        self.tcodes.insert(code, actions.into_iter().collect());
+        self
+    }
+
+    /// Maps `T1` to `T10` to the UTool with the same number, keeping any
+    /// `T` codes already mapped.
+    pub fn tools_as_utools(mut self) -> Self {
+        for tool in crate::UTOOL_RANGE {
+            self.tcodes.entry(tool as u16).or_insert_with(|| vec![CodeAction::SetUTool(tool)]);
+        }
+        self
+    }
+
+    /// Packets for `M<code>`.
+    pub fn mcode_packets(&self, code: u16) -> Result<Vec<PacketEnum>, String> {
+        match self.mcodes.get(&code) {
+            Some(actions) => Ok(actions.iter().map(CodeAction::to_packet).collect()),
+            None if matches!(code, 2 | 30) => Ok(Vec::new()),
+            None => Err("no action mapped".to_string()),
+        }
+    }
+
+    /// Packets for `T<code>`.
+    pub fn tcode_packets(&self, code: u16) -> Result<Vec<PacketEnum>, String> {
+        match self.tcodes.get(&code) {
+            Some(actions) => Ok(actions.iter().map(CodeAction::to_packet).collect()),
+            None => Err("no action mapped".to_string()),
+        }
+    }
+}
@@ -0,0 +1,555 @@
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+use std::path::Path;
+
+use crate::commands::FrcWriteDOUT;
+use crate::instructions::{CircularArc, FrcCircularMotion, FrcLinearMotion, MotionSettings, PortOutput, PortType};
+use crate::packets::{Command, Instruction};
+use crate::{Configuration, FrcError, PacketEnum, Position, Speed, Termination, MM_PER_INCH};
+
+use super::CodeMap;
+
+/// Speed of `G0` rapid moves unless [`GcodeLoader::rapid_speed`] is set.
+pub const DEFAULT_RAPID_SPEED: Speed = Speed::MmSec(500);
+/// Feed rate in mm/min used until the program sets `F`.
+pub const DEFAULT_FEED_MM_MIN: f64 = 1200.0;
+
+/// How far, in mm, an arc end point may be off the circle through its start.
+const ARC_TOLERANCE: f64 = 0.01;
+/// Below this length, in mm, two points are treated as the same.
+const LENGTH_EPSILON: f64 = 1e-6;
+/// Below this sweep, in radians, an arc is treated as a full circle.
+const ANGLE_EPSILON: f64 = 1e-9;
+
+/// Produces the packets for an `M` or `T` code; instruction sequence IDs are
+/// overwritten by the loader.
+pub type CodeHook = Box<dyn FnMut(u16) -> Result<Vec<PacketEnum>, String> + Send>;
+
+/// Turns G-code into `FrcLinearMotion` and `FrcCircularMotion` instructions.
+///
+/// ```
+/// use fanuc_rmi::gcode::GcodeLoader;
+///
+/// let queue = GcodeLoader::new()
+///     .frame(1)
+///     .tool(1)
+///     .load_str("G21 G90\nG1 X100 Y0 Z50 F3000 ; 50 mm/sec\nG3 X150 Y50 I0 J50")
+///     .unwrap();
+/// assert_eq!(queue.len(), 2);
+/// ```
+pub struct GcodeLoader {
+    settings: MotionSettings,
+    start: [f64; 3],
+    rapid_speed: Speed,
+    feed_mm_min: f64,
+    termination: Termination,
+    first_sequence_id: u32,
+    outputs_on_motion: bool,
+    mcode_hook: Option<CodeHook>,
+    tcode_hook: Option<CodeHook>,
+}
+
+impl Default for GcodeLoader {
+    fn default() -> Self {
+        Self {
+            settings: MotionSettings::default(),
+            start: [0.0; 3],
+            rapid_speed: DEFAULT_RAPID_SPEED,
+            feed_mm_min: DEFAULT_FEED_MM_MIN,
+            termination: Termination::Fine,
+            first_sequence_id: 1,
+            outputs_on_motion: true,
+            mcode_hook: None,
+            tcode_hook: None,
+        }
+    }
+}
+
+impl GcodeLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configuration and orientation of every move.
+    pub fn settings(mut self, settings: MotionSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Configuration sent with every move, including its UFrame and UTool.
+    pub fn configuration(mut self, configuration: Configuration) -> Self {
+        self.settings = self.settings.configuration(configuration);
+        self
+    }
+
+    pub fn frame(mut self, u_frame_number: u8) -> Self {
+        self.settings = self.settings.frame(u_frame_number);
+        self
+    }
+
+    pub fn tool(mut self, u_tool_number: u8) -> Self {
+        self.settings = self.settings.tool(u_tool_number);
+        self
+    }
+
+    /// Tool orientation W/P/R, in degrees, held for every move; tool down by default.
+    pub fn orientation(mut self, w: f32, p: f32, r: f32) -> Self {
+        self.settings = self.settings.orientation(w, p, r);
+        self
+    }
+
+    /// Where the tool is when the program starts, used for `G91` moves and
+    /// axes a block leaves out; the frame origin by default.
+    pub fn start(mut self, position: &Position) -> Self {
+        self.start = [position.x as f64, position.y as f64, position.z as f64];
+        self
+    }
+
+    pub fn rapid_speed(mut self, speed: Speed) -> Self {
+        self.rapid_speed = speed;
+        self
+    }
+
+    /// Feed rate in mm/min used until the program sets `F`.
+    pub fn feed_rate(mut self, mm_per_min: f64) -> Self {
+        self.feed_mm_min = mm_per_min;
+        self
+    }
+
+    pub fn termination(mut self, termination: Termination) -> Self {
+        self.termination = termination;
+        self
+    }
+
+    pub fn first_sequence_id(mut self, sequence_id: u32) -> Self {
+        self.first_sequence_id = sequence_id;
+        self
+    }
+
+    /// Whether an `FRC_WriteDOUT` from an `M` or `T` code that is followed
+    /// directly by a motion switches as that motion starts, through its port
+    /// output, rather than as a command; on by default. The controller runs
+    /// the command only once every earlier motion has finished, so the robot
+    /// stops there instead of blending through. Outputs followed by anything
+    /// but a motion, or by a second output, are always sent as commands.
+    pub fn outputs_on_motion(mut self, on_motion: bool) -> Self {
+        self.outputs_on_motion = on_motion;
+        self
+    }
+
+    /// Called for every `M` code, before the motion in the same block.
+    /// `M2` and `M30` also end the program once their block has run.
+    /// Without a hook other `M` codes are ignored.
+    pub fn on_mcode<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(u16) -> Result<Vec<PacketEnum>, String> + Send + 'static,
+    {
+        self.mcode_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Called for every `T` word, before the `M` codes in the same block.
+    /// `T` words are rejected without a hook. An `FrcSetUTool` from the hook
+    /// also switches the tool sent with the moves that follow.
+    pub fn on_tcode<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(u16) -> Result<Vec<PacketEnum>, String> + Send + 'static,
+    {
+        self.tcode_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Runs `M` and `T` codes through a [`CodeMap`].
+    pub fn codes(self, map: CodeMap) -> Self {
+        let tcodes = map.clone();
+        self.on_mcode(move |code| map.mcode_packets(code))
+            .on_tcode(move |code| tcodes.tcode_packets(code))
+    }
+
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let path = path.as_ref();
+        let program = std::fs::read_to_string(path).map_err(|e| FrcError::Gcode {
+            line: 0,
+            message: format!("{}: {}", path.display(), e),
+        })?;
+        self.load_str(&program)
+    }
+
+    pub fn load_str(&mut self, program: &str) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let mut state = State {
+            motion: None,
+            plane: Plane::XY,
+            inches: false,
+            incremental: false,
+            feed_mm_sec: self.feed_mm_min / 60.0,
+            position: self.start,
+            sequence_id: self.first_sequence_id,
+            pending_output: None,
+        };
+        let mut queue = VecDeque::new();
+        for (index, line) in program.lines().enumerate() {
+            let ended = parse_words(line)
+                .and_then(|words| self.run_block(&words, &mut state, &mut queue))
+                .map_err(|message| FrcError::Gcode { line: index + 1, message })?;
+            if ended {
+                break;
+            }
+        }
+        flush_output(&mut state, &mut queue);
+        Ok(queue)
+    }
+
+    /// Runs one block; returns whether it ended the program.
+    fn run_block(&mut self, words: &[(char, f64)], state: &mut State, queue: &mut VecDeque<PacketEnum>) -> Result<bool, String> {
+        let mut motion = None;
+        let mut axes = [None; 3];
+        let mut offsets = [None; 3];
+        let mut radius = None;
+        let mut feed = None;
+        let mut mcodes = Vec::new();
+        let mut tcodes = Vec::new();
+
+        for &(letter, value) in words {
+            match letter {
+                'G' => match integer(letter, value)? {
+                    0 => motion = Some(Motion::Rapid),
+                    1 => motion = Some(Motion::Linear),
+                    2 => motion = Some(Motion::Clockwise),
+                    3 => motion = Some(Motion::CounterClockwise),
+                    17 => state.plane = Plane::XY,
+                    18 => state.plane = Plane::ZX,
+                    19 => state.plane = Plane::YZ,
+                    20 => state.inches = true,
+                    21 => state.inches = false,
+                    90 => state.incremental = false,
+                    91 => state.incremental = true,
+                    // Cancel codes and feed-per-minute mode, which is the only one supported.
+                    40 | 49 | 80 | 94 => {}
+                    code => return Err(format!("unsupported G-code G{}", code)),
+                },
+                'M' => mcodes.push(integer(letter, value)?),
+                'T' => tcodes.push(integer(letter, value)?),
+                'F' => feed = Some(value),
+                'X' => axes[0] = Some(value),
+                'Y' => axes[1] = Some(value),
+                'Z' => axes[2] = Some(value),
+                'I' => offsets[0] = Some(value),
+                'J' => offsets[1] = Some(value),
+                'K' => offsets[2] = Some(value),
+                'R' => radius = Some(value),
+                // Block numbers and spindle speeds have no RMI equivalent.
+                'N' | 'S' => {}
+                _ => return Err(format!("unsupported word {}{}", letter, value)),
+            }
+        }
+
+        let scale = if state.inches { MM_PER_INCH } else { 1.0 };
+        if let Some(feed) = feed {
+            if feed <= 0.0 {
+                return Err(format!("feed rate F{} must be positive", feed));
+            }
+            state.feed_mm_sec = feed * scale / 60.0;
+        }
+
+        for code in tcodes {
+            let hook = self.tcode_hook.as_mut().ok_or_else(|| format!("no action for T{}", code))?;
+            let packets = hook(code).map_err(|e| format!("T{}: {}", code, e))?;
+            self.push_packets(packets, state, queue);
+        }
+        let (ending, mcodes): (Vec<u16>, Vec<u16>) = mcodes.into_iter().partition(|code| matches!(code, 2 | 30));
+        for code in mcodes {
+            self.run_mcode(code, state, queue)?;
+        }
+
+        if let Some(motion) = motion {
+            state.motion = Some(motion);
+        }
+        let arc_offsets = offsets.iter().any(Option::is_some) || radius.is_some();
+        let is_arc = matches!(state.motion, Some(Motion::Clockwise | Motion::CounterClockwise));
+        if arc_offsets && !is_arc {
+            return Err("I/J/K/R are only valid with G2 or G3".to_string());
+        }
+        if axes.iter().any(Option::is_some) || arc_offsets {
+            let mode = state.motion.ok_or("coordinates given before any G0, G1, G2 or G3")?;
+            let mut target = state.position;
+            for (axis, value) in axes.iter().enumerate() {
+                if let Some(value) = value {
+                    target[axis] = if state.incremental { state.position[axis] + value * scale } else { value * scale };
+                }
+            }
+            match mode {
+                Motion::Rapid => self.linear(target, self.rapid_speed, state, queue),
+                Motion::Linear => self.linear(target, feed_speed(state)?, state, queue),
+                Motion::Clockwise | Motion::CounterClockwise => {
+                    let offsets = offsets.map(|offset| offset.map(|offset| offset * scale));
+                    let segments = arc(state, target, offsets, radius.map(|r| r * scale), mode == Motion::Clockwise)?;
+                    let speed = feed_speed(state)?;
+                    for (via, end) in segments {
+                        let motion = FrcCircularMotion::new(
+                            0,
+                            self.settings.configuration,
+                            self.settings.position(end),
+                            self.settings.configuration,
+                            self.settings.position(via),
+                            speed,
+                            self.termination,
+                        );
+                        push(PacketEnum::Instruction(motion.into()), state, queue);
+                    }
+                }
+            }
+            state.position = target;
+        }
+
+        for code in &ending {
+            self.run_mcode(*code, state, queue)?;
+        }
+        Ok(!ending.is_empty())
+    }
+
+    fn run_mcode(&mut self, code: u16, state: &mut State, queue: &mut VecDeque<PacketEnum>) -> Result<(), String> {
+        if let Some(hook) = self.mcode_hook.as_mut() {
+            let packets = hook(code).map_err(|e| format!("M{}: {}", code, e))?;
+            self.push_packets(packets, state, queue);
+        }
+        Ok(())
+    }
+
+    fn push_packets(&mut self, packets: Vec<PacketEnum>, state: &mut State, queue: &mut VecDeque<PacketEnum>) {
+        for packet in packets {
+            if let PacketEnum::Instruction(Instruction::FrcSetUTool(ref set_tool)) = packet {
+                self.settings.configuration.u_tool_number = set_tool.tool_number;
+            }
+            if let PacketEnum::Command(Command::FrcWriteDOUT(write)) = &packet {
+                if self.outputs_on_motion {
+                    flush_output(state, queue);
+                    let output = PortOutput { port_type: PortType::DO, port_number: write.port_number, port_value: write.port_value };
+                    state.pending_output = Some(output);
+                    continue;
+                }
+            }
+            flush_output(state, queue);
+            push(packet, state, queue);
+        }
+    }
+
+    fn linear(&self, target: [f64; 3], speed: Speed, state: &mut State, queue: &mut VecDeque<PacketEnum>) {
+        if distance(&state.position, &target) < LENGTH_EPSILON {
+            return;
+        }
+        let motion = FrcLinearMotion::new(0, self.settings.configuration, self.settings.position(target), speed, self.termination);
+        push(PacketEnum::Instruction(motion.into()), state, queue);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Motion {
+    Rapid,
+    Linear,
+    Clockwise,
+    CounterClockwise,
+}
+
+/// Arc plane selected by `G17`-`G19`.
+#[derive(Debug, Clone, Copy)]
+enum Plane {
+    XY,
+    ZX,
+    YZ,
+}
+
+impl Plane {
+    /// The two in-plane axes, ordered so arcs run counterclockwise when seen
+    /// from the positive side of the third, normal axis.
+    fn axes(self) -> (usize, usize, usize) {
+        match self {
+            Plane::XY => (0, 1, 2),
+            Plane::ZX => (2, 0, 1),
+            Plane::YZ => (1, 2, 0),
+        }
+    }
+}
+
+/// Modal state carried from block to block. Lengths are in mm.
+struct State {
+    motion: Option<Motion>,
+    plane: Plane,
+    inches: bool,
+    incremental: bool,
+    feed_mm_sec: f64,
+    position: [f64; 3],
+    sequence_id: u32,
+    /// Output waiting to switch with the next motion.
+    pending_output: Option<PortOutput>,
+}
+
+fn push(mut packet: PacketEnum, state: &mut State, queue: &mut VecDeque<PacketEnum>) {
+    if let PacketEnum::Instruction(ref mut instruction) = packet {
+        instruction.set_sequence_id(state.sequence_id);
+        state.sequence_id += 1;
+        match instruction {
+            Instruction::FrcLinearMotion(motion) if state.pending_output.is_some() => {
+                motion.options.port_output = state.pending_output.take();
+            }
+            Instruction::FrcCircularMotion(motion) if state.pending_output.is_some() => {
+                motion.options.port_output = state.pending_output.take();
+            }
+            _ => {}
+        }
+    }
+    queue.push_back(packet);
+}
+
+/// Sends an output still waiting for a motion as `FRC_WriteDOUT`.
+fn flush_output(state: &mut State, queue: &mut VecDeque<PacketEnum>) {
+    if let Some(output) = state.pending_output.take() {
+        let write = FrcWriteDOUT::new(output.port_number, output.port_value);
+        queue.push_back(PacketEnum::Command(Command::FrcWriteDOUT(write)));
+    }
+}
+
+fn feed_speed(state: &State) -> Result<Speed, String> {
+    let mm_sec = state.feed_mm_sec.round();
+    if mm_sec < 1.0 {
+        return Err(format!("feed rate of {:.3} mm/sec is below 1 mm/sec", state.feed_mm_sec));
+    }
+    let speed = Speed::MmSec(mm_sec.min(u16::MAX as f64) as u16);
+    speed.validate().map_err(|e| e.to_string())?;
+    Ok(speed)
+}
+
+/// Via and end point of one `FrcCircularMotion`.
+type ArcSegment = ([f64; 3], [f64; 3]);
+
+/// Splits an arc from the current position into `(via, end)` pairs, as
+/// [`CircularArc::segments`] does.
+fn arc(
+    state: &State,
+    target: [f64; 3],
+    offsets: [Option<f64>; 3],
+    radius: Option<f64>,
+    clockwise: bool,
+) -> Result<Vec<ArcSegment>, String> {
+    let (a, b, n) = state.plane.axes();
+    let start = state.position;
+
+    let center = match radius {
+        Some(_) if offsets[a].is_some() || offsets[b].is_some() => {
+            return Err("an arc takes either R or I/J/K, not both".to_string());
+        }
+        Some(radius) => {
+            let (da, db) = (target[a] - start[a], target[b] - start[b]);
+            let chord = da.hypot(db);
+            if chord < LENGTH_EPSILON {
+                return Err("an R arc needs an end point different from its start".to_string());
+            }
+            let half = chord / 2.0;
+            if radius.abs() < half - ARC_TOLERANCE {
+                return Err(format!("radius {} is too small to reach the end point", radius.abs()));
+            }
+            // The center lies left of the chord for short counterclockwise
+            // arcs; clockwise and negative R (long arcs) each flip the side.
+            let height = (radius * radius - half * half).max(0.0).sqrt();
+            let side = if clockwise { -1.0 } else { 1.0 } * radius.signum();
+            let (ua, ub) = (da / chord, db / chord);
+            (start[a] + da / 2.0 - side * height * ub, start[b] + db / 2.0 + side * height * ua)
+        }
+        None => {
+            if offsets[a].is_none() && offsets[b].is_none() {
+                return Err("an arc needs I/J/K center offsets or R".to_string());
+            }
+            (start[a] + offsets[a].unwrap_or(0.0), start[b] + offsets[b].unwrap_or(0.0))
+        }
+    };
+
+    let start_radius = (start[a] - center.0).hypot(start[b] - center.1);
+    let end_radius = (target[a] - center.0).hypot(target[b] - center.1);
+    if start_radius < LENGTH_EPSILON {
+        return Err("the arc center is on its start point".to_string());
+    }
+    let off_circle = (start_radius - end_radius).abs();
+    if off_circle > ARC_TOLERANCE.max(start_radius * 1e-4) {
+        return Err(format!("the arc end point is {:.3} mm off the circle", off_circle));
+    }
+
+    let start_angle = (start[b] - center.1).atan2(start[a] - center.0);
+    let end_angle = (target[b] - center.1).atan2(target[a] - center.0);
+    let sweep = if clockwise {
+        let sweep = -(start_angle - end_angle).rem_euclid(TAU);
+        if sweep > -ANGLE_EPSILON { sweep - TAU } else { sweep }
+    } else {
+        let sweep = (end_angle - start_angle).rem_euclid(TAU);
+        if sweep < ANGLE_EPSILON { sweep + TAU } else { sweep }
+    };
+
+    // The arc in the plane through the start; a helix climbs along the
+    // normal on top of it.
+    let mut center_xyz = start;
+    center_xyz[a] = center.0;
+    center_xyz[b] = center.1;
+    let mut normal = [0.0; 3];
+    normal[n] = 1.0;
+    let start_position = Position { x: start[0] as f32, y: start[1] as f32, z: start[2] as f32, ..Position::default() };
+    let circle = CircularArc::from_center(&start_position, center_xyz, normal, sweep.to_degrees()).map_err(|e| e.to_string())?;
+    let point = |t: f64| {
+        let on_arc = circle.point(t);
+        let mut point = [on_arc.x as f64, on_arc.y as f64, on_arc.z as f64];
+        point[n] = start[n] + t * (target[n] - start[n]);
+        point
+    };
+
+    Ok(circle
+        .segment_fractions()
+        .into_iter()
+        .map(|(via, end)| (point(via), if end == 1.0 { target } else { point(end) }))
+        .collect())
+}
+
+/// Splits a line into address words, dropping `( )` and `;` comments and `%` tape marks.
+fn parse_words(line: &str) -> Result<Vec<(char, f64)>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            '(' => loop {
+                match chars.next() {
+                    Some(')') => break,
+                    Some(_) => {}
+                    None => return Err("unclosed comment".to_string()),
+                }
+            },
+            '%' => {}
+            c if c.is_whitespace() => {}
+            c if c.is_ascii_alphabetic() => {
+                let letter = c.to_ascii_uppercase();
+                let mut number = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next.is_ascii_digit() || matches!(next, '.' | '-' | '+') {
+                        number.push(next);
+                    } else if !(next.is_whitespace() && number.is_empty()) {
+                        break;
+                    }
+                    chars.next();
+                }
+                let value = number
+                    .parse::<f64>()
+                    .map_err(|_| format!("{} needs a number, got \"{}\"", letter, number))?;
+                words.push((letter, value));
+            }
+            other => return Err(format!("unexpected character '{}'", other)),
+        }
+    }
+    Ok(words)
+}
+
+fn integer(letter: char, value: f64) -> Result<u16, String> {
+    if value.fract() != 0.0 || !(0.0..=u16::MAX as f64).contains(&value) {
+        return Err(format!("unsupported code {}{}", letter, value));
+    }
+    Ok(value as u16)
+}
+
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
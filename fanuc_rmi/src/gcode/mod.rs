@@ -0,0 +1,13 @@
+//! Loading G-code programs as RMI motion instructions.
+//!
+//! Supported: `G0`/`G1` linear moves, `G2`/`G3` arcs (IJK or R form, helical
+//! in the plane normal), `G17`-`G19` planes, `G20`/`G21` units, `G90`/`G91`
+//! distance modes, `F` feed rates, `M` and `T` codes through hooks or a
+//! [`CodeMap`], and `( )`/`;` comments. Coordinates are taken in the loader's
+//! user frame.
+
+mod codes;
+mod loader;
+
+pub use codes::*;
+pub use loader::*;
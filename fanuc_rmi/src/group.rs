@@ -0,0 +1,64 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::FrcError;
+
+/// Motion group numbers a controller can address.
+pub const GROUP_RANGE: RangeInclusive<u8> = 1..=8;
+
+/// Rejects group numbers the controller would answer with `InvalidGroupNumber`.
+pub fn validate_group(group: u8) -> Result<(), FrcError> {
+    if GROUP_RANGE.contains(&group) {
+        Ok(())
+    } else {
+        Err(FrcError::InvalidGroup(format!("group {} is outside {:?}", group, GROUP_RANGE)))
+    }
+}
+
+/// The motion groups `FRC_Initialize` takes control of, bit `n - 1` for group `n`.
+///
+/// Motion instructions move every group in the mask; extended axes travel in
+/// `Position::ext1..ext3` and `JointAngles::j7..j9`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct GroupMask(u8);
+
+impl GroupMask {
+    pub const GROUP_1: GroupMask = GroupMask(1);
+
+    pub fn from_bits(bits: u8) -> Result<Self, FrcError> {
+        if bits == 0 {
+            return Err(FrcError::InvalidGroup("group mask selects no group".to_string()));
+        }
+        Ok(GroupMask(bits))
+    }
+
+    pub fn from_groups(groups: &[u8]) -> Result<Self, FrcError> {
+        let mut bits = 0;
+        for &group in groups {
+            validate_group(group)?;
+            bits |= 1 << (group - 1);
+        }
+        Self::from_bits(bits)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(&self, group: u8) -> bool {
+        GROUP_RANGE.contains(&group) && self.0 & (1 << (group - 1)) != 0
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = u8> {
+        let mask = *self;
+        GROUP_RANGE.filter(move |&group| mask.contains(group))
+    }
+}
+
+impl Default for GroupMask {
+    fn default() -> Self {
+        GroupMask::GROUP_1
+    }
+}
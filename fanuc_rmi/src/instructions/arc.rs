@@ -0,0 +1,293 @@
+use std::f64::consts::{PI, TAU};
+use std::fmt;
+
+use crate::{FrcError, Position};
+
+/// How far, in mm, a via point must be from the start, the end and the line
+/// through them for the controller to accept it.
+pub const VIA_TOLERANCE: f64 = 0.01;
+
+/// A coordinate plane for [`CircularArc::in_plane`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArcPlane {
+    XY,
+    ZX,
+    YZ,
+}
+
+impl ArcPlane {
+    /// The two in-plane axes and the normal. Angles run from the first axis
+    /// towards the second, counterclockwise seen from the normal.
+    fn axes(self) -> ([f64; 3], [f64; 3], [f64; 3]) {
+        let (x, y, z) = ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]);
+        match self {
+            ArcPlane::XY => (x, y, z),
+            ArcPlane::ZX => (z, x, y),
+            ArcPlane::YZ => (y, z, x),
+        }
+    }
+}
+
+/// A circular path, to be run as one or more `FRC_CircularMotion`
+/// instructions through [`MoveBuilder::arc`](super::MoveBuilder::arc).
+///
+/// The controller cannot run a full circle in a single instruction, so
+/// [`segments`](Self::segments) splits arcs of more than half a turn into
+/// pieces of at most half a turn each. Orientation and external axes are
+/// interpolated from the start pose to the end pose.
+///
+/// ```
+/// use fanuc_rmi::instructions::{ArcPlane, CircularArc, Move};
+/// use fanuc_rmi::Position;
+///
+/// let center = Position::new(500.0, 0.0, 300.0, 180.0, 0.0, 0.0);
+/// let circle = CircularArc::full_circle(ArcPlane::XY, &center, 50.0, 0.0).unwrap();
+/// assert_eq!(circle.segments().len(), 2);
+///
+/// // First move to the start of the circle, then run it.
+/// let approach = Move::linear().to(circle.start()).frame(1).tool(1).build(1)?;
+/// let motions = Move::circular().arc(&circle).speed_mm_s(50).frame(1).tool(1).build(2)?;
+/// assert_eq!(motions.len(), 2);
+/// # Ok::<(), fanuc_rmi::FrcError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircularArc {
+    start: Position,
+    end: Position,
+    center: [f64; 3],
+    /// In-plane unit vectors, `u` from the center towards the start and `v` a
+    /// quarter turn ahead of it.
+    u: [f64; 3],
+    v: [f64; 3],
+    radius: f64,
+    /// Radians turned from `u` towards `v`; negative turns the other way.
+    sweep: f64,
+}
+
+impl CircularArc {
+    /// The arc starting at `start` that turns `sweep_degrees` around `center`,
+    /// counterclockwise seen from `normal` when positive. The end keeps the
+    /// start's orientation.
+    pub fn from_center(start: &Position, center: [f64; 3], normal: [f64; 3], sweep_degrees: f64) -> Result<CircularArc, FrcError> {
+        if norm(&normal) < f64::EPSILON {
+            return Err(arc_error("the normal of the arc plane has no length"));
+        }
+        let normal = scale(&normal, 1.0 / norm(&normal));
+        let offset = sub(&xyz(start), &center);
+        if dot(&offset, &normal).abs() > VIA_TOLERANCE {
+            return Err(arc_error("the start is not in the plane of the arc"));
+        }
+        let radius = norm(&offset);
+        if radius < VIA_TOLERANCE {
+            return Err(arc_error("the start is on the center"));
+        }
+        if sweep_degrees.to_radians().abs() * radius < VIA_TOLERANCE {
+            return Err(arc_error("the arc has no length"));
+        }
+        let u = scale(&offset, 1.0 / radius);
+        let v = cross(&normal, &u);
+        let mut arc = CircularArc { start: *start, end: *start, center, u, v, radius, sweep: sweep_degrees.to_radians() };
+        arc.end = arc.point(1.0);
+        Ok(arc)
+    }
+
+    /// The arc of `radius` around `center` in `plane`, from `start_degrees` and
+    /// turning `sweep_degrees`. Angles are measured from the plane's first axis,
+    /// so `0` in `XY` is on +X and `90` on +Y. Every point takes the
+    /// orientation and external axes of `center`.
+    pub fn in_plane(plane: ArcPlane, center: &Position, radius: f64, start_degrees: f64, sweep_degrees: f64) -> Result<CircularArc, FrcError> {
+        let (first, second, normal) = plane.axes();
+        let (sin, cos) = start_degrees.to_radians().sin_cos();
+        let origin = xyz(center);
+        let start = add(&origin, &add(&scale(&first, radius * cos), &scale(&second, radius * sin)));
+        CircularArc::from_center(&with_xyz(center, start), origin, normal, sweep_degrees)
+    }
+
+    /// A whole turn of `radius` around `center` in `plane`, from `start_degrees`.
+    pub fn full_circle(plane: ArcPlane, center: &Position, radius: f64, start_degrees: f64) -> Result<CircularArc, FrcError> {
+        CircularArc::in_plane(plane, center, radius, start_degrees, 360.0)
+    }
+
+    /// The arc from `start` through `via` to `end`, as `FRC_CircularMotion`
+    /// takes it.
+    pub fn through(start: &Position, via: &Position, end: &Position) -> Result<CircularArc, FrcError> {
+        check_via(start, via, end)?;
+        let a = xyz(start);
+        let (ab, ac) = (sub(&xyz(via), &a), sub(&xyz(end), &a));
+        let normal = cross(&ab, &ac);
+        let area = dot(&normal, &normal);
+        let numerator = cross(&sub(&scale(&ac, dot(&ab, &ab)), &scale(&ab, dot(&ac, &ac))), &normal);
+        let center = add(&a, &scale(&numerator, 1.0 / (2.0 * area)));
+
+        let offset = sub(&a, &center);
+        let radius = norm(&offset);
+        let u = scale(&offset, 1.0 / radius);
+        let v = cross(&scale(&normal, 1.0 / area.sqrt()), &u);
+        // `normal` turns the start towards the via point and on to the end.
+        let to_end = sub(&xyz(end), &center);
+        let sweep = dot(&to_end, &v).atan2(dot(&to_end, &u)).rem_euclid(TAU);
+        Ok(CircularArc { start: *start, end: *end, center, u, v, radius, sweep })
+    }
+
+    pub fn start(&self) -> Position {
+        self.start
+    }
+
+    pub fn end(&self) -> Position {
+        self.end
+    }
+
+    pub fn center(&self) -> [f64; 3] {
+        self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Signed angle turned, in degrees.
+    pub fn sweep_degrees(&self) -> f64 {
+        self.sweep.to_degrees()
+    }
+
+    /// Distance travelled along the arc, in mm.
+    pub fn length(&self) -> f64 {
+        self.radius * self.sweep.abs()
+    }
+
+    /// The pose a share `t` of the way along the arc, from 0 at the start to 1
+    /// at the end.
+    pub fn point(&self, t: f64) -> Position {
+        let (sin, cos) = (self.sweep * t).sin_cos();
+        let point = add(&self.center, &add(&scale(&self.u, self.radius * cos), &scale(&self.v, self.radius * sin)));
+        with_xyz(&self.start.interpolate(&self.end, t), point)
+    }
+
+    /// `(via, end)` of each circular move the arc takes, in order; more than
+    /// one when it turns more than half a circle.
+    pub fn segments(&self) -> Vec<(Position, Position)> {
+        self.segment_fractions()
+            .into_iter()
+            .map(|(via, end)| (self.point(via), if end == 1.0 { self.end } else { self.point(end) }))
+            .collect()
+    }
+
+    /// Where the via point and end of each of [`segments`](Self::segments)
+    /// are, as shares of the way along the arc for [`point`](Self::point).
+    pub fn segment_fractions(&self) -> Vec<(f64, f64)> {
+        let count = ((self.sweep.abs() - VIA_TOLERANCE / self.radius) / PI).floor() as usize + 1;
+        (0..count).map(|i| ((i as f64 + 0.5) / count as f64, (i + 1) as f64 / count as f64)).collect()
+    }
+}
+
+/// Why the controller answers a circular move with `InvalidVIAPosition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViaProblem {
+    /// The via point is on the start.
+    OnStart,
+    /// The via point is on the end.
+    OnEnd,
+    /// The via point is in line with the start and end.
+    Collinear,
+    /// The arc starts and ends at the same point.
+    FullCircle,
+}
+
+impl fmt::Display for ViaProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ViaProblem::OnStart => "the via point is on the start",
+            ViaProblem::OnEnd => "the via point is on the end",
+            ViaProblem::Collinear => "the via point is in line with the start and end",
+            ViaProblem::FullCircle => "the arc starts and ends at the same point; a full circle takes two circular moves",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+/// What is wrong with a via point, if the controller would reject it.
+pub fn via_problem(start: &Position, via: &Position, end: &Position) -> Option<ViaProblem> {
+    let (start, via, end) = (xyz(start), xyz(via), xyz(end));
+    let chord = sub(&end, &start);
+    let to_via = sub(&via, &start);
+    if norm(&to_via) < VIA_TOLERANCE {
+        Some(ViaProblem::OnStart)
+    } else if norm(&sub(&end, &via)) < VIA_TOLERANCE {
+        Some(ViaProblem::OnEnd)
+    } else if norm(&chord) < VIA_TOLERANCE {
+        Some(ViaProblem::FullCircle)
+    } else if norm(&cross(&chord, &to_via)) / norm(&chord) < VIA_TOLERANCE {
+        Some(ViaProblem::Collinear)
+    } else {
+        None
+    }
+}
+
+/// Rejects via points the controller answers with `InvalidVIAPosition`; see
+/// [`via_problem`].
+pub fn check_via(start: &Position, via: &Position, end: &Position) -> Result<(), FrcError> {
+    match via_problem(start, via, end) {
+        Some(problem) => Err(FrcError::InvalidInstruction(format!("InvalidVIAPosition: {}", problem))),
+        None => Ok(()),
+    }
+}
+
+impl super::FrcCircularMotion {
+    /// [`via_problem`] for this move when it starts at `start`, in the same user frame.
+    pub fn via_problem(&self, start: &Position) -> Option<ViaProblem> {
+        via_problem(start, &self.via_position, &self.position)
+    }
+
+    /// [`check_via`] for this move when it starts at `start`, in the same user frame.
+    pub fn check_via(&self, start: &Position) -> Result<(), FrcError> {
+        check_via(start, &self.via_position, &self.position)
+    }
+}
+
+impl super::FrcCircularRelative {
+    /// [`via_problem`] for the via point and end, which are offsets from the start.
+    pub fn via_problem(&self) -> Option<ViaProblem> {
+        via_problem(&Position::default(), &self.via_position, &self.position)
+    }
+
+    /// [`check_via`] for the via point and end, which are offsets from the start.
+    pub fn check_via(&self) -> Result<(), FrcError> {
+        check_via(&Position::default(), &self.via_position, &self.position)
+    }
+}
+
+fn arc_error(message: &str) -> FrcError {
+    FrcError::InvalidInstruction(message.to_string())
+}
+
+fn xyz(position: &Position) -> [f64; 3] {
+    [position.x as f64, position.y as f64, position.z as f64]
+}
+
+fn with_xyz(position: &Position, [x, y, z]: [f64; 3]) -> Position {
+    Position { x: x as f32, y: y as f32, z: z as f32, ..*position }
+}
+
+fn add(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: &[f64; 3], factor: f64) -> [f64; 3] {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
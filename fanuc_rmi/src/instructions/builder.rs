@@ -0,0 +1,534 @@
+use std::marker::PhantomData;
+
+use crate::packets::OnOff;
+use crate::{Configuration, FrcError, JointAngles, Position, Speed, Termination};
+use super::*;
+
+/// Speed used when a move does not set one, in mm/sec.
+pub const DEFAULT_SPEED_MM_SEC: u16 = 100;
+
+/// Entry point for building motion instructions.
+///
+/// ```
+/// use fanuc_rmi::instructions::Move;
+/// use fanuc_rmi::Position;
+///
+/// let motion = Move::linear()
+///     .to(Position::new(400.0, 0.0, 300.0, 180.0, 0.0, 0.0))
+///     .speed_mm_s(100)
+///     .cnt(50)
+///     .frame(1)
+///     .tool(1)
+///     .build(1)?;
+/// # Ok::<(), fanuc_rmi::FrcError>(())
+/// ```
+///
+/// Cartesian targets start from `Configuration::default()` and zero external
+/// axes. Joint targets never carry a configuration, so `frame`, `tool` and
+/// `config` are simply not available on them. Until set, a move runs at
+/// [`DEFAULT_SPEED_MM_SEC`] and terminates `FINE`. `build` fails when a linear
+/// or circular move was given a [`Speed::Percent`].
+///
+/// ```compile_fail
+/// use fanuc_rmi::instructions::Move;
+/// use fanuc_rmi::JointAngles;
+///
+/// // Joint representation has no user frame.
+/// Move::joint().to_joints(JointAngles::default()).frame(1);
+/// ```
+pub struct Move;
+
+impl Move {
+    pub fn linear() -> MoveBuilder<Linear, NoTarget> {
+        MoveBuilder::new()
+    }
+
+    pub fn joint() -> MoveBuilder<Joint, NoTarget> {
+        MoveBuilder::new()
+    }
+
+    pub fn circular() -> MoveBuilder<Circular, NoTarget> {
+        MoveBuilder::new()
+    }
+}
+
+/// Motion type marker for `FRC_Linear*` instructions.
+pub struct Linear;
+/// Motion type marker for `FRC_Joint*` instructions.
+pub struct Joint;
+/// Motion type marker for `FRC_Circular*` instructions.
+pub struct Circular;
+
+/// Motion types that move point to point and so accept joint targets.
+pub trait PointToPoint {}
+impl PointToPoint for Linear {}
+impl PointToPoint for Joint {}
+
+/// Motion types whose speed is given along the path rather than per joint.
+pub trait PathMotion {}
+impl PathMotion for Linear {}
+impl PathMotion for Circular {}
+
+/// No destination chosen yet.
+pub struct NoTarget;
+
+/// Absolute Cartesian destination.
+pub struct ToPose {
+    position: Position,
+    config: Configuration,
+}
+
+/// Cartesian offset from the current position.
+pub struct ByOffset {
+    position: Position,
+    config: Configuration,
+}
+
+/// Absolute joint destination.
+pub struct ToJoints(JointAngles);
+
+/// Joint offset from the current position.
+pub struct ByJoints(JointAngles);
+
+/// Circular move with its via point set but no destination yet.
+pub struct Via {
+    via: Position,
+    via_config: Option<Configuration>,
+}
+
+/// Via point and destination of a circular move.
+struct ArcPoints {
+    via: Position,
+    via_config: Option<Configuration>,
+    position: Position,
+    config: Configuration,
+}
+
+impl ArcPoints {
+    fn into_parts(self) -> (Configuration, Position, Configuration, Position) {
+        (self.config, self.position, self.via_config.unwrap_or(self.config), self.via)
+    }
+}
+
+/// Circular move to an absolute destination.
+pub struct ArcTo(ArcPoints);
+
+/// Circular move with via point and destination relative to the current position.
+pub struct ArcBy(ArcPoints);
+
+/// A whole [`CircularArc`], run as one circular move per segment.
+pub struct ArcPath {
+    arc: CircularArc,
+    config: Configuration,
+    ext: Option<[f32; 3]>,
+}
+
+/// Targets that carry a `Configuration` and external axis values.
+pub trait CartesianTarget {
+    fn configuration_mut(&mut self) -> &mut Configuration;
+    fn set_ext(&mut self, ext: [f32; 3]);
+}
+
+impl CartesianTarget for ToPose {
+    fn configuration_mut(&mut self) -> &mut Configuration {
+        &mut self.config
+    }
+
+    fn set_ext(&mut self, [ext1, ext2, ext3]: [f32; 3]) {
+        self.position = Position { ext1, ext2, ext3, ..self.position };
+    }
+}
+
+impl CartesianTarget for ByOffset {
+    fn configuration_mut(&mut self) -> &mut Configuration {
+        &mut self.config
+    }
+
+    fn set_ext(&mut self, [ext1, ext2, ext3]: [f32; 3]) {
+        self.position = Position { ext1, ext2, ext3, ..self.position };
+    }
+}
+
+impl CartesianTarget for ArcPoints {
+    fn configuration_mut(&mut self) -> &mut Configuration {
+        &mut self.config
+    }
+
+    /// Applies to the via point as well as the destination.
+    fn set_ext(&mut self, [ext1, ext2, ext3]: [f32; 3]) {
+        self.position = Position { ext1, ext2, ext3, ..self.position };
+        self.via = Position { ext1, ext2, ext3, ..self.via };
+    }
+}
+
+impl CartesianTarget for ArcTo {
+    fn configuration_mut(&mut self) -> &mut Configuration {
+        self.0.configuration_mut()
+    }
+
+    fn set_ext(&mut self, ext: [f32; 3]) {
+        self.0.set_ext(ext)
+    }
+}
+
+impl CartesianTarget for ArcBy {
+    fn configuration_mut(&mut self) -> &mut Configuration {
+        self.0.configuration_mut()
+    }
+
+    fn set_ext(&mut self, ext: [f32; 3]) {
+        self.0.set_ext(ext)
+    }
+}
+
+impl CartesianTarget for ArcPath {
+    fn configuration_mut(&mut self) -> &mut Configuration {
+        &mut self.config
+    }
+
+    /// Applies to every point of the arc.
+    fn set_ext(&mut self, ext: [f32; 3]) {
+        self.ext = Some(ext);
+    }
+}
+
+/// A motion instruction under construction; see [`Move`].
+pub struct MoveBuilder<K, T> {
+    kind: PhantomData<K>,
+    target: T,
+    speed: Speed,
+    termination: Termination,
+    options: MotionOptions,
+}
+
+impl<K> MoveBuilder<K, NoTarget> {
+    fn new() -> Self {
+        Self {
+            kind: PhantomData,
+            target: NoTarget,
+            speed: Speed::MmSec(DEFAULT_SPEED_MM_SEC),
+            termination: Termination::Fine,
+            options: MotionOptions::default(),
+        }
+    }
+}
+
+impl<K, T> MoveBuilder<K, T> {
+    fn with_target<U>(self, target: U) -> MoveBuilder<K, U> {
+        MoveBuilder {
+            kind: PhantomData,
+            target,
+            speed: self.speed,
+            termination: self.termination,
+            options: self.options,
+        }
+    }
+
+    fn with_speed(mut self, speed: Speed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Any speed; `build` rejects [`Speed::Percent`] on linear and circular moves.
+    pub fn speed(self, speed: Speed) -> Self {
+        self.with_speed(speed)
+    }
+
+    pub fn speed_mm_s(self, speed: u16) -> Self {
+        self.with_speed(Speed::MmSec(speed))
+    }
+
+    pub fn speed_inch_min(self, speed: u16) -> Self {
+        self.with_speed(Speed::InchMin(speed))
+    }
+
+    /// Move duration in 0.1 second increments.
+    pub fn time(self, tenths: u16) -> Self {
+        self.with_speed(Speed::Time(tenths))
+    }
+
+    /// Move duration in milliseconds.
+    pub fn millis(self, millis: u16) -> Self {
+        self.with_speed(Speed::Millis(millis))
+    }
+
+    pub fn termination(mut self, termination: Termination) -> Self {
+        self.termination = termination;
+        self
+    }
+
+    pub fn fine(self) -> Self {
+        self.termination(Termination::Fine)
+    }
+
+    pub fn cnt(self, value: u8) -> Self {
+        self.termination(Termination::Cnt(value))
+    }
+
+    pub fn cr(self, radius: u8) -> Self {
+        self.termination(Termination::Cr(radius))
+    }
+
+    /// Replaces all motion options at once.
+    pub fn options(mut self, options: MotionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn acc(mut self, percent: u8) -> Self {
+        self.options.acc = Some(percent);
+        self
+    }
+
+    pub fn offset_pr(mut self, register: u16) -> Self {
+        self.options.offset_pr_number = Some(register);
+        self
+    }
+
+    pub fn vision_pr(mut self, register: u16) -> Self {
+        self.options.vision_pr_number = Some(register);
+        self
+    }
+
+    pub fn mrot(mut self) -> Self {
+        self.options.mrot = true;
+        self
+    }
+
+    pub fn no_blend(mut self) -> Self {
+        self.options.no_blend = true;
+        self
+    }
+
+    pub fn local_condition(mut self, port_type: PortType, value: u16) -> Self {
+        self.options.lcb = Some(LocalCondition { port_type, value });
+        self
+    }
+
+    pub fn port_output(mut self, port_type: PortType, port_number: u16, port_value: OnOff) -> Self {
+        self.options.port_output = Some(PortOutput { port_type, port_number, port_value });
+        self
+    }
+}
+
+impl<T> MoveBuilder<Joint, T> {
+    /// Percentage of the maximum joint speed.
+    pub fn percent(self, percent: u16) -> Self {
+        self.with_speed(Speed::Percent(percent))
+    }
+}
+
+impl<K: PathMotion, T> MoveBuilder<K, T> {
+    /// Rejects [`Speed::Percent`], which only joint moves accept.
+    fn check_speed(&self) -> Result<(), FrcError> {
+        if matches!(self.speed, Speed::Percent(_)) {
+            return Err(FrcError::InvalidInstruction("percent speed is only valid for joint motion".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl<K: PointToPoint> MoveBuilder<K, NoTarget> {
+    pub fn to(self, position: Position) -> MoveBuilder<K, ToPose> {
+        self.with_target(ToPose { position, config: Configuration::default() })
+    }
+
+    pub fn by(self, offset: Position) -> MoveBuilder<K, ByOffset> {
+        self.with_target(ByOffset { position: offset, config: Configuration::default() })
+    }
+
+    pub fn to_joints(self, joints: JointAngles) -> MoveBuilder<K, ToJoints> {
+        self.with_target(ToJoints(joints))
+    }
+
+    pub fn by_joints(self, offset: JointAngles) -> MoveBuilder<K, ByJoints> {
+        self.with_target(ByJoints(offset))
+    }
+}
+
+impl MoveBuilder<Circular, NoTarget> {
+    pub fn via(self, via: Position) -> MoveBuilder<Circular, Via> {
+        self.with_target(Via { via, via_config: None })
+    }
+
+    /// Runs all of `arc`, which must start where the robot is.
+    pub fn arc(self, arc: &CircularArc) -> MoveBuilder<Circular, ArcPath> {
+        self.with_target(ArcPath { arc: arc.clone(), config: Configuration::default(), ext: None })
+    }
+}
+
+impl MoveBuilder<Circular, Via> {
+    /// Configuration for the via point; defaults to the destination's.
+    pub fn via_config(mut self, config: Configuration) -> Self {
+        self.target.via_config = Some(config);
+        self
+    }
+
+    pub fn to(self, position: Position) -> MoveBuilder<Circular, ArcTo> {
+        let points = self.arc_points(position);
+        self.with_target(ArcTo(points))
+    }
+
+    /// Both the via point and `offset` are taken relative to the current position.
+    pub fn by(self, offset: Position) -> MoveBuilder<Circular, ArcBy> {
+        let points = self.arc_points(offset);
+        self.with_target(ArcBy(points))
+    }
+
+    fn arc_points(&self, position: Position) -> ArcPoints {
+        ArcPoints {
+            via: self.target.via,
+            via_config: self.target.via_config,
+            position,
+            config: Configuration::default(),
+        }
+    }
+}
+
+impl<K, T: CartesianTarget> MoveBuilder<K, T> {
+    /// Replaces the whole configuration, including frame and tool numbers.
+    pub fn config(mut self, config: Configuration) -> Self {
+        *self.target.configuration_mut() = config;
+        self
+    }
+
+    pub fn frame(mut self, u_frame_number: u8) -> Self {
+        self.target.configuration_mut().u_frame_number = u_frame_number;
+        self
+    }
+
+    pub fn tool(mut self, u_tool_number: u8) -> Self {
+        self.target.configuration_mut().u_tool_number = u_tool_number;
+        self
+    }
+
+    pub fn ext(mut self, ext1: f32, ext2: f32, ext3: f32) -> Self {
+        self.target.set_ext([ext1, ext2, ext3]);
+        self
+    }
+}
+
+impl MoveBuilder<Linear, ToPose> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcLinearMotion, FrcError> {
+        self.check_speed()?;
+        let ToPose { position, config } = self.target;
+        let mut instruction = FrcLinearMotion::new(sequence_id, config, position, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Linear, ByOffset> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcLinearRelative, FrcError> {
+        self.check_speed()?;
+        let ByOffset { position, config } = self.target;
+        let mut instruction = FrcLinearRelative::new(sequence_id, config, position, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Linear, ToJoints> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcLinearMotionJRep, FrcError> {
+        self.check_speed()?;
+        let mut instruction = FrcLinearMotionJRep::new(sequence_id, self.target.0, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Linear, ByJoints> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcLinearRelativeJRep, FrcError> {
+        self.check_speed()?;
+        let mut instruction = FrcLinearRelativeJRep::new(sequence_id, self.target.0, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Joint, ToPose> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcJointMotion, FrcError> {
+        let ToPose { position, config } = self.target;
+        let mut instruction = FrcJointMotion::new(sequence_id, config, position, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Joint, ByOffset> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcJointRelative, FrcError> {
+        let ByOffset { position, config } = self.target;
+        let mut instruction = FrcJointRelative::new(sequence_id, config, position, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Joint, ToJoints> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcJointMotionJRep, FrcError> {
+        let mut instruction = FrcJointMotionJRep::new(sequence_id, self.target.0, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Joint, ByJoints> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcJointRelativeJRep, FrcError> {
+        let mut instruction = FrcJointRelativeJRep::new(sequence_id, self.target.0, self.speed, self.termination);
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Circular, ArcTo> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcCircularMotion, FrcError> {
+        self.check_speed()?;
+        let (config, position, via_config, via) = self.target.0.into_parts();
+        let mut instruction = FrcCircularMotion::new(
+            sequence_id, config, position, via_config, via, self.speed, self.termination,
+        );
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Circular, ArcBy> {
+    pub fn build(self, sequence_id: u32) -> Result<FrcCircularRelative, FrcError> {
+        self.check_speed()?;
+        let (config, position, via_config, via) = self.target.0.into_parts();
+        let mut instruction = FrcCircularRelative::new(
+            sequence_id, config, position, via_config, via, self.speed, self.termination,
+        );
+        instruction.options = self.options;
+        Ok(instruction)
+    }
+}
+
+impl MoveBuilder<Circular, ArcPath> {
+    /// One `FrcCircularMotion` per [segment](CircularArc::segments), numbered on from
+    /// `first_sequence_id`. All but the last blend into the next with `CNT 100`
+    /// so the robot does not stop partway round.
+    pub fn build(self, first_sequence_id: u32) -> Result<Vec<FrcCircularMotion>, FrcError> {
+        self.check_speed()?;
+        let ArcPath { arc, config, ext } = self.target;
+        let with_ext = |position: Position| match ext {
+            Some([ext1, ext2, ext3]) => Position { ext1, ext2, ext3, ..position },
+            None => position,
+        };
+        let segments = arc.segments();
+        let last = segments.len() - 1;
+        let motions = segments
+            .into_iter()
+            .enumerate()
+            .map(|(i, (via, end))| {
+                let termination = if i == last { self.termination } else { Termination::Cnt(100) };
+                let mut instruction = FrcCircularMotion::new(
+                    first_sequence_id + i as u32, config, with_ext(end), config, with_ext(via), self.speed, termination,
+                );
+                instruction.options = self.options;
+                instruction
+            })
+            .collect();
+        Ok(motions)
+    }
+}
@@ -3,16 +3,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcCall {
     #[serde(rename = "SequenceID")]
-    sequence_id: i32,
+    pub sequence_id: u32,
     #[serde(rename = "ProgramName")]
-    program_name: String,
+    pub program_name: String,
 
 }
 
  
 impl FrcCall{
-    #[allow(unused)]
-    fn new(seq:i32, program:String) -> Self {
+    pub fn new(seq:u32, program:String) -> Self {
         Self {
             sequence_id: seq,
             program_name: program,
@@ -1,26 +1,25 @@
 use serde::{Deserialize, Serialize};
-use crate::{Configuration, Position, SpeedType, TermType};
+use super::MotionOptions;
+use crate::{Configuration, Position, Speed, Termination};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcCircularRelative {
     #[serde(rename = "SequenceID")]
-    sequence_id: u32,    
+    pub sequence_id: u32,    
     #[serde(rename = "Configuration")]
-    configuration: Configuration,
+    pub configuration: Configuration,
     #[serde(rename = "Position")]
-    position: Position,
+    pub position: Position,
     #[serde(rename = "ViaConfiguration")]
-    via_configuration: Configuration,
+    pub via_configuration: Configuration,
     #[serde(rename = "ViaPosition")]
-    via_position: Position,
-    #[serde(rename = "SpeedType")]
-    speed_type: SpeedType,
-    #[serde(rename = "Speed")]
-    speed: u16,
-    #[serde(rename = "TermType")]
-    term_type: TermType,
-    #[serde(rename = "TermValue")]
-    term_value: u8,
+    pub via_position: Position,
+    #[serde(flatten)]
+    pub speed: Speed,
+    #[serde(flatten)]
+    pub termination: Termination,
+    #[serde(flatten)]
+    pub options: MotionOptions,
 }
 
 impl FrcCircularRelative{
@@ -30,10 +29,8 @@ impl FrcCircularRelative{
         pos: Position,
         vconfig: Configuration,
         vpos: Position,
-        speed_t: SpeedType,
-        speed: u16,
-        term_t: TermType,
-        term_va: u8,
+        speed: Speed,
+        termination: Termination,
     
     ) -> Self {
         Self {
@@ -42,10 +39,9 @@ impl FrcCircularRelative{
             position: pos,
             via_configuration: vconfig,
             via_position: vpos,
-            speed_type: speed_t,
-            speed: speed,
-            term_type: term_t,
-            term_value: term_va,
+            speed,
+            termination,
+            options: MotionOptions::default(),
         }
 
     }
@@ -1,22 +1,21 @@
 use serde::{Deserialize, Serialize};
-use crate::{Configuration, Position, SpeedType, TermType};
+use super::MotionOptions;
+use crate::{Configuration, Position, Speed, Termination};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcJointMotion {
     #[serde(rename = "SequenceID")]
-    sequence_id: u32,    
+    pub sequence_id: u32,    
     #[serde(rename = "Configuration")]
-    configuration: Configuration,
+    pub configuration: Configuration,
     #[serde(rename = "Position")]
-    position: Position,
-    #[serde(rename = "SpeedType")]
-    speed_type: SpeedType,
-    #[serde(rename = "Speed")]
-    speed: u16,
-    #[serde(rename = "TermType")]
-    term_type: TermType,
-    #[serde(rename = "TermValue")]
-    term_value: u8,
+    pub position: Position,
+    #[serde(flatten)]
+    pub speed: Speed,
+    #[serde(flatten)]
+    pub termination: Termination,
+    #[serde(flatten)]
+    pub options: MotionOptions,
 }
 
 
@@ -25,20 +24,17 @@ impl FrcJointMotion{
         sequenceid: u32,    
         config: Configuration,
         pos: Position,
-        speed_t: SpeedType,
-        speed: u16,
-        term_t: TermType,
-        term_va: u8,
+        speed: Speed,
+        termination: Termination,
     
     ) -> Self {
         Self {
             sequence_id: sequenceid,    
             configuration: config,
             position: pos,
-            speed_type: speed_t,
-            speed: speed,
-            term_type: term_t,
-            term_value: term_va,
+            speed,
+            termination,
+            options: MotionOptions::default(),
         }
 
     }
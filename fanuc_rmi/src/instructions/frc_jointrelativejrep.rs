@@ -1,20 +1,19 @@
 use serde::{Deserialize, Serialize};
-use crate::{JointAngles, SpeedType, TermType};
+use super::MotionOptions;
+use crate::{JointAngles, Speed, Termination};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcJointRelativeJRep {
     #[serde(rename = "SequenceID")]
-    sequence_id: u32,    
+    pub sequence_id: u32,    
     #[serde(rename = "JointAngles")]
-    joint_angles: JointAngles,
-    #[serde(rename = "SpeedType")]
-    speed_type: SpeedType,
-    #[serde(rename = "Speed")]
-    speed: u16,
-    #[serde(rename = "TermType")]
-    term_type: TermType,
-    #[serde(rename = "TermValue")]
-    term_value: u8,
+    pub joint_angles: JointAngles,
+    #[serde(flatten)]
+    pub speed: Speed,
+    #[serde(flatten)]
+    pub termination: Termination,
+    #[serde(flatten)]
+    pub options: MotionOptions,
 }
 
 
@@ -22,19 +21,16 @@ impl FrcJointRelativeJRep{
     pub fn new(    
         sequenceid: u32,    
         joints: JointAngles,
-        speed_t: SpeedType,
-        speed: u16,
-        term_t: TermType,
-        term_va: u8,
+        speed: Speed,
+        termination: Termination,
     
     ) -> Self {
         Self {
             sequence_id: sequenceid,    
             joint_angles: joints,
-            speed_type: speed_t,
-            speed: speed,
-            term_type: term_t,
-            term_value: term_va,
+            speed,
+            termination,
+            options: MotionOptions::default(),
         }
 
     }
@@ -3,17 +3,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSetPayLoad {
     #[serde(rename = "SequenceID")]
-    sequence_id: i32,
+    pub sequence_id: u32,
     #[serde(rename = "ScheduleNumber")]
-    schedule_number: u8,
+    pub schedule_number: u8,
 
 }
 
  
 impl FrcSetPayLoad{
-    #[allow(unused)]
-
-    fn new(seq:i32, schedule_num:u8) -> Self {
+    pub fn new(seq:u32, schedule_num:u8) -> Self {
         Self {
             sequence_id: seq,
             schedule_number: schedule_num,
@@ -3,16 +3,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSetUFrame {
     #[serde(rename = "SequenceID")]
-    sequence_id: i32,
+    pub sequence_id: u32,
     #[serde(rename = "FrameNumber")]
-    frame_number: u8,
+    pub frame_number: u8,
 
 }
 
  
 impl FrcSetUFrame{
-    #[allow(unused)]
-    fn new(seq:i32, frame_num:u8) -> Self {
+    pub fn new(seq:u32, frame_num:u8) -> Self {
         Self {
             sequence_id: seq,
             frame_number: frame_num,
@@ -3,16 +3,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSetUTool {
     #[serde(rename = "SequenceID")]
-    sequence_id: i32,
+    pub sequence_id: u32,
     #[serde(rename = "ToolNumber")]
-    tool_number: u8,
+    pub tool_number: u8,
 
 }
 
  
 impl FrcSetUTool{
-    #[allow(unused)]
-    fn new(seq:i32, tool_num:u8) -> Self {
+    pub fn new(seq:u32, tool_num:u8) -> Self {
         Self {
             sequence_id: seq,
             tool_number: tool_num,
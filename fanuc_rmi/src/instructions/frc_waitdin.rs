@@ -4,17 +4,16 @@ use crate::packets::OnOff;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcWaitDIN {
     #[serde(rename = "SequenceID")]
-    sequence_id: i32,
+    pub sequence_id: u32,
     #[serde(rename = "PortNumber")]
-    port_number: u32,
+    pub port_number: u32,
     #[serde(rename = "PortValue")]
-    port_value: OnOff,
+    pub port_value: OnOff,
 }
 
  
 impl FrcWaitDIN{
-    #[allow(unused)]
-    fn new(seq:i32,port_num:u32,port_val:OnOff) -> Self {
+    pub fn new(seq:u32,port_num:u32,port_val:OnOff) -> Self {
         Self {
             sequence_id: seq,
             port_number: port_num,
@@ -3,19 +3,18 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcWaitTime {
     #[serde(rename = "SequenceID")]
-    sequence_id: i32,
+    pub sequence_id: u32,
     #[serde(rename = "Time")]
-    time: f32,
+    pub time: f32,
 
 }
 
  
 impl FrcWaitTime{
-    #[allow(unused)]
-    fn new(seq:i32, time:f32) -> Self {
+    pub fn new(seq:u32, time:f32) -> Self {
         Self {
             sequence_id: seq,
-            time: time,
+            time,
         }
 
     }
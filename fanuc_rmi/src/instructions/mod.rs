@@ -14,6 +14,10 @@ mod frc_circularrelative;
 mod frc_jointmotionjrep;
 mod frc_jointrelativejrep;
 mod frc_linearmotionjrep;
+mod arc;
+mod builder;
+mod motion_options;
+mod motion_settings;
 
 pub use frc_waitdin::*;
 pub use frc_setuframe::*;
@@ -31,3 +35,7 @@ pub use frc_circularrelative::*;
 pub use frc_jointmotionjrep::*;
 pub use frc_jointrelativejrep::*;
 pub use frc_linearmotionjrep::*;
+pub use arc::*;
+pub use builder::*;
+pub use motion_options::*;
+pub use motion_settings::*;
@@ -0,0 +1,102 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::packets::OnOff;
+use crate::FrcError;
+
+/// Acceleration override values accepted in `Acc`, in percent.
+pub const ACC_RANGE: RangeInclusive<u8> = 0..=150;
+
+/// Optional motion modifiers shared by the linear, joint and circular
+/// instructions. Unset options are left out of the packet.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct MotionOptions {
+    /// Acceleration override, `ACC` in TP.
+    #[serde(rename = "Acc", default, skip_serializing_if = "Option::is_none")]
+    pub acc: Option<u8>,
+    /// Position register added to the target, `Offset,PR[n]` in TP.
+    #[serde(rename = "OffsetPRNumber", default, skip_serializing_if = "Option::is_none")]
+    pub offset_pr_number: Option<u16>,
+    /// Vision register added to the target, `VOFFSET,VR[n]` in TP.
+    #[serde(rename = "VisionPRNumber", default, skip_serializing_if = "Option::is_none")]
+    pub vision_pr_number: Option<u16>,
+    /// Minimal wrist rotation, `MROT` in TP.
+    #[serde(rename = "MROT", default, skip_serializing_if = "is_false", with = "flag")]
+    pub mrot: bool,
+    /// Local condition port, `LCBType`/`LCBValue`.
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub lcb: Option<LocalCondition>,
+    /// Signal output when the motion starts, `PortType`/`PortNumber`/`PortValue`.
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub port_output: Option<PortOutput>,
+    /// Do not blend into the next motion even with `CNT`.
+    #[serde(rename = "NoBlend", default, skip_serializing_if = "is_false", with = "flag")]
+    pub no_blend: bool,
+}
+
+/// I/O port types usable with motion options.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortType {
+    DO,
+    RO,
+    GO,
+    AO,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalCondition {
+    #[serde(rename = "LCBType")]
+    pub port_type: PortType,
+    #[serde(rename = "LCBValue")]
+    pub value: u16,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortOutput {
+    #[serde(rename = "PortType")]
+    pub port_type: PortType,
+    #[serde(rename = "PortNumber")]
+    pub port_number: u16,
+    #[serde(rename = "PortValue")]
+    pub port_value: OnOff,
+}
+
+impl MotionOptions {
+    /// Rejects values the controller would answer with `InvalidACCValue`,
+    /// `InvalidPositionRegister`, `InvalidVisionRegister` or `InvalidPortNumber`.
+    pub fn validate(&self) -> Result<(), FrcError> {
+        if let Some(acc) = self.acc {
+            if !ACC_RANGE.contains(&acc) {
+                return Err(FrcError::InvalidInstruction(format!("Acc {} is outside {:?}", acc, ACC_RANGE)));
+            }
+        }
+        if self.offset_pr_number == Some(0) {
+            return Err(FrcError::InvalidInstruction("OffsetPRNumber must be at least 1".to_string()));
+        }
+        if self.vision_pr_number == Some(0) {
+            return Err(FrcError::InvalidInstruction("VisionPRNumber must be at least 1".to_string()));
+        }
+        if matches!(self.port_output, Some(PortOutput { port_number: 0, .. })) {
+            return Err(FrcError::InvalidInstruction("PortNumber must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Booleans the RMI encodes as `1`/`0`.
+mod flag {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*value as u8)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        Ok(u8::deserialize(deserializer)? != 0)
+    }
+}
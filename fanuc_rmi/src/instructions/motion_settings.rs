@@ -0,0 +1,61 @@
+use crate::{Configuration, Position};
+
+/// Configuration and tool orientation sent with every move that
+/// [`GcodeLoader`](crate::gcode::GcodeLoader) and
+/// [`PathGenerator`](crate::program::PathGenerator) generate, so both can be
+/// set up once with [`settings`](crate::program::PathGenerator::settings).
+///
+/// ```
+/// use fanuc_rmi::gcode::GcodeLoader;
+/// use fanuc_rmi::instructions::MotionSettings;
+/// use fanuc_rmi::program::PathGenerator;
+///
+/// let settings = MotionSettings::new().frame(2).tool(1).orientation(180.0, 0.0, 90.0);
+/// let loader = GcodeLoader::new().settings(settings);
+/// let paths = PathGenerator::new().settings(settings);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionSettings {
+    /// Including the UFrame and UTool of every move.
+    pub configuration: Configuration,
+    /// W/P/R, in degrees, held for every move; tool down by default.
+    pub orientation: (f32, f32, f32),
+}
+
+impl Default for MotionSettings {
+    fn default() -> Self {
+        Self { configuration: Configuration::default(), orientation: (180.0, 0.0, 0.0) }
+    }
+}
+
+impl MotionSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn configuration(mut self, configuration: Configuration) -> Self {
+        self.configuration = configuration;
+        self
+    }
+
+    pub fn frame(mut self, u_frame_number: u8) -> Self {
+        self.configuration.u_frame_number = u_frame_number;
+        self
+    }
+
+    pub fn tool(mut self, u_tool_number: u8) -> Self {
+        self.configuration.u_tool_number = u_tool_number;
+        self
+    }
+
+    pub fn orientation(mut self, w: f32, p: f32, r: f32) -> Self {
+        self.orientation = (w, p, r);
+        self
+    }
+
+    /// The pose at `[x, y, z]` in the user frame, in the orientation.
+    pub fn position(&self, [x, y, z]: [f64; 3]) -> Position {
+        let (w, p, r) = self.orientation;
+        Position { x: x as f32, y: y as f32, z: z as f32, w, p, r, ext1: 0.0, ext2: 0.0, ext3: 0.0 }
+    }
+}
@@ -0,0 +1,613 @@
+//! Offline forward and inverse kinematics for FANUC 6-axis arms.
+//!
+//! Joint angles follow the FANUC convention: J2 is measured from vertical,
+//! J3 from the horizon (not relative to J2), and at all-zero joints the
+//! faceplate Z axis points along world +X. Positions are
+//! expressed in the robot world frame, whose origin sits on the J1 axis at
+//! J2 height.
+//!
+//! Arms with a spherical wrist are solved in closed form. Arms whose J6 axis
+//! is offset from J4, such as the CRX-10iA, are solved by searching the
+//! directions of the J5 axis and refining each match numerically.
+
+use crate::pose::{Matrix3, Transform};
+use crate::{Configuration, FlipNoFlip, FrameData, FrontBack, JointAngles, LeftRight, Position, UpDown};
+
+/// Link dimensions (mm) and joint limits (degrees) of an arm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobotModel {
+    pub name: &'static str,
+    /// Horizontal offset from the J1 axis to the J2 axis.
+    pub a1: f64,
+    /// Height of the J2 axis above the world origin.
+    pub d1: f64,
+    /// Upper arm length, J2 to J3.
+    pub a2: f64,
+    /// Offset of the forearm axis above J3.
+    pub a3: f64,
+    /// Forearm length, J3 to the wrist center.
+    pub d4: f64,
+    /// Offset of the J6 axis from the J4 axis, along J5; zero for a
+    /// spherical wrist.
+    pub d5: f64,
+    /// Wrist center to faceplate.
+    pub d6: f64,
+    /// `(min, max)` for J1..J6.
+    pub joint_limits: [(f64, f64); 6],
+    /// Rated speed of J1..J6 in degrees per second.
+    pub max_joint_speeds: [f64; 6],
+}
+
+impl RobotModel {
+    pub const LR_MATE_200ID: RobotModel = RobotModel {
+        name: "LR Mate 200iD",
+        a1: 50.0,
+        d1: 0.0,
+        a2: 330.0,
+        a3: 35.0,
+        d4: 335.0,
+        d5: 0.0,
+        d6: 80.0,
+        joint_limits: [(-170.0, 170.0), (-100.0, 145.0), (-70.0, 205.0), (-190.0, 190.0), (-125.0, 125.0), (-360.0, 360.0)],
+        max_joint_speeds: [450.0, 380.0, 520.0, 550.0, 545.0, 1000.0],
+    };
+
+    pub const LR_MATE_200ID_7L: RobotModel = RobotModel {
+        name: "LR Mate 200iD/7L",
+        a1: 50.0,
+        d1: 0.0,
+        a2: 440.0,
+        a3: 35.0,
+        d4: 420.0,
+        d5: 0.0,
+        d6: 80.0,
+        joint_limits: [(-170.0, 170.0), (-100.0, 145.0), (-70.0, 205.0), (-190.0, 190.0), (-125.0, 125.0), (-360.0, 360.0)],
+        max_joint_speeds: [370.0, 310.0, 410.0, 550.0, 545.0, 1000.0],
+    };
+
+    pub const M_10IA: RobotModel = RobotModel {
+        name: "M-10iA",
+        a1: 150.0,
+        d1: 0.0,
+        a2: 600.0,
+        a3: 200.0,
+        d4: 640.0,
+        d5: 0.0,
+        d6: 100.0,
+        joint_limits: [(-170.0, 170.0), (-90.0, 160.0), (-180.0, 265.0), (-190.0, 190.0), (-190.0, 190.0), (-360.0, 360.0)],
+        max_joint_speeds: [210.0, 190.0, 210.0, 400.0, 400.0, 600.0],
+    };
+
+    pub const M_20IA: RobotModel = RobotModel {
+        name: "M-20iA",
+        a1: 150.0,
+        d1: 0.0,
+        a2: 790.0,
+        a3: 250.0,
+        d4: 835.0,
+        d5: 0.0,
+        d6: 100.0,
+        joint_limits: [(-170.0, 170.0), (-100.0, 160.0), (-185.0, 273.0), (-200.0, 200.0), (-180.0, 180.0), (-450.0, 450.0)],
+        max_joint_speeds: [195.0, 175.0, 180.0, 360.0, 360.0, 550.0],
+    };
+
+    pub const R_2000IC_165F: RobotModel = RobotModel {
+        name: "R-2000iC/165F",
+        a1: 312.0,
+        d1: 0.0,
+        a2: 1075.0,
+        a3: 225.0,
+        d4: 1280.0,
+        d5: 0.0,
+        d6: 215.0,
+        joint_limits: [(-185.0, 185.0), (-60.0, 76.0), (-132.0, 230.0), (-360.0, 360.0), (-125.0, 125.0), (-360.0, 360.0)],
+        max_joint_speeds: [130.0, 115.0, 125.0, 180.0, 180.0, 260.0],
+    };
+
+    pub const CRX_10IA: RobotModel = RobotModel {
+        name: "CRX-10iA",
+        a1: 0.0,
+        d1: 0.0,
+        a2: 540.0,
+        a3: 0.0,
+        d4: 540.0,
+        d5: 150.0,
+        d6: 160.0,
+        joint_limits: [(-190.0, 190.0), (-180.0, 180.0), (-270.0, 270.0), (-190.0, 190.0), (-180.0, 180.0), (-225.0, 225.0)],
+        max_joint_speeds: [120.0, 120.0, 180.0, 180.0, 180.0, 180.0],
+    };
+
+    /// Every model defined in this module.
+    pub fn all() -> [&'static RobotModel; 6] {
+        [&Self::LR_MATE_200ID, &Self::LR_MATE_200ID_7L, &Self::M_10IA, &Self::M_20IA, &Self::R_2000IC_165F, &Self::CRX_10IA]
+    }
+
+    pub fn within_limits(&self, joints: &JointAngles) -> bool {
+        joint_array(joints)
+            .iter()
+            .zip(self.joint_limits.iter())
+            .all(|(&j, &(min, max))| j >= min - LIMIT_EPSILON && j <= max + LIMIT_EPSILON)
+    }
+
+    /// Computes the TCP pose for `joints`, with `tool` given relative to the faceplate.
+    pub fn forward(&self, joints: &JointAngles, tool: &FrameData) -> (Position, ArmConfig) {
+        let [t1, t2, t3, t4, t5, t6] = joint_array(joints);
+        let (r, _, elbow) = self.wrist_center_in_plane(t2, t3);
+        let tcp = self.flange(&[t1, t2, t3, t4, t5, t6]).compose(&tool.to_transform());
+        let position = Position::from_transform(&tcp, [joints.j7, joints.j8, joints.j9]);
+
+        let config = ArmConfig {
+            front: if r >= 0.0 { FrontBack::Front } else { FrontBack::Back },
+            up: if elbow { UpDown::Up } else { UpDown::Down },
+            flip: if t5 > 0.0 { FlipNoFlip::Flip } else { FlipNoFlip::NoFlip },
+            turn4: turn_number(t4),
+            turn5: turn_number(t5),
+            turn6: turn_number(t6),
+        };
+        (position, config)
+    }
+
+    /// Finds every joint solution within limits that puts the TCP at `pos`.
+    ///
+    /// Solutions differing only by a full turn of J4 or J6 are all returned,
+    /// each with its own turn numbers.
+    pub fn inverse(&self, pos: &Position, tool: &FrameData) -> Vec<IkSolution> {
+        let flange = pos.to_transform().compose(&tool.to_transform().inverse());
+        let branches = if self.d5 == 0.0 {
+            self.solve_spherical(&flange)
+        } else {
+            let mut branches: Vec<[f64; 6]> = Vec::new();
+            for seed in self.offset_seeds(&flange) {
+                let Some(solved) = self.refine(&flange, seed) else { continue };
+                if !branches.iter().any(|known| same_branch(known, &solved)) {
+                    branches.push(solved);
+                }
+            }
+            branches
+        };
+
+        let mut solutions = Vec::new();
+        for [t1, t2, t3, t4, t5, t6] in branches {
+            for t4 in turns_within(t4, self.joint_limits[3]) {
+                for t6 in turns_within(t6, self.joint_limits[5]) {
+                    let joints = JointAngles {
+                        j1: t1 as f32,
+                        j2: t2 as f32,
+                        j3: t3 as f32,
+                        j4: t4 as f32,
+                        j5: t5 as f32,
+                        j6: t6 as f32,
+                        j7: pos.ext1,
+                        j8: pos.ext2,
+                        j9: pos.ext3,
+                    };
+                    if self.within_limits(&joints) {
+                        let (_, config) = self.forward(&joints, tool);
+                        solutions.push(IkSolution { joints, config });
+                    }
+                }
+            }
+        }
+        solutions
+    }
+
+    /// The joint solution for `pos` that matches `config`, if it is reachable.
+    pub fn inverse_with_config(&self, pos: &Position, tool: &FrameData, config: &ArmConfig) -> Option<JointAngles> {
+        self.inverse(pos, tool)
+            .into_iter()
+            .find(|solution| solution.config == *config)
+            .map(|solution| solution.joints)
+    }
+
+    pub fn is_reachable(&self, pos: &Position, tool: &FrameData) -> bool {
+        !self.inverse(pos, tool).is_empty()
+    }
+
+    fn wrist_rotation(&self, t1: f64, t3: f64) -> Matrix3 {
+        mul(&rz(t1), &ry(-t3))
+    }
+
+    /// Faceplate pose for J1..J6 in degrees.
+    fn flange(&self, [t1, t2, t3, t4, t5, t6]: &[f64; 6]) -> Transform {
+        let wrist = mul(&self.wrist_rotation(*t1, *t3), &rx(-t4));
+        let rotation = mul(&wrist, &mul(&ry(-t5), &mul(&rx(-t6), &TOOL_AT_ZERO)));
+
+        let (r, h, _) = self.wrist_center_in_plane(*t2, *t3);
+        let (s1, c1) = t1.to_radians().sin_cos();
+        let center = [r * c1, r * s1, h + self.d1];
+        // The J5 axis, which the offset wrist moves the J6 axis along.
+        let side = [wrist[0][1], wrist[1][1], wrist[2][1]];
+        let z = [rotation[0][2], rotation[1][2], rotation[2][2]];
+        let translation = [0, 1, 2].map(|i| center[i] + self.d5 * side[i] + self.d6 * z[i]);
+        Transform { rotation, translation }
+    }
+
+    /// J1..J6, each in (-180, 180], of every branch of a spherical wrist
+    /// that puts the faceplate at `flange`, ignoring `d5`.
+    fn solve_spherical(&self, flange: &Transform) -> Vec<[f64; 6]> {
+        let rotation = flange.rotation;
+        let z = [rotation[0][2], rotation[1][2], rotation[2][2]];
+        let center = [0, 1, 2].map(|i| flange.translation[i] - self.d6 * z[i]);
+
+        let mut branches = Vec::new();
+        let base = center[1].atan2(center[0]).to_degrees();
+        for t1 in [base, base + 180.0] {
+            let t1 = wrap(t1);
+            let (s1, c1) = t1.to_radians().sin_cos();
+            let r = center[0] * c1 + center[1] * s1 - self.a1;
+            let h = center[2] - self.d1;
+
+            for (t2, t3) in self.solve_arm(r, h) {
+                let wrist = transpose(&self.wrist_rotation(t1, t3));
+                let m = mul(&mul(&wrist, &rotation), &transpose(&TOOL_AT_ZERO));
+                for (t4, t5, t6) in solve_wrist(&m) {
+                    branches.push([t1, t2, t3, t4, t5, t6]);
+                }
+            }
+        }
+        branches
+    }
+
+    /// Approximate solutions for an offset wrist.
+    ///
+    /// The J5 axis is normal to the faceplate Z axis, so it points somewhere
+    /// on a circle around it; each direction fixes the wrist center and so
+    /// J1..J3. The directions that are also normal to the forearm are found
+    /// by bisecting sign changes around the circle, per arm branch.
+    fn offset_seeds(&self, flange: &Transform) -> Vec<[f64; 6]> {
+        let rotation = flange.rotation;
+        let column = |j: usize| [rotation[0][j], rotation[1][j], rotation[2][j]];
+        let (x, y) = (column(0), column(1));
+        let axis_at = |phi: f64| {
+            let (sin, cos) = phi.sin_cos();
+            [0, 1, 2].map(|i| x[i] * cos + y[i] * sin)
+        };
+        let step = std::f64::consts::TAU / OFFSET_SAMPLES as f64;
+
+        let mut seeds = Vec::new();
+        for branch in 0..4 {
+            let normal = |phi: f64| self.offset_arm(flange, &axis_at(phi), branch).map(|(_, dot)| dot);
+            for sample in 0..OFFSET_SAMPLES {
+                let (low, high) = (sample as f64 * step, (sample + 1) as f64 * step);
+                // Where the wrist center leaves the reach the branch ends, and
+                // roots close to that edge still need finding.
+                let edge = |mut inside: f64, mut outside: f64| {
+                    for _ in 0..OFFSET_BISECTIONS {
+                        let middle = (inside + outside) / 2.0;
+                        if normal(middle).is_some() {
+                            inside = middle;
+                        } else {
+                            outside = middle;
+                        }
+                    }
+                    normal(inside).map(|at_inside| (inside, at_inside))
+                };
+                let (mut low, mut at_low, mut high, at_high) = match (normal(low), normal(high)) {
+                    (Some(at_low), Some(at_high)) => (low, at_low, high, at_high),
+                    (Some(at_low), None) => {
+                        let Some((high, at_high)) = edge(low, high) else { continue };
+                        (low, at_low, high, at_high)
+                    }
+                    (None, Some(at_high)) => {
+                        let Some((low, at_low)) = edge(high, low) else { continue };
+                        (low, at_low, high, at_high)
+                    }
+                    (None, None) => continue,
+                };
+                if at_low.signum() == at_high.signum() {
+                    continue;
+                }
+                for _ in 0..OFFSET_BISECTIONS {
+                    let middle = (low + high) / 2.0;
+                    let Some(at_middle) = normal(middle) else { break };
+                    if at_middle.signum() == at_low.signum() {
+                        (low, at_low) = (middle, at_middle);
+                    } else {
+                        high = middle;
+                    }
+                }
+                // Sign changes where J1 jumps half a turn are not roots.
+                let Some(([t1, t2, t3], dot)) = self.offset_arm(flange, &axis_at(low), branch) else { continue };
+                if dot.abs() > 1e-6 {
+                    continue;
+                }
+                let wrist = transpose(&self.wrist_rotation(t1, t3));
+                let m = mul(&mul(&wrist, &rotation), &transpose(&TOOL_AT_ZERO));
+                for (t4, t5, t6) in solve_wrist(&m) {
+                    let seed = [t1, t2, t3, t4, t5, t6];
+                    if norm6(&self.pose_error(flange, &seed)) < 1e-3 {
+                        seeds.push(seed);
+                    }
+                }
+            }
+        }
+        seeds
+    }
+
+    /// J1..J3 of arm branch `branch` (0..4) for the J5 axis along `axis`,
+    /// with the cosine between that axis and the forearm.
+    fn offset_arm(&self, flange: &Transform, axis: &[f64; 3], branch: usize) -> Option<([f64; 3], f64)> {
+        let z = [flange.rotation[0][2], flange.rotation[1][2], flange.rotation[2][2]];
+        let center = [0, 1, 2].map(|i| flange.translation[i] - self.d6 * z[i] - self.d5 * axis[i]);
+        let t1 = wrap(center[1].atan2(center[0]).to_degrees() + if branch < 2 { 0.0 } else { 180.0 });
+        let (s1, c1) = t1.to_radians().sin_cos();
+        let r = center[0] * c1 + center[1] * s1 - self.a1;
+        let h = center[2] - self.d1;
+        let (t2, t3) = *self.solve_arm(r, h).get(branch % 2)?;
+        let wrist = self.wrist_rotation(t1, t3);
+        let dot = (0..3).map(|i| wrist[i][0] * axis[i]).sum();
+        Some(([t1, t2, t3], dot))
+    }
+
+    /// Damped Newton iterations from `seed` to the joints that put the
+    /// faceplate at `flange`, or `None` if they do not converge.
+    fn refine(&self, flange: &Transform, seed: [f64; 6]) -> Option<[f64; 6]> {
+        let mut joints = seed;
+        for _ in 0..IK_ITERATIONS {
+            let error = self.pose_error(flange, &joints);
+            if norm6(&error) < IK_TOLERANCE {
+                return Some(joints.map(wrap));
+            }
+            // Jacobian by finite differences, in mm (or scaled radians) per degree.
+            let mut jacobian = [[0.0; 6]; 6];
+            for joint in 0..6 {
+                let mut nudged = joints;
+                nudged[joint] += JACOBIAN_STEP;
+                let moved = self.pose_error(flange, &nudged);
+                for (row, (error, moved)) in jacobian.iter_mut().zip(error.iter().zip(moved)) {
+                    row[joint] = (error - moved) / JACOBIAN_STEP;
+                }
+            }
+            let step = solve_damped(&jacobian, &error)?;
+            // Large steps jump between branches; keep each under 10 degrees.
+            let largest = step.iter().fold(0.0_f64, |largest, s| largest.max(s.abs()));
+            let scale = if largest > 10.0 { 10.0 / largest } else { 1.0 };
+            for (joint, step) in joints.iter_mut().zip(step) {
+                *joint += step * scale;
+            }
+        }
+        None
+    }
+
+    /// Translation and rotation from the faceplate at `joints` to `flange`,
+    /// the rotation as a rotation vector scaled by the upper arm length.
+    fn pose_error(&self, flange: &Transform, joints: &[f64; 6]) -> [f64; 6] {
+        let current = self.flange(joints);
+        let difference = mul(&flange.rotation, &transpose(&current.rotation));
+        let rotation = rotation_vector(&difference);
+        let t = flange.translation;
+        let c = current.translation;
+        [t[0] - c[0], t[1] - c[1], t[2] - c[2], rotation[0] * self.a2, rotation[1] * self.a2, rotation[2] * self.a2]
+    }
+
+    /// Wrist center in the arm plane as `(radial, height above J2, elbow up)`.
+    fn wrist_center_in_plane(&self, t2: f64, t3: f64) -> (f64, f64, bool) {
+        let (s2, c2) = t2.to_radians().sin_cos();
+        let (s3, c3) = t3.to_radians().sin_cos();
+        let elbow = (self.a2 * s2, self.a2 * c2);
+        let r = elbow.0 + self.d4 * c3 - self.a3 * s3;
+        let h = elbow.1 + self.d4 * s3 + self.a3 * c3;
+        let up = r * elbow.1 - h * elbow.0 > 0.0;
+        (r + self.a1, h, up)
+    }
+
+    /// Solves J2/J3 for a wrist center at `(r, h)` relative to J2.
+    fn solve_arm(&self, r: f64, h: f64) -> Vec<(f64, f64)> {
+        let forearm = self.a3.hypot(self.d4);
+        let beta = self.a3.atan2(self.d4);
+        let k = (r * r + h * h - self.a2 * self.a2 - forearm * forearm) / (2.0 * self.a2 * forearm);
+        if !(-1.0..=1.0).contains(&k) {
+            return Vec::new();
+        }
+
+        let psi = k.asin();
+        [psi, std::f64::consts::PI - psi]
+            .iter()
+            .map(|&psi| {
+                let a = self.a2 + forearm * psi.sin();
+                let b = forearm * psi.cos();
+                let t2 = (a * r - b * h).atan2(b * r + a * h);
+                let t3 = psi - t2 - beta;
+                (wrap(t2.to_degrees()), wrap(t3.to_degrees()))
+            })
+            .collect()
+    }
+}
+
+/// Configuration flags and turn numbers as FANUC reports them for a pose.
+///
+/// Left/right is not meaningful for these arms and is always reported as right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArmConfig {
+    pub front: FrontBack,
+    pub up: UpDown,
+    pub flip: FlipNoFlip,
+    pub turn4: i8,
+    pub turn5: i8,
+    pub turn6: i8,
+}
+
+impl ArmConfig {
+    /// Builds the RMI `Configuration` for motion in `uframe`/`utool`.
+    pub fn to_configuration(&self, u_frame_number: u8, u_tool_number: u8) -> Configuration {
+        Configuration {
+            u_tool_number,
+            u_frame_number,
+            front: self.front,
+            up: self.up,
+            left: LeftRight::Right,
+            flip: self.flip,
+            turn4: self.turn4,
+            turn5: self.turn5,
+            turn6: self.turn6,
+        }
+    }
+}
+
+impl From<&Configuration> for ArmConfig {
+    /// Drops the frame numbers and the left/right flag.
+    fn from(config: &Configuration) -> Self {
+        Self {
+            front: config.front,
+            up: config.up,
+            flip: config.flip,
+            turn4: config.turn4,
+            turn5: config.turn5,
+            turn6: config.turn6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IkSolution {
+    pub joints: JointAngles,
+    pub config: ArmConfig,
+}
+
+/// Slack allowed on joint limits, in degrees, so `f32` round-off in positions
+/// does not reject boundary poses.
+const LIMIT_EPSILON: f64 = 1e-3;
+
+/// Below this `sin(J5)` the wrist is singular and J4 is pinned to 0.
+const WRIST_SINGULARITY: f64 = 1e-9;
+
+/// Directions of the J5 axis tried around the faceplate Z axis.
+const OFFSET_SAMPLES: usize = 360;
+/// Bisections narrowing each sign change to a J5 axis direction.
+const OFFSET_BISECTIONS: usize = 50;
+/// Newton iterations allowed for an offset wrist.
+const IK_ITERATIONS: usize = 100;
+/// Pose error, in mm, at which an offset-wrist solution is accepted.
+const IK_TOLERANCE: f64 = 1e-7;
+/// Joint step, in degrees, for the finite-difference Jacobian.
+const JACOBIAN_STEP: f64 = 1e-6;
+/// Levenberg-Marquardt damping, which keeps steps bounded near singularities.
+const DAMPING: f64 = 1e-6;
+
+/// Faceplate axes at all-zero joints, in forearm coordinates: X up, Y to the
+/// right, Z along the forearm.
+const TOOL_AT_ZERO: Matrix3 = [[0.0, 0.0, 1.0], [0.0, -1.0, 0.0], [1.0, 0.0, 0.0]];
+
+fn joint_array(joints: &JointAngles) -> [f64; 6] {
+    [joints.j1, joints.j2, joints.j3, joints.j4, joints.j5, joints.j6].map(|j| j as f64)
+}
+
+/// FANUC turn number: 0 for (-180, 180], 1 for (180, 540] and so on.
+fn turn_number(angle: f64) -> i8 {
+    (-((180.0 - angle) / 360.0).floor()) as i8
+}
+
+/// Wraps an angle into (-180, 180].
+fn wrap(angle: f64) -> f64 {
+    let wrapped = angle - 360.0 * ((angle + 180.0) / 360.0).ceil() + 360.0;
+    if wrapped <= -180.0 { wrapped + 360.0 } else { wrapped }
+}
+
+fn turns_within(angle: f64, (min, max): (f64, f64)) -> Vec<f64> {
+    (-2..=2)
+        .map(|turn| angle + 360.0 * turn as f64)
+        .filter(|a| *a >= min - LIMIT_EPSILON && *a <= max + LIMIT_EPSILON)
+        .collect()
+}
+
+/// Solves `Rx(-j4) * Ry(-j5) * Rx(-j6) = m` for both wrist flips.
+fn solve_wrist(m: &Matrix3) -> Vec<(f64, f64, f64)> {
+    let b = m[0][0].clamp(-1.0, 1.0).acos();
+    if b.sin().abs() < WRIST_SINGULARITY {
+        // J4 and J6 turn about the same axis; only their sum is fixed.
+        let sum = m[2][1].atan2(m[1][1]);
+        let j5 = if m[0][0] > 0.0 { 0.0 } else { -180.0 };
+        return vec![(0.0, j5, wrap(-sum.to_degrees()))];
+    }
+
+    [b, -b]
+        .iter()
+        .map(|&b| {
+            let sign = b.sin().signum();
+            let a = (sign * m[1][0]).atan2(-sign * m[2][0]);
+            let c = (sign * m[0][1]).atan2(sign * m[0][2]);
+            (wrap(-a.to_degrees()), wrap(-b.to_degrees()), wrap(-c.to_degrees()))
+        })
+        .collect()
+}
+
+/// Whether two solutions are the same up to full turns of J4 and J6.
+fn same_branch(a: &[f64; 6], b: &[f64; 6]) -> bool {
+    a.iter().zip(b).all(|(a, b)| wrap(a - b).abs() < 1e-4)
+}
+
+fn norm6(v: &[f64; 6]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+/// Axis times angle, in radians, of the rotation `m`.
+fn rotation_vector(m: &Matrix3) -> [f64; 3] {
+    let axis = [m[2][1] - m[1][2], m[0][2] - m[2][0], m[1][0] - m[0][1]];
+    let sin = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt() / 2.0;
+    let cos = ((m[0][0] + m[1][1] + m[2][2] - 1.0) / 2.0).clamp(-1.0, 1.0);
+    let angle = sin.atan2(cos);
+    if sin < 1e-12 {
+        return axis.map(|a| a / 2.0);
+    }
+    axis.map(|a| a / (2.0 * sin) * angle)
+}
+
+/// Solves `(JᵀJ + λI) x = Jᵀe` by Gaussian elimination.
+fn solve_damped(jacobian: &[[f64; 6]; 6], error: &[f64; 6]) -> Option<[f64; 6]> {
+    let mut system = [[0.0; 7]; 6];
+    for (i, row) in system.iter_mut().enumerate() {
+        for j in 0..6 {
+            row[j] = (0..6).map(|k| jacobian[k][i] * jacobian[k][j]).sum::<f64>() + if i == j { DAMPING } else { 0.0 };
+        }
+        row[6] = (0..6).map(|k| jacobian[k][i] * error[k]).sum();
+    }
+    for column in 0..6 {
+        let pivot = (column..6).max_by(|&a, &b| system[a][column].abs().total_cmp(&system[b][column].abs()))?;
+        if system[pivot][column].abs() < f64::EPSILON {
+            return None;
+        }
+        system.swap(column, pivot);
+        for row in column + 1..6 {
+            let factor = system[row][column] / system[column][column];
+            let pivot_row = system[column];
+            for (value, pivot) in system[row].iter_mut().zip(pivot_row).skip(column) {
+                *value -= factor * pivot;
+            }
+        }
+    }
+    let mut x = [0.0; 6];
+    for row in (0..6).rev() {
+        let rest: f64 = (row + 1..6).map(|k| system[row][k] * x[k]).sum();
+        x[row] = (system[row][6] - rest) / system[row][row];
+    }
+    Some(x)
+}
+
+fn rx(deg: f64) -> Matrix3 {
+    let (s, c) = deg.to_radians().sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, c, -s], [0.0, s, c]]
+}
+
+fn ry(deg: f64) -> Matrix3 {
+    let (s, c) = deg.to_radians().sin_cos();
+    [[c, 0.0, s], [0.0, 1.0, 0.0], [-s, 0.0, c]]
+}
+
+fn rz(deg: f64) -> Matrix3 {
+    let (s, c) = deg.to_radians().sin_cos();
+    [[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]]
+}
+
+fn mul(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    let mut out = [[0.0; 3]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    out
+}
+
+fn transpose(m: &Matrix3) -> Matrix3 {
+    [[m[0][0], m[1][0], m[2][0]], [m[0][1], m[1][1], m[2][1]], [m[0][2], m[1][2], m[2][2]]]
+}
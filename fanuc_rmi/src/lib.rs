@@ -1,46 +1,42 @@
-use std::collections::HashMap;
-use std::error::{self, Error};
+use std::error::Error;
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::time::Duration;
 
 use packets::Communication;
 use packets::Command;
 use packets::Instruction;
 
 use serde::{Deserialize, Serialize};
-use int_enum::IntEnum;
 
 pub mod packets;
 pub mod drivers;
 pub mod instructions;
 pub mod commands;
 pub mod communication;
+pub mod pose;
+pub mod kinematics;
+pub mod gcode;
+pub mod program;
+mod configuration;
+mod group;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct FrameData {
-    x: f32,
-    y: f32,
-    z: f32,
-    w: f32,
-    p: f32,
-    r: f32,
-}
+pub use configuration::*;
+pub use group::*;
 
-
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
 #[serde(rename_all = "PascalCase")]
-pub struct Configuration {
-    pub u_tool_number: u8,
-    pub u_frame_number: u8,
-    pub front: u8,
-    pub up: u8,
-    pub left: u8,
-    pub glip: u8,
-    pub turn4: u8,
-    pub turn5: u8,
-    pub turn6: u8,
+pub struct FrameData {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+    pub p: f32,
+    pub r: f32,
 }
 
-#[derive(Serialize, Deserialize,Debug)]
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
 #[serde(rename_all = "PascalCase")]
 pub struct Position {
     pub x: f32,
@@ -54,27 +50,119 @@ pub struct Position {
     pub ext3: f32,
 }
 
-#[derive(Serialize, Deserialize,Debug)]
+impl Position {
+    /// A pose with the extended axes at zero.
+    pub fn new(x: f32, y: f32, z: f32, w: f32, p: f32, r: f32) -> Self {
+        Self { x, y, z, w, p, r, ..Default::default() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "PascalCase")]
 pub struct JointAngles {
-    j1: f32,
-    j2: f32,
-    j3: f32,
-    j4: f32,
-    j5: f32,
-    j6: f32,
-    j7: f32,
-    j8: f32,
-    j9: f32,
+    pub j1: f32,
+    pub j2: f32,
+    pub j3: f32,
+    pub j4: f32,
+    pub j5: f32,
+    pub j6: f32,
+    pub j7: f32,
+    pub j8: f32,
+    pub j9: f32,
 }
 
+impl JointAngles {
+    /// J1..J6 with the extended axes J7..J9 at zero.
+    pub fn new(j1: f32, j2: f32, j3: f32, j4: f32, j5: f32, j6: f32) -> Self {
+        Self { j1, j2, j3, j4, j5, j6, ..Default::default() }
+    }
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TermType {
     FINE,
     CNT, // CNT with a value from 1 to 100
     CR,  // CR with a value from 1 to 100
 }
 
+/// `TermValue`s accepted with `CNT` and `CR`.
+pub const TERM_VALUE_RANGE: RangeInclusive<u8> = 1..=100;
+
+/// How a motion ends, together with its value.
+///
+/// Serialized as the `TermType`/`TermValue` pair of motion instructions.
+/// `Fine` is sent with a `TermValue` of 1, which the controller ignores.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "TermPair", into = "TermPair")]
+pub enum Termination {
+    /// Stop at the target.
+    #[default]
+    Fine,
+    /// Blend into the next motion, 1 to 100; higher rounds the corner more.
+    Cnt(u8),
+    /// Blend with a corner radius in mm, 1 to 100.
+    Cr(u8),
+}
+
+impl Termination {
+    pub fn term_type(&self) -> TermType {
+        match self {
+            Termination::Fine => TermType::FINE,
+            Termination::Cnt(_) => TermType::CNT,
+            Termination::Cr(_) => TermType::CR,
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        match *self {
+            Termination::Fine => 1,
+            Termination::Cnt(value) | Termination::Cr(value) => value,
+        }
+    }
+
+    /// Rejects `CNT`/`CR` values the controller would answer with `InvalidTermValue`.
+    pub fn validate(&self) -> Result<(), FrcError> {
+        if TERM_VALUE_RANGE.contains(&self.value()) {
+            Ok(())
+        } else {
+            Err(FrcError::InvalidInstruction(format!(
+                "{:?} value {} is outside {:?}",
+                self.term_type(),
+                self.value(),
+                TERM_VALUE_RANGE
+            )))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TermPair {
+    term_type: TermType,
+    term_value: u8,
+}
+
+impl From<Termination> for TermPair {
+    fn from(termination: Termination) -> Self {
+        TermPair { term_type: termination.term_type(), term_value: termination.value() }
+    }
+}
+
+impl TryFrom<TermPair> for Termination {
+    type Error = String;
+
+    fn try_from(pair: TermPair) -> Result<Self, Self::Error> {
+        let termination = match pair.term_type {
+            TermType::FINE => Termination::Fine,
+            TermType::CNT => Termination::Cnt(pair.term_value),
+            TermType::CR => Termination::Cr(pair.term_value),
+        };
+        termination.validate().map_err(|e| e.to_string())?;
+        Ok(termination)
+    }
+}
+
 
 /// Represents different types of speed measurements.
 ///
@@ -84,28 +172,227 @@ pub enum TermType {
 /// # Variants
 ///
 /// * `MMSec` - Represents speed in millimeters per second (mm/sec).
-/// * `InchMin` - Represents speed in inches per second.
+/// * `InchMin` - Represents speed in inches per minute.
 /// * `Time` - Represents time in 0.1 second increments.
 /// * `MilliSeconds` - Represents time in milliseconds (0.001 seconds).
-#[derive(Serialize, Deserialize, Debug)]
+/// * `Percent` - Represents a percentage of the maximum joint speed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SpeedType {
     #[serde(rename = "mmSec")]
     MMSec, // Speed in millimeters per second (mm/sec).
-    InchMin, // Speed in inches per second.
+    InchMin, // Speed in inches per minute.
     Time, // Time in 0.1 second increments.
     #[serde(rename = "mSec")]
     MilliSeconds, // Time in milliseconds (0.001 seconds).
+    Percent, // Percentage of the maximum joint speed, joint motion only.
+}
+
+/// Linear speeds accepted in mm/sec.
+pub const MM_SEC_RANGE: RangeInclusive<u16> = 1..=2000;
+/// Linear speeds accepted in inch/min, the same limit as [`MM_SEC_RANGE`].
+pub const INCH_MIN_RANGE: RangeInclusive<u16> = 1..=4724;
+/// Joint speeds accepted in percent.
+pub const PERCENT_RANGE: RangeInclusive<u16> = 1..=100;
+
+pub(crate) const MM_PER_INCH: f64 = 25.4;
+
+/// Motion speed together with its unit.
+///
+/// Serialized as the `SpeedType`/`Speed` pair of motion instructions.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "SpeedPair", into = "SpeedPair")]
+pub enum Speed {
+    MmSec(u16),
+    InchMin(u16),
+    /// Move duration in 0.1 second increments.
+    Time(u16),
+    /// Move duration in milliseconds.
+    Millis(u16),
+    /// Percentage of the maximum joint speed; only valid for joint motion.
+    Percent(u16),
+}
+
+impl Speed {
+    pub fn speed_type(&self) -> SpeedType {
+        match self {
+            Speed::MmSec(_) => SpeedType::MMSec,
+            Speed::InchMin(_) => SpeedType::InchMin,
+            Speed::Time(_) => SpeedType::Time,
+            Speed::Millis(_) => SpeedType::MilliSeconds,
+            Speed::Percent(_) => SpeedType::Percent,
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        match *self {
+            Speed::MmSec(value)
+            | Speed::InchMin(value)
+            | Speed::Time(value)
+            | Speed::Millis(value)
+            | Speed::Percent(value) => value,
+        }
+    }
+
+    /// Linear speed in mm/sec, or `None` for durations and percentages.
+    pub fn mm_per_sec(&self) -> Option<f64> {
+        match *self {
+            Speed::MmSec(value) => Some(value as f64),
+            Speed::InchMin(value) => Some(value as f64 * MM_PER_INCH / 60.0),
+            _ => None,
+        }
+    }
+
+    /// The same linear speed in mm/sec, rounded to the nearest unit.
+    pub fn to_mm_sec(&self) -> Option<Speed> {
+        self.mm_per_sec().map(|mm| Speed::MmSec(mm.round() as u16))
+    }
+
+    /// The same linear speed in inch/min, rounded to the nearest unit.
+    pub fn to_inch_min(&self) -> Option<Speed> {
+        self.mm_per_sec().map(|mm| Speed::InchMin((mm * 60.0 / MM_PER_INCH).round() as u16))
+    }
+
+    /// How long the move takes, for `Time` and `Millis`.
+    pub fn duration(&self) -> Option<Duration> {
+        match *self {
+            Speed::Time(tenths) => Some(Duration::from_millis(tenths as u64 * 100)),
+            Speed::Millis(millis) => Some(Duration::from_millis(millis as u64)),
+            _ => None,
+        }
+    }
+
+    /// Rejects values the controller would answer with `InvalidSpeedValue`.
+    pub fn validate(&self) -> Result<(), FrcError> {
+        let range = match self {
+            Speed::MmSec(_) => MM_SEC_RANGE,
+            Speed::InchMin(_) => INCH_MIN_RANGE,
+            Speed::Percent(_) => PERCENT_RANGE,
+            Speed::Time(_) | Speed::Millis(_) => 1..=u16::MAX,
+        };
+        if range.contains(&self.value()) {
+            Ok(())
+        } else {
+            Err(FrcError::InvalidInstruction(format!(
+                "{:?} speed {} is outside {:?}",
+                self.speed_type(),
+                self.value(),
+                range
+            )))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpeedPair {
+    speed_type: SpeedType,
+    speed: u16,
+}
+
+impl From<Speed> for SpeedPair {
+    fn from(speed: Speed) -> Self {
+        SpeedPair { speed_type: speed.speed_type(), speed: speed.value() }
+    }
+}
+
+impl TryFrom<SpeedPair> for Speed {
+    type Error = String;
+
+    fn try_from(pair: SpeedPair) -> Result<Self, Self::Error> {
+        let speed = match pair.speed_type {
+            SpeedType::MMSec => Speed::MmSec(pair.speed),
+            SpeedType::InchMin => Speed::InchMin(pair.speed),
+            SpeedType::Time => Speed::Time(pair.speed),
+            SpeedType::MilliSeconds => Speed::Millis(pair.speed),
+            SpeedType::Percent => Speed::Percent(pair.speed),
+        };
+        speed.validate().map_err(|e| e.to_string())?;
+        Ok(speed)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum FrcError{
     Serialization(String),
     UnrecognizedPacket,
-    FanucErrorCode(FanucErrorCode),
+    /// The controller answered `packet` with a non-zero `ErrorID`.
+    FanucErrorCode {
+        code: FanucErrorCode,
+        /// RMI name of the command or instruction that failed, e.g. `FRC_Initialize`.
+        packet: String,
+        /// Sequence ID of the failed instruction; `None` for commands.
+        sequence_id: Option<u32>,
+    },
     FailedToSend(String),
     FailedToRecieve(String),
-    Disconnected()
+    Disconnected(),
+    /// An instruction with this sequence ID is still in flight, so a second
+    /// one was not sent.
+    DuplicateSequenceId(u32),
+    /// The controller reported `FRC_SystemFault` while the instruction with this sequence ID was running.
+    SystemFault(u32),
+    /// The controller sent `FRC_Terminate` and closed the RMI session.
+    Terminated,
+    /// A `Configuration` was rejected before being sent.
+    InvalidConfiguration(String),
+    /// An instruction parameter was rejected before being sent.
+    InvalidInstruction(String),
+    /// A group number or group mask was rejected before being sent.
+    InvalidGroup(String),
+    /// A G-code program could not be loaded; `line` is 1-based, 0 when the file could not be read.
+    Gcode { line: usize, message: String },
+    /// A program could not be loaded or written; `step` is the 0-based index of the step or packet.
+    Program { step: Option<usize>, message: String },
+    /// A TP listing could not be read; `line` is 1-based.
+    Ls { line: usize, message: String },
+}
+impl FrcError {
+    /// Builds the error for a response that came back with a non-zero `ErrorID`.
+    pub fn from_error_id(error_id: u32, packet: impl Into<String>, sequence_id: Option<u32>) -> Self {
+        FrcError::FanucErrorCode {
+            code: FanucErrorCode::from_id(error_id),
+            packet: packet.into(),
+            sequence_id,
+        }
+    }
+
+    /// The controller error code, if this error came from an `ErrorID`.
+    pub fn error_code(&self) -> Option<&FanucErrorCode> {
+        match self {
+            FrcError::FanucErrorCode { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Whether the session can carry on, possibly after a reset.
+    ///
+    /// Transport failures and `FRC_Terminate` are not recoverable on this
+    /// connection; a system fault needs a reset.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            FrcError::FanucErrorCode { code, .. } => code.is_recoverable(),
+            FrcError::SystemFault(_) => true,
+            FrcError::Serialization(_) | FrcError::UnrecognizedPacket | FrcError::InvalidConfiguration(_) | FrcError::InvalidInstruction(_) | FrcError::InvalidGroup(_) | FrcError::DuplicateSequenceId(_) | FrcError::Gcode { .. } | FrcError::Program { .. } | FrcError::Ls { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub fn requires_reset(&self) -> bool {
+        match self {
+            FrcError::FanucErrorCode { code, .. } => code.requires_reset(),
+            FrcError::SystemFault(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn requires_power_cycle(&self) -> bool {
+        match self {
+            FrcError::FanucErrorCode { code, .. } => code.requires_power_cycle(),
+            _ => false,
+        }
+    }
 }
+
 impl Error for FrcError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         None
@@ -116,84 +403,233 @@ impl fmt::Display for FrcError {
         match *self {
             FrcError::Serialization(ref msg) => write!(f, "Serialization error: {}", msg),
             FrcError::UnrecognizedPacket => write!(f, "Fanuc threw a unrecognized "),
-            FrcError::FanucErrorCode(ref code) => write!(f, "fanuc returned  error#: {}", code.message()),
+            FrcError::FanucErrorCode { ref code, ref packet, sequence_id: Some(sequence_id) } => write!(f, "fanuc returned error {} for {} (sequence id {})", code, packet, sequence_id),
+            FrcError::FanucErrorCode { ref code, ref packet, sequence_id: None } => write!(f, "fanuc returned error {} for {}", code, packet),
             FrcError::FailedToSend(ref msg) => write!(f, "SendError: {}", msg),
             FrcError::FailedToRecieve(ref msg) => write!(f, "RecieveError: {}", msg),
             FrcError::Disconnected() => write!(f, "Fanuc appears to be disconnected"),
+            FrcError::DuplicateSequenceId(sequence_id) => write!(f, "sequence id {} is already in flight", sequence_id),
+            FrcError::SystemFault(sequence_id) => write!(f, "Fanuc reported a system fault at sequence id {}", sequence_id),
+            FrcError::Terminated => write!(f, "Fanuc terminated the RMI session"),
+            FrcError::InvalidConfiguration(ref msg) => write!(f, "Invalid configuration: {}", msg),
+            FrcError::InvalidInstruction(ref msg) => write!(f, "Invalid instruction: {}", msg),
+            FrcError::InvalidGroup(ref msg) => write!(f, "Invalid group: {}", msg),
+            FrcError::Gcode { line, ref message } => write!(f, "G-code line {}: {}", line, message),
+            FrcError::Program { step: Some(step), ref message } => write!(f, "Program step {}: {}", step, message),
+            FrcError::Program { step: None, ref message } => write!(f, "Program: {}", message),
+            FrcError::Ls { line, ref message } => write!(f, "LS line {}: {}", line, message),
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(untagged)]
-
 pub enum PacketEnum {
     Communication(Communication),
     Command(Command),
     Instruction(Instruction)
 }
 
-#[repr(u32)]
-#[derive(Debug, Serialize, Deserialize, IntEnum)]
-enum FanucErrorCode {
-    InternalSystemError = 2556929,
-    InvalidUToolNumber = 2556930,
-    InvalidUFrameNumber = 2556931,
-    InvalidPositionRegister = 2556932,
-    InvalidSpeedOverride = 2556933,
-    CannotExecuteTPProgram = 2556934,
-    ControllerServoOff = 2556935,
-    CannotExecuteTPProgramDuplicate = 2556936,
-    RMINotRunning = 2556937,
-    TPProgramNotPaused = 2556938,
-    CannotResumeTPProgram = 2556939,
-    CannotResetController = 2556940,
-    InvalidRMICommand = 2556941,
-    RMICommandFail = 2556942,
-    InvalidControllerState = 2556943,
-    PleaseCyclePower = 2556944,
-    InvalidPayloadSchedule = 2556945,
-    InvalidMotionOption = 2556946,
-    InvalidVisionRegister = 2556947,
-    InvalidRMIInstruction = 2556948,
-    InvalidValue = 2556949,
-    InvalidTextString = 2556950,
-    InvalidPositionData = 2556951,
-    RMIInHoldState = 2556952,
-    RemoteDeviceDisconnected = 2556953,
-    RobotAlreadyConnected = 2556954,
-    WaitForCommandDone = 2556955,
-    WaitForInstructionDone = 2556956,
-    InvalidSequenceIDNumber = 2556957,
-    InvalidSpeedType = 2556958,
-    InvalidSpeedValue = 2556959,
-    InvalidTermType = 2556960,
-    InvalidTermValue = 2556961,
-    InvalidLCBPortType = 2556962,
-    InvalidACCValue = 2556963,
-    InvalidDestinationPosition = 2556964,
-    InvalidVIAPosition = 2556965,
-    InvalidPortNumber = 2556966,
-    InvalidGroupNumber = 2556967,
-    InvalidGroupMask = 2556968,
-    JointMotionWithCOORD = 2556969,
-    IncrementalMotionWithCOORD = 2556970,
-    RobotInSingleStepMode = 2556971,
-    InvalidPositionDataType = 2556972,
-    ReadyForASCIIPacket = 2556973,
-    ASCIIConversionFailed = 2556974,
-    InvalidASCIIInstruction = 2556975,
-    InvalidNumberOfGroups = 2556976,
-    InvalidInstructionPacket = 2556977,
-    InvalidASCIIStringPacket = 2556978,
-    InvalidASCIIStringSize = 2556979,
-    InvalidApplicationTool = 2556980,
-    InvalidCallProgramName = 2556981,
-    UnrecognizedFrcError = 0,
+/// Error codes returned in the `ErrorID` field of RMI responses.
+///
+/// Codes this crate does not know about are kept as `Unrecognized` with the
+/// raw ID so they can still be looked up in the controller manual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FanucErrorCode {
+    InternalSystemError,
+    InvalidUToolNumber,
+    InvalidUFrameNumber,
+    InvalidPositionRegister,
+    InvalidSpeedOverride,
+    CannotExecuteTPProgram,
+    ControllerServoOff,
+    CannotExecuteTPProgramDuplicate,
+    RMINotRunning,
+    TPProgramNotPaused,
+    CannotResumeTPProgram,
+    CannotResetController,
+    InvalidRMICommand,
+    RMICommandFail,
+    InvalidControllerState,
+    PleaseCyclePower,
+    InvalidPayloadSchedule,
+    InvalidMotionOption,
+    InvalidVisionRegister,
+    InvalidRMIInstruction,
+    InvalidValue,
+    InvalidTextString,
+    InvalidPositionData,
+    RMIInHoldState,
+    RemoteDeviceDisconnected,
+    RobotAlreadyConnected,
+    WaitForCommandDone,
+    WaitForInstructionDone,
+    InvalidSequenceIDNumber,
+    InvalidSpeedType,
+    InvalidSpeedValue,
+    InvalidTermType,
+    InvalidTermValue,
+    InvalidLCBPortType,
+    InvalidACCValue,
+    InvalidDestinationPosition,
+    InvalidVIAPosition,
+    InvalidPortNumber,
+    InvalidGroupNumber,
+    InvalidGroupMask,
+    JointMotionWithCOORD,
+    IncrementalMotionWithCOORD,
+    RobotInSingleStepMode,
+    InvalidPositionDataType,
+    ReadyForASCIIPacket,
+    ASCIIConversionFailed,
+    InvalidASCIIInstruction,
+    InvalidNumberOfGroups,
+    InvalidInstructionPacket,
+    InvalidASCIIStringPacket,
+    InvalidASCIIStringSize,
+    InvalidApplicationTool,
+    InvalidCallProgramName,
+    Unrecognized(u32),
+}
+
+/// Broad classes of [`FanucErrorCode`], describing what it takes to get the
+/// controller accepting packets again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FanucErrorClass {
+    /// The packet itself was rejected; fix its contents and send it again.
+    InvalidRequest,
+    /// The controller is still busy with earlier work; retry once it finishes.
+    Busy,
+    /// The controller is in a state that needs `FRC_Reset`/`FRC_Abort` and a new `FRC_Initialize`.
+    ControllerState,
+    /// Only cycling power on the controller clears this.
+    Fatal,
+    /// The code is not one this crate knows about.
+    Unknown,
 }
 
 impl FanucErrorCode {
-    fn message(&self) -> &str {
+    /// Maps a raw `ErrorID` to its code, keeping unknown IDs as [`FanucErrorCode::Unrecognized`].
+    pub fn from_id(id: u32) -> Self {
+        match id {
+            2556929 => FanucErrorCode::InternalSystemError,
+            2556930 => FanucErrorCode::InvalidUToolNumber,
+            2556931 => FanucErrorCode::InvalidUFrameNumber,
+            2556932 => FanucErrorCode::InvalidPositionRegister,
+            2556933 => FanucErrorCode::InvalidSpeedOverride,
+            2556934 => FanucErrorCode::CannotExecuteTPProgram,
+            2556935 => FanucErrorCode::ControllerServoOff,
+            2556936 => FanucErrorCode::CannotExecuteTPProgramDuplicate,
+            2556937 => FanucErrorCode::RMINotRunning,
+            2556938 => FanucErrorCode::TPProgramNotPaused,
+            2556939 => FanucErrorCode::CannotResumeTPProgram,
+            2556940 => FanucErrorCode::CannotResetController,
+            2556941 => FanucErrorCode::InvalidRMICommand,
+            2556942 => FanucErrorCode::RMICommandFail,
+            2556943 => FanucErrorCode::InvalidControllerState,
+            2556944 => FanucErrorCode::PleaseCyclePower,
+            2556945 => FanucErrorCode::InvalidPayloadSchedule,
+            2556946 => FanucErrorCode::InvalidMotionOption,
+            2556947 => FanucErrorCode::InvalidVisionRegister,
+            2556948 => FanucErrorCode::InvalidRMIInstruction,
+            2556949 => FanucErrorCode::InvalidValue,
+            2556950 => FanucErrorCode::InvalidTextString,
+            2556951 => FanucErrorCode::InvalidPositionData,
+            2556952 => FanucErrorCode::RMIInHoldState,
+            2556953 => FanucErrorCode::RemoteDeviceDisconnected,
+            2556954 => FanucErrorCode::RobotAlreadyConnected,
+            2556955 => FanucErrorCode::WaitForCommandDone,
+            2556956 => FanucErrorCode::WaitForInstructionDone,
+            2556957 => FanucErrorCode::InvalidSequenceIDNumber,
+            2556958 => FanucErrorCode::InvalidSpeedType,
+            2556959 => FanucErrorCode::InvalidSpeedValue,
+            2556960 => FanucErrorCode::InvalidTermType,
+            2556961 => FanucErrorCode::InvalidTermValue,
+            2556962 => FanucErrorCode::InvalidLCBPortType,
+            2556963 => FanucErrorCode::InvalidACCValue,
+            2556964 => FanucErrorCode::InvalidDestinationPosition,
+            2556965 => FanucErrorCode::InvalidVIAPosition,
+            2556966 => FanucErrorCode::InvalidPortNumber,
+            2556967 => FanucErrorCode::InvalidGroupNumber,
+            2556968 => FanucErrorCode::InvalidGroupMask,
+            2556969 => FanucErrorCode::JointMotionWithCOORD,
+            2556970 => FanucErrorCode::IncrementalMotionWithCOORD,
+            2556971 => FanucErrorCode::RobotInSingleStepMode,
+            2556972 => FanucErrorCode::InvalidPositionDataType,
+            2556973 => FanucErrorCode::ReadyForASCIIPacket,
+            2556974 => FanucErrorCode::ASCIIConversionFailed,
+            2556975 => FanucErrorCode::InvalidASCIIInstruction,
+            2556976 => FanucErrorCode::InvalidNumberOfGroups,
+            2556977 => FanucErrorCode::InvalidInstructionPacket,
+            2556978 => FanucErrorCode::InvalidASCIIStringPacket,
+            2556979 => FanucErrorCode::InvalidASCIIStringSize,
+            2556980 => FanucErrorCode::InvalidApplicationTool,
+            2556981 => FanucErrorCode::InvalidCallProgramName,
+            other => FanucErrorCode::Unrecognized(other),
+        }
+    }
+
+    /// The raw `ErrorID` value sent by the controller.
+    pub fn id(&self) -> u32 {
+        match *self {
+            FanucErrorCode::InternalSystemError => 2556929,
+            FanucErrorCode::InvalidUToolNumber => 2556930,
+            FanucErrorCode::InvalidUFrameNumber => 2556931,
+            FanucErrorCode::InvalidPositionRegister => 2556932,
+            FanucErrorCode::InvalidSpeedOverride => 2556933,
+            FanucErrorCode::CannotExecuteTPProgram => 2556934,
+            FanucErrorCode::ControllerServoOff => 2556935,
+            FanucErrorCode::CannotExecuteTPProgramDuplicate => 2556936,
+            FanucErrorCode::RMINotRunning => 2556937,
+            FanucErrorCode::TPProgramNotPaused => 2556938,
+            FanucErrorCode::CannotResumeTPProgram => 2556939,
+            FanucErrorCode::CannotResetController => 2556940,
+            FanucErrorCode::InvalidRMICommand => 2556941,
+            FanucErrorCode::RMICommandFail => 2556942,
+            FanucErrorCode::InvalidControllerState => 2556943,
+            FanucErrorCode::PleaseCyclePower => 2556944,
+            FanucErrorCode::InvalidPayloadSchedule => 2556945,
+            FanucErrorCode::InvalidMotionOption => 2556946,
+            FanucErrorCode::InvalidVisionRegister => 2556947,
+            FanucErrorCode::InvalidRMIInstruction => 2556948,
+            FanucErrorCode::InvalidValue => 2556949,
+            FanucErrorCode::InvalidTextString => 2556950,
+            FanucErrorCode::InvalidPositionData => 2556951,
+            FanucErrorCode::RMIInHoldState => 2556952,
+            FanucErrorCode::RemoteDeviceDisconnected => 2556953,
+            FanucErrorCode::RobotAlreadyConnected => 2556954,
+            FanucErrorCode::WaitForCommandDone => 2556955,
+            FanucErrorCode::WaitForInstructionDone => 2556956,
+            FanucErrorCode::InvalidSequenceIDNumber => 2556957,
+            FanucErrorCode::InvalidSpeedType => 2556958,
+            FanucErrorCode::InvalidSpeedValue => 2556959,
+            FanucErrorCode::InvalidTermType => 2556960,
+            FanucErrorCode::InvalidTermValue => 2556961,
+            FanucErrorCode::InvalidLCBPortType => 2556962,
+            FanucErrorCode::InvalidACCValue => 2556963,
+            FanucErrorCode::InvalidDestinationPosition => 2556964,
+            FanucErrorCode::InvalidVIAPosition => 2556965,
+            FanucErrorCode::InvalidPortNumber => 2556966,
+            FanucErrorCode::InvalidGroupNumber => 2556967,
+            FanucErrorCode::InvalidGroupMask => 2556968,
+            FanucErrorCode::JointMotionWithCOORD => 2556969,
+            FanucErrorCode::IncrementalMotionWithCOORD => 2556970,
+            FanucErrorCode::RobotInSingleStepMode => 2556971,
+            FanucErrorCode::InvalidPositionDataType => 2556972,
+            FanucErrorCode::ReadyForASCIIPacket => 2556973,
+            FanucErrorCode::ASCIIConversionFailed => 2556974,
+            FanucErrorCode::InvalidASCIIInstruction => 2556975,
+            FanucErrorCode::InvalidNumberOfGroups => 2556976,
+            FanucErrorCode::InvalidInstructionPacket => 2556977,
+            FanucErrorCode::InvalidASCIIStringPacket => 2556978,
+            FanucErrorCode::InvalidASCIIStringSize => 2556979,
+            FanucErrorCode::InvalidApplicationTool => 2556980,
+            FanucErrorCode::InvalidCallProgramName => 2556981,
+            FanucErrorCode::Unrecognized(id) => id,
+        }
+    }
+
+    pub fn message(&self) -> &str {
         match self {
             FanucErrorCode::InternalSystemError => "Internal System Error.",
             FanucErrorCode::InvalidUToolNumber => "Invalid UTool Number.",
@@ -248,9 +684,54 @@ impl FanucErrorCode {
             FanucErrorCode::InvalidASCIIStringSize => "Invalid ASCII string size.",
             FanucErrorCode::InvalidApplicationTool => "Invalid Application Tool.",
             FanucErrorCode::InvalidCallProgramName => "Invalid Call Program Name.",
-            FanucErrorCode::UnrecognizedFrcError => "Unrecognized FANUC Error ID",
+            FanucErrorCode::Unrecognized(_) => "Unrecognized FANUC Error ID",
+        }
+    }
+
+    pub fn class(&self) -> FanucErrorClass {
+        match self {
+            FanucErrorCode::InternalSystemError
+            | FanucErrorCode::PleaseCyclePower
+            | FanucErrorCode::CannotResetController => FanucErrorClass::Fatal,
+            FanucErrorCode::ControllerServoOff
+            | FanucErrorCode::RMINotRunning
+            | FanucErrorCode::RMICommandFail
+            | FanucErrorCode::InvalidControllerState
+            | FanucErrorCode::RMIInHoldState
+            | FanucErrorCode::RemoteDeviceDisconnected
+            | FanucErrorCode::RobotInSingleStepMode => FanucErrorClass::ControllerState,
+            FanucErrorCode::WaitForCommandDone
+            | FanucErrorCode::WaitForInstructionDone
+            | FanucErrorCode::ReadyForASCIIPacket
+            | FanucErrorCode::RobotAlreadyConnected => FanucErrorClass::Busy,
+            FanucErrorCode::Unrecognized(_) => FanucErrorClass::Unknown,
+            _ => FanucErrorClass::InvalidRequest,
         }
     }
+
+    /// Whether the session can carry on without a power cycle.
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(self.class(), FanucErrorClass::Fatal | FanucErrorClass::Unknown)
+    }
+
+    /// Whether the controller has to be reset and re-initialized before it accepts motion again.
+    pub fn requires_reset(&self) -> bool {
+        self.class() == FanucErrorClass::ControllerState
+    }
+
+    pub fn requires_power_cycle(&self) -> bool {
+        self.class() == FanucErrorClass::Fatal
+    }
 }
 
+impl From<u32> for FanucErrorCode {
+    fn from(id: u32) -> Self {
+        FanucErrorCode::from_id(id)
+    }
+}
 
+impl fmt::Display for FanucErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message(), self.id())
+    }
+}
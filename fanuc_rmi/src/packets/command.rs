@@ -29,7 +29,7 @@ pub enum Command {
     #[serde(rename = "FRC_ReadPositionRegister")]
     FrcReadPositionRegister(FrcReadPositionRegister),
 
-    #[serde(rename = "FrcWritePositionRegister")]
+    #[serde(rename = "FRC_WritePositionRegister")]
     FrcWritePositionRegister(FrcWritePositionRegister),
 
     #[serde(rename = "FRC_SetOverride")]
@@ -41,6 +41,9 @@ pub enum Command {
     #[serde(rename = "FRC_GetUFrameUTool")]
     FrcGetUFrameUTool(FrcGetUFrameUTool),
 
+    #[serde(rename = "FRC_ReadUToolData")]
+    FrcReadUToolData(FrcReadUToolData),
+
     #[serde(rename = "FRC_WriteUToolData")]
     FrcWriteUToolData(FrcWriteUToolData),
 
@@ -106,7 +109,7 @@ pub enum CommandResponse {
     FrcReadUToolData(FrcReadUToolDataResponse),
 
     #[serde(rename = "FRC_WriteUToolData")]
-    FrcWriteUToolData(FrcWriteUToolData),
+    FrcWriteUToolData(FrcWriteUToolDataResponse),
 
     #[serde(rename = "FRC_ReadDIN")]
     FrcReadDIN(FrcReadDINResponse),
@@ -135,8 +138,12 @@ pub enum CommandResponse {
     #[serde(rename = "FRC_ReadTCPSpeed")]
     FrcReadTCPSpeed(FrcReadTCPSpeedResponse),
 
-
+    /// A packet this crate has no variant for, kept in [`ParseMode::Lenient`](super::ParseMode).
+    #[serde(skip)]
+    Unknown { name: String, raw: serde_json::Value },
 }
 
+super::impl_response!(CommandResponse, "Command");
+
 
 impl Packet for Command{}
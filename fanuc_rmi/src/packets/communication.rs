@@ -24,11 +24,17 @@ pub enum CommunicationResponse {
     #[serde(rename = "FRC_Disconnect")]
     FrcDisconnect(FrcDisconnectResponse),
     #[serde(rename = "FRC_Terminate")]
-    FrcTerminate,
+    FrcTerminate(FrcTerminateResponse),
     #[serde(rename = "FRC_SystemFault")]
-    FrcSystemFault,
+    FrcSystemFault(FrcSystemFault),
+
+    /// A packet this crate has no variant for, kept in [`ParseMode::Lenient`](super::ParseMode).
+    #[serde(skip)]
+    Unknown { name: String, raw: serde_json::Value },
 }
 
+super::impl_response!(CommunicationResponse, "Communication");
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FrcConnectResponse {
     #[serde(rename = "ErrorID")]
@@ -47,6 +53,11 @@ pub struct FrcDisconnectResponse {
     pub error_id: u32,
 }
 #[derive(Serialize, Deserialize, Debug)]
+pub struct FrcTerminateResponse {
+    #[serde(rename = "ErrorID", default)]
+    pub error_id: u32,
+}
+#[derive(Serialize, Deserialize, Debug)]
 pub struct FrcSystemFault {
     #[serde(rename = "SequenceID")]
     pub sequence_id: u32,
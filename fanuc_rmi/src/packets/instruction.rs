@@ -1,6 +1,7 @@
 use serde::{Serialize,Deserialize};
 use super::Packet;
 use crate::instructions::*;
+use crate::{FrcError, Speed, Termination};
 
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -89,11 +90,152 @@ pub enum InstructionResponse {
     #[serde(rename = "FRC_JointRelativeJRep")]
     FrcJointRelativeJRep(FrcJointRelativeJRepResponse),
     #[serde(rename = "FRC_LinearMotionJRep")]
-    FrcLinearMotionJRep(FrcLinearMotionJRepResponse), 
+    FrcLinearMotionJRep(FrcLinearMotionJRepResponse),
+    /// A packet this crate has no variant for, kept in [`ParseMode::Lenient`](super::ParseMode).
+    #[serde(skip)]
+    Unknown { name: String, raw: serde_json::Value },
+}
+
+super::impl_response!(InstructionResponse, "Instruction");
+
+
+impl Instruction {
+    pub fn get_sequence_id(&self) -> u32 {
+        match self {
+            Instruction::FrcWaitDIN(instruction) => instruction.sequence_id,
+            Instruction::FrcSetUFrame(instruction) => instruction.sequence_id,
+            Instruction::FrcSetUTool(instruction) => instruction.sequence_id,
+            Instruction::FrcWaitTime(instruction) => instruction.sequence_id,
+            Instruction::FrcSetPayLoad(instruction) => instruction.sequence_id,
+            Instruction::FrcCall(instruction) => instruction.sequence_id,
+            Instruction::FrcLinearMotion(instruction) => instruction.sequence_id,
+            Instruction::FrcLinearRelative(instruction) => instruction.sequence_id,
+            Instruction::FrcLinearRelativeJRep(instruction) => instruction.sequence_id,
+            Instruction::FrcJointMotion(instruction) => instruction.sequence_id,
+            Instruction::FrcJointRelative(instruction) => instruction.sequence_id,
+            Instruction::FrcCircularMotion(instruction) => instruction.sequence_id,
+            Instruction::FrcCircularRelative(instruction) => instruction.sequence_id,
+            Instruction::FrcJointMotionJRep(instruction) => instruction.sequence_id,
+            Instruction::FrcJointRelativeJRep(instruction) => instruction.sequence_id,
+            Instruction::FrcLinearMotionJRep(instruction) => instruction.sequence_id,
+        }
+    }
+
+    pub fn set_sequence_id(&mut self, sequence_id: u32) {
+        match self {
+            Instruction::FrcWaitDIN(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcSetUFrame(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcSetUTool(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcWaitTime(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcSetPayLoad(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcCall(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcLinearMotion(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcLinearRelative(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcLinearRelativeJRep(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcJointMotion(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcJointRelative(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcCircularMotion(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcCircularRelative(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcJointMotionJRep(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcJointRelativeJRep(instruction) => instruction.sequence_id = sequence_id,
+            Instruction::FrcLinearMotionJRep(instruction) => instruction.sequence_id = sequence_id,
+        }
+    }
+
+    /// Checks the configuration, speed, termination and options of motion
+    /// instructions against the ranges the controller accepts.
+    pub fn validate(&self) -> Result<(), FrcError> {
+        match self {
+            Instruction::FrcLinearMotion(instruction) => {
+                instruction.configuration.validate()?;
+                validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options)
+            }
+            Instruction::FrcLinearRelative(instruction) => {
+                instruction.configuration.validate()?;
+                validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options)
+            }
+            Instruction::FrcJointMotion(instruction) => {
+                instruction.configuration.validate()?;
+                validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options)
+            }
+            Instruction::FrcJointRelative(instruction) => {
+                instruction.configuration.validate()?;
+                validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options)
+            }
+            Instruction::FrcCircularMotion(instruction) => {
+                instruction.configuration.validate()?;
+                instruction.via_configuration.validate()?;
+                validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options)
+            }
+            Instruction::FrcCircularRelative(instruction) => {
+                instruction.configuration.validate()?;
+                instruction.via_configuration.validate()?;
+                validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options)
+            }
+            Instruction::FrcLinearMotionJRep(instruction) => validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options),
+            Instruction::FrcLinearRelativeJRep(instruction) => validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options),
+            Instruction::FrcJointMotionJRep(instruction) => validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options),
+            Instruction::FrcJointRelativeJRep(instruction) => validate_motion(self, &instruction.speed, &instruction.termination, &instruction.options),
+            _ => Ok(()),
+        }
+    }
 
+    /// Whether this is one of the `FRC_Joint*` motion instructions.
+    pub fn is_joint_motion(&self) -> bool {
+        matches!(
+            self,
+            Instruction::FrcJointMotion(_)
+                | Instruction::FrcJointRelative(_)
+                | Instruction::FrcJointMotionJRep(_)
+                | Instruction::FrcJointRelativeJRep(_)
+        )
+    }
+}
+
+fn validate_motion(
+    instruction: &Instruction,
+    speed: &Speed,
+    termination: &Termination,
+    options: &MotionOptions,
+) -> Result<(), FrcError> {
+    if matches!(speed, Speed::Percent(_)) && !instruction.is_joint_motion() {
+        return Err(FrcError::InvalidInstruction("percent speed is only valid for joint motion".to_string()));
+    }
+    speed.validate()?;
+    termination.validate()?;
+    options.validate()
+}
 
+macro_rules! impl_from_instruction {
+    ($($variant:ident),* $(,)?) => {
+        $(
+            impl From<$variant> for Instruction {
+                fn from(instruction: $variant) -> Self {
+                    Instruction::$variant(instruction)
+                }
+            }
+        )*
+    };
 }
 
+impl_from_instruction!(
+    FrcWaitDIN,
+    FrcSetUFrame,
+    FrcSetUTool,
+    FrcWaitTime,
+    FrcSetPayLoad,
+    FrcCall,
+    FrcLinearMotion,
+    FrcLinearRelative,
+    FrcLinearRelativeJRep,
+    FrcJointMotion,
+    FrcJointRelative,
+    FrcCircularMotion,
+    FrcCircularRelative,
+    FrcJointMotionJRep,
+    FrcJointRelativeJRep,
+    FrcLinearMotionJRep,
+);
 
 impl InstructionResponse {
     pub fn get_sequence_id(&self) -> u32 {
@@ -114,14 +256,64 @@ impl InstructionResponse {
             InstructionResponse::FrcJointMotionJRep(resp) => resp.sequence_id,
             InstructionResponse::FrcJointRelativeJRep(resp) => resp.sequence_id,
             InstructionResponse::FrcLinearMotionJRep(resp) => resp.sequence_id,
+            InstructionResponse::Unknown { raw, .. } => raw_u32(raw, "SequenceID"),
+        }
+    }
+
+    pub fn get_error_id(&self) -> u32 {
+        match self {
+            InstructionResponse::FrcWaitDIN(resp) => resp.error_id,
+            InstructionResponse::FrcSetUFrame(resp) => resp.error_id,
+            InstructionResponse::FrcSetUTool(resp) => resp.error_id,
+            InstructionResponse::FrcWaitTime(resp) => resp.error_id,
+            InstructionResponse::FrcSetPayLoad(resp) => resp.error_id,
+            InstructionResponse::FrcCall(resp) => resp.error_id,
+            InstructionResponse::FrcLinearMotion(resp) => resp.error_id,
+            InstructionResponse::FrcLinearRelative(resp) => resp.error_id,
+            InstructionResponse::FrcLinearRelativeJRep(resp) => resp.error_id,
+            InstructionResponse::FrcJointMotion(resp) => resp.error_id,
+            InstructionResponse::FrcJointRelative(resp) => resp.error_id,
+            InstructionResponse::FrcCircularMotion(resp) => resp.error_id,
+            InstructionResponse::FrcCircularRelative(resp) => resp.error_id,
+            InstructionResponse::FrcJointMotionJRep(resp) => resp.error_id,
+            InstructionResponse::FrcJointRelativeJRep(resp) => resp.error_id,
+            InstructionResponse::FrcLinearMotionJRep(resp) => resp.error_id,
+            InstructionResponse::Unknown { raw, .. } => raw_u32(raw, "ErrorID"),
+        }
+    }
+
+    /// RMI name of the instruction this response belongs to, e.g. `FRC_LinearMotion`.
+    pub fn get_name(&self) -> &str {
+        match self {
+            InstructionResponse::FrcWaitDIN(_) => "FRC_WaitDIN",
+            InstructionResponse::FrcSetUFrame(_) => "FRC_SetUFrame",
+            InstructionResponse::FrcSetUTool(_) => "FRC_SetUTool",
+            InstructionResponse::FrcWaitTime(_) => "FRC_WaitTime",
+            InstructionResponse::FrcSetPayLoad(_) => "FRC_SetPayLoad",
+            InstructionResponse::FrcCall(_) => "FRC_Call",
+            InstructionResponse::FrcLinearMotion(_) => "FRC_LinearMotion",
+            InstructionResponse::FrcLinearRelative(_) => "FRC_LinearRelative",
+            InstructionResponse::FrcLinearRelativeJRep(_) => "FRC_LinearRelativeJRep",
+            InstructionResponse::FrcJointMotion(_) => "FRC_JointMotion",
+            InstructionResponse::FrcJointRelative(_) => "FRC_JointRelative",
+            InstructionResponse::FrcCircularMotion(_) => "FRC_CircularMotion",
+            InstructionResponse::FrcCircularRelative(_) => "FRC_CircularRelative",
+            InstructionResponse::FrcJointMotionJRep(_) => "FRC_JointMotionJRep",
+            InstructionResponse::FrcJointRelativeJRep(_) => "FRC_JointRelativeJRep",
+            InstructionResponse::FrcLinearMotionJRep(_) => "FRC_LinearMotionJRep",
+            InstructionResponse::Unknown { name, .. } => name,
         }
     }
 }
 
+fn raw_u32(raw: &serde_json::Value, field: &str) -> u32 {
+    raw.get(field).and_then(|value| value.as_u64()).unwrap_or(0) as u32
+}
+
 
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OnOff{
     ON,
     OFF
@@ -6,7 +6,127 @@ pub use command::*;
 pub use communication::*;
 pub use instruction::*;
 
+use std::fmt;
+
+use serde::de::{self, value::MapDeserializer};
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::FrcError;
 
 pub trait Packet: Serialize + for<'de> Deserialize<'de> {}
 
+/// How response packets the crate does not fully model are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Packets with an unknown name, an unknown field or a field that does
+    /// not decode are rejected with `FrcError::Serialization`. Fields are
+    /// checked at every level, including inside `Configuration`, `Position`
+    /// and the other nested objects.
+    Strict,
+    /// Unknown fields are ignored and packets with a name the crate has no
+    /// variant for are kept as `Unknown` together with their raw JSON. Known
+    /// packets that do not decode are still rejected.
+    #[default]
+    Lenient,
+}
+
+/// The response enums: `CommunicationResponse`, `CommandResponse` and `InstructionResponse`.
+pub trait Response: Serialize + for<'de> Deserialize<'de> {
+    /// Key holding the packet name, e.g. `"Command"`.
+    const TAG: &'static str;
+
+    fn unknown(name: String, raw: Value) -> Self;
+
+    /// Name and raw JSON when this is the `Unknown` variant.
+    fn as_unknown(&self) -> Option<(&str, &Value)>;
+
+    fn parse(raw: Value, mode: ParseMode) -> Result<Self, FrcError> {
+        let name = raw.get(Self::TAG).and_then(Value::as_str).unwrap_or_default().to_string();
+        match Self::deserialize(&raw) {
+            Ok(packet) if mode == ParseMode::Strict => {
+                let known = serde_json::to_value(&packet).map_err(|e| FrcError::Serialization(e.to_string()))?;
+                match unknown_field(&raw, &known) {
+                    Some(field) => Err(FrcError::Serialization(format!("Unknown field `{}` in {}", field, name))),
+                    None => Ok(packet),
+                }
+            }
+            Ok(packet) => Ok(packet),
+            Err(_) if mode == ParseMode::Lenient && !Self::is_known(&name) => Ok(Self::unknown(name, raw)),
+            Err(e) => Err(FrcError::Serialization(format!("Could not parse response: {}", e))),
+        }
+    }
+
+    /// Whether `name` is one of this enum's packet names.
+    fn is_known(name: &str) -> bool {
+        let tag_only = MapDeserializer::<_, TagProbe>::new(std::iter::once((Self::TAG, name)));
+        !matches!(Self::deserialize(tag_only), Err(TagProbe { unknown_variant: true }))
+    }
+
+    fn parse_str(response: &str, mode: ParseMode) -> Result<Self, FrcError> {
+        let raw = serde_json::from_str(response).map_err(|e| FrcError::Serialization(format!("Could not parse response: {}", e)))?;
+        Self::parse(raw, mode)
+    }
+}
+
+/// Path of the first field of `raw` that the decoded packet, serialized back
+/// as `known`, does not have, searching nested objects and arrays.
+fn unknown_field(raw: &Value, known: &Value) -> Option<String> {
+    match (raw, known) {
+        (Value::Object(fields), Value::Object(known)) => fields.iter().find_map(|(field, value)| match known.get(field) {
+            None => Some(field.clone()),
+            Some(known) => unknown_field(value, known).map(|inner| format!("{}.{}", field, inner)),
+        }),
+        (Value::Array(items), Value::Array(known)) => items
+            .iter()
+            .zip(known)
+            .enumerate()
+            .find_map(|(index, (item, known))| unknown_field(item, known).map(|inner| format!("{}.{}", index, inner))),
+        _ => None,
+    }
+}
+
+/// Deserializer error that only records whether the packet name was unknown.
+#[derive(Debug)]
+struct TagProbe {
+    unknown_variant: bool,
+}
+
+impl de::Error for TagProbe {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        TagProbe { unknown_variant: false }
+    }
+
+    fn unknown_variant(_: &str, _: &'static [&'static str]) -> Self {
+        TagProbe { unknown_variant: true }
+    }
+}
+
+impl fmt::Display for TagProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.unknown_variant { "unknown packet name" } else { "packet did not decode" })
+    }
+}
+
+impl std::error::Error for TagProbe {}
+
+/// Implements [`Response`] for an enum with an `Unknown { name, raw }` variant.
+macro_rules! impl_response {
+    ($response:ident, $tag:literal) => {
+        impl crate::packets::Response for $response {
+            const TAG: &'static str = $tag;
+
+            fn unknown(name: String, raw: serde_json::Value) -> Self {
+                $response::Unknown { name, raw }
+            }
+
+            fn as_unknown(&self) -> Option<(&str, &serde_json::Value)> {
+                match self {
+                    $response::Unknown { name, raw } => Some((name, raw)),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+use impl_response;
@@ -0,0 +1,303 @@
+//! Pose algebra for FANUC Cartesian data.
+//!
+//! FANUC orientations are W/P/R angles in degrees, applied as fixed-axis
+//! rotations about X (W), then Y (P), then Z (R). The matching rotation
+//! matrix is `Rz(r) * Ry(p) * Rx(w)`. All math is done in `f64` and rounded
+//! back to `f32` only when a [`Position`] or [`FrameData`] is produced.
+
+use crate::{FrameData, Position};
+
+/// A 3x3 row-major rotation matrix.
+pub type Matrix3 = [[f64; 3]; 3];
+
+/// Below this `cos(P)` the W/P/R decomposition is treated as gimbal locked.
+const GIMBAL_EPSILON: f64 = 1e-9;
+
+/// Converts FANUC W/P/R angles in degrees to a rotation matrix.
+pub fn wpr_to_matrix(w: f64, p: f64, r: f64) -> Matrix3 {
+    let (sw, cw) = w.to_radians().sin_cos();
+    let (sp, cp) = p.to_radians().sin_cos();
+    let (sr, cr) = r.to_radians().sin_cos();
+
+    [
+        [cr * cp, cr * sp * sw - sr * cw, cr * sp * cw + sr * sw],
+        [sr * cp, sr * sp * sw + cr * cw, sr * sp * cw - cr * sw],
+        [-sp, cp * sw, cp * cw],
+    ]
+}
+
+/// Converts a rotation matrix to FANUC W/P/R angles in degrees.
+///
+/// At `P = ±90°` only `W - R` (or `W + R`) is defined; `W` is then reported
+/// as 0 and the whole rotation is put into `R`.
+pub fn matrix_to_wpr(m: &Matrix3) -> (f64, f64, f64) {
+    let cp = (m[0][0] * m[0][0] + m[1][0] * m[1][0]).sqrt();
+    let p = (-m[2][0]).atan2(cp);
+
+    if cp > GIMBAL_EPSILON {
+        let w = m[2][1].atan2(m[2][2]);
+        let r = m[1][0].atan2(m[0][0]);
+        (w.to_degrees(), p.to_degrees(), r.to_degrees())
+    } else {
+        let r = (-m[0][1]).atan2(m[1][1]);
+        (0.0, p.to_degrees(), r.to_degrees())
+    }
+}
+
+/// A unit quaternion `w + xi + yj + zk`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion { w: 1.0, x: 0.0, y: 0.0, z: 0.0 };
+
+    pub fn from_wpr(w: f64, p: f64, r: f64) -> Self {
+        Self::from_matrix(&wpr_to_matrix(w, p, r))
+    }
+
+    pub fn to_wpr(&self) -> (f64, f64, f64) {
+        matrix_to_wpr(&self.to_matrix())
+    }
+
+    pub fn from_matrix(m: &Matrix3) -> Self {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion { w: 0.25 * s, x: (m[2][1] - m[1][2]) / s, y: (m[0][2] - m[2][0]) / s, z: (m[1][0] - m[0][1]) / s }
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Quaternion { w: (m[2][1] - m[1][2]) / s, x: 0.25 * s, y: (m[0][1] + m[1][0]) / s, z: (m[0][2] + m[2][0]) / s }
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Quaternion { w: (m[0][2] - m[2][0]) / s, x: (m[0][1] + m[1][0]) / s, y: 0.25 * s, z: (m[1][2] + m[2][1]) / s }
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Quaternion { w: (m[1][0] - m[0][1]) / s, x: (m[0][2] + m[2][0]) / s, y: (m[1][2] + m[2][1]) / s, z: 0.25 * s }
+        };
+        q.normalized()
+    }
+
+    pub fn to_matrix(&self) -> Matrix3 {
+        let Quaternion { w, x, y, z } = self.normalized();
+        [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ]
+    }
+
+    pub fn normalized(&self) -> Self {
+        let norm = self.dot(self).sqrt();
+        if norm == 0.0 {
+            return Quaternion::IDENTITY;
+        }
+        Quaternion { w: self.w / norm, x: self.x / norm, y: self.y / norm, z: self.z / norm }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Spherical linear interpolation along the shorter arc; `t` runs from 0 (`self`) to 1 (`other`).
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Self {
+        let mut other = *other;
+        let mut dot = self.dot(&other);
+        if dot < 0.0 {
+            other = Quaternion { w: -other.w, x: -other.x, y: -other.y, z: -other.z };
+            dot = -dot;
+        }
+
+        // Nearly parallel, fall back to a normalized lerp to avoid dividing by ~0.
+        if dot > 0.9995 {
+            return Quaternion {
+                w: self.w + (other.w - self.w) * t,
+                x: self.x + (other.x - self.x) * t,
+                y: self.y + (other.y - self.y) * t,
+                z: self.z + (other.z - self.z) * t,
+            }
+            .normalized();
+        }
+
+        let theta = dot.acos();
+        let a = ((1.0 - t) * theta).sin() / theta.sin();
+        let b = (t * theta).sin() / theta.sin();
+        Quaternion {
+            w: a * self.w + b * other.w,
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+        }
+    }
+}
+
+/// A rigid transform: rotate by `rotation`, then translate by `translation` (mm).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub rotation: Matrix3,
+    pub translation: [f64; 3],
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        translation: [0.0, 0.0, 0.0],
+    };
+
+    pub fn from_xyzwpr(x: f64, y: f64, z: f64, w: f64, p: f64, r: f64) -> Self {
+        Transform { rotation: wpr_to_matrix(w, p, r), translation: [x, y, z] }
+    }
+
+    /// Returns `[x, y, z, w, p, r]`.
+    pub fn to_xyzwpr(&self) -> [f64; 6] {
+        let (w, p, r) = matrix_to_wpr(&self.rotation);
+        let [x, y, z] = self.translation;
+        [x, y, z, w, p, r]
+    }
+
+    /// `self * other`: applies `other` first, then `self`.
+    pub fn compose(&self, other: &Transform) -> Transform {
+        let a = &self.rotation;
+        let b = &other.rotation;
+        let mut rotation = [[0.0; 3]; 3];
+        for (i, row) in rotation.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+            }
+        }
+        Transform { rotation, translation: self.transform_point(other.translation) }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let m = &self.rotation;
+        let rotation = [
+            [m[0][0], m[1][0], m[2][0]],
+            [m[0][1], m[1][1], m[2][1]],
+            [m[0][2], m[1][2], m[2][2]],
+        ];
+        let t = self.translation;
+        let translation = [
+            -(rotation[0][0] * t[0] + rotation[0][1] * t[1] + rotation[0][2] * t[2]),
+            -(rotation[1][0] * t[0] + rotation[1][1] * t[1] + rotation[1][2] * t[2]),
+            -(rotation[2][0] * t[0] + rotation[2][1] * t[1] + rotation[2][2] * t[2]),
+        ];
+        Transform { rotation, translation }
+    }
+
+    pub fn transform_point(&self, point: [f64; 3]) -> [f64; 3] {
+        let m = &self.rotation;
+        let t = self.translation;
+        [
+            m[0][0] * point[0] + m[0][1] * point[1] + m[0][2] * point[2] + t[0],
+            m[1][0] * point[0] + m[1][1] * point[1] + m[1][2] * point[2] + t[1],
+            m[2][0] * point[0] + m[2][1] * point[1] + m[2][2] * point[2] + t[2],
+        ]
+    }
+
+    /// Interpolates translation linearly and rotation by slerp; `t` runs from 0 to 1.
+    pub fn interpolate(&self, other: &Transform, t: f64) -> Transform {
+        let q = Quaternion::from_matrix(&self.rotation).slerp(&Quaternion::from_matrix(&other.rotation), t);
+        let translation = [
+            lerp(self.translation[0], other.translation[0], t),
+            lerp(self.translation[1], other.translation[1], t),
+            lerp(self.translation[2], other.translation[2], t),
+        ];
+        Transform { rotation: q.to_matrix(), translation }
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+impl Position {
+    pub fn to_transform(&self) -> Transform {
+        Transform::from_xyzwpr(self.x as f64, self.y as f64, self.z as f64, self.w as f64, self.p as f64, self.r as f64)
+    }
+
+    /// Builds a position from `transform`, carrying over the extended axes from `ext`.
+    pub fn from_transform(transform: &Transform, ext: [f32; 3]) -> Self {
+        let [x, y, z, w, p, r] = transform.to_xyzwpr();
+        Position {
+            x: x as f32,
+            y: y as f32,
+            z: z as f32,
+            w: w as f32,
+            p: p as f32,
+            r: r as f32,
+            ext1: ext[0],
+            ext2: ext[1],
+            ext3: ext[2],
+        }
+    }
+
+    fn ext(&self) -> [f32; 3] {
+        [self.ext1, self.ext2, self.ext3]
+    }
+
+    pub fn to_quaternion(&self) -> Quaternion {
+        Quaternion::from_wpr(self.w as f64, self.p as f64, self.r as f64)
+    }
+
+    /// `self * other`, e.g. a user frame composed with a position taught in it.
+    /// Extended axes are taken from `other`.
+    pub fn compose(&self, other: &Position) -> Position {
+        Position::from_transform(&self.to_transform().compose(&other.to_transform()), other.ext())
+    }
+
+    pub fn inverse(&self) -> Position {
+        Position::from_transform(&self.to_transform().inverse(), self.ext())
+    }
+
+    /// Maps a point expressed in this pose's frame into the parent frame.
+    pub fn transform_point(&self, point: [f64; 3]) -> [f64; 3] {
+        self.to_transform().transform_point(point)
+    }
+
+    /// Re-expresses a position taught in user frame `from` in user frame `to`.
+    /// Both frames are given relative to the world frame.
+    pub fn change_frame(&self, from: &FrameData, to: &FrameData) -> Position {
+        let world = from.to_transform().compose(&self.to_transform());
+        Position::from_transform(&to.to_transform().inverse().compose(&world), self.ext())
+    }
+
+    /// Blends towards `other` with `t` from 0 to 1; extended axes are blended linearly.
+    pub fn interpolate(&self, other: &Position, t: f64) -> Position {
+        let transform = self.to_transform().interpolate(&other.to_transform(), t);
+        let ext = [
+            lerp(self.ext1 as f64, other.ext1 as f64, t) as f32,
+            lerp(self.ext2 as f64, other.ext2 as f64, t) as f32,
+            lerp(self.ext3 as f64, other.ext3 as f64, t) as f32,
+        ];
+        Position::from_transform(&transform, ext)
+    }
+}
+
+impl FrameData {
+    pub fn to_transform(&self) -> Transform {
+        Transform::from_xyzwpr(self.x as f64, self.y as f64, self.z as f64, self.w as f64, self.p as f64, self.r as f64)
+    }
+
+    pub fn from_transform(transform: &Transform) -> Self {
+        let [x, y, z, w, p, r] = transform.to_xyzwpr();
+        FrameData { x: x as f32, y: y as f32, z: z as f32, w: w as f32, p: p as f32, r: r as f32 }
+    }
+
+    pub fn compose(&self, other: &FrameData) -> FrameData {
+        FrameData::from_transform(&self.to_transform().compose(&other.to_transform()))
+    }
+
+    pub fn inverse(&self) -> FrameData {
+        FrameData::from_transform(&self.to_transform().inverse())
+    }
+}
+
+impl From<&Position> for FrameData {
+    fn from(pos: &Position) -> Self {
+        FrameData { x: pos.x, y: pos.y, z: pos.z, w: pos.w, p: pos.p, r: pos.r }
+    }
+}
@@ -0,0 +1,533 @@
+use std::time::Duration;
+
+use crate::instructions::{CircularArc, MotionOptions};
+use crate::kinematics::{ArmConfig, RobotModel};
+use crate::packets::{Command, Instruction};
+use crate::{Configuration, FrameData, JointAngles, PacketEnum, Position, Speed, Termination, MM_SEC_RANGE};
+
+use super::state::ProgramState;
+
+/// Time to ramp up to the programmed speed at `ACC 100`.
+pub const DEFAULT_ACCEL_TIME: Duration = Duration::from_millis(250);
+/// Orientation speed of linear and circular moves, in degrees per second.
+pub const DEFAULT_ROTATION_SPEED: f64 = 90.0;
+
+/// How long one packet of a program takes, from [`CycleTimeEstimator::estimate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepTime {
+    pub step: usize,
+    /// Set for instructions.
+    pub sequence_id: Option<u32>,
+    /// With the time saved by blending into the next motion already taken off.
+    pub duration: Duration,
+    /// Set when the time depends on something only known at run time, such as
+    /// `FRC_WaitDIN` or the position a move starts from; `duration` is then a
+    /// lower bound.
+    pub lower_bound: bool,
+}
+
+/// Per-step and total time of a program.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CycleTime {
+    pub steps: Vec<StepTime>,
+    pub total: Duration,
+}
+
+impl CycleTime {
+    /// Whether every step has a definite time.
+    pub fn is_exact(&self) -> bool {
+        self.steps.iter().all(|step| !step.lower_bound)
+    }
+}
+
+/// Estimates how long a program takes on a robot, without a controller.
+///
+/// Every motion follows a trapezoidal profile that ramps up and down over the
+/// acceleration time, shortened by the `ACC` option. Linear and circular moves
+/// run at their speed or at [`DEFAULT_ROTATION_SPEED`], whichever is slower for
+/// the move; joint moves take as long as their slowest axis at the rated joint
+/// speeds of the [`RobotModel`]. A `CNT` move overlaps its deceleration with
+/// the next move's acceleration in proportion to its value, and `CR` blends as
+/// `CNT 100`. The speed override applies to everything and follows
+/// `FRC_SetOverride` commands in the program.
+///
+/// ```
+/// use fanuc_rmi::instructions::Move;
+/// use fanuc_rmi::kinematics::RobotModel;
+/// use fanuc_rmi::program::CycleTimeEstimator;
+/// use fanuc_rmi::{Configuration, PacketEnum, Position};
+///
+/// let start = Position::new(500.0, 0.0, 300.0, 180.0, 0.0, 0.0);
+/// let queue: Vec<PacketEnum> = [200.0, 400.0]
+///     .into_iter()
+///     .zip(1..)
+///     .map(|(y, id)| {
+///         let to = Position { y, ..start };
+///         PacketEnum::Instruction(Move::linear().to(to).speed_mm_s(100).cnt(100).tool(0).build(id).unwrap().into())
+///     })
+///     .collect();
+///
+/// let estimate = CycleTimeEstimator::new(&RobotModel::M_10IA)
+///     .start(Configuration { u_tool_number: 0, ..Default::default() }, &start)
+///     .estimate(&queue);
+/// // 2 x 200 mm at 100 mm/sec; blending hides the ramp between the moves.
+/// assert!((estimate.total.as_secs_f64() - 4.25).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CycleTimeEstimator {
+    robot: RobotModel,
+    override_percent: u8,
+    accel_time: Duration,
+    rotation_speed: f64,
+    program: ProgramState,
+    start_joints: Option<JointAngles>,
+}
+
+impl CycleTimeEstimator {
+    pub fn new(robot: &RobotModel) -> Self {
+        Self {
+            robot: robot.clone(),
+            override_percent: 100,
+            accel_time: DEFAULT_ACCEL_TIME,
+            rotation_speed: DEFAULT_ROTATION_SPEED,
+            program: ProgramState::default(),
+            start_joints: None,
+        }
+    }
+
+    /// Speed override in percent at the start of the program, 1 to 100.
+    pub fn override_percent(mut self, percent: u8) -> Self {
+        self.override_percent = percent.clamp(1, 100);
+        self
+    }
+
+    /// Time to ramp up to speed at `ACC 100`.
+    pub fn accel_time(mut self, accel_time: Duration) -> Self {
+        self.accel_time = accel_time;
+        self
+    }
+
+    /// Orientation speed of linear and circular moves, in degrees per second.
+    pub fn rotation_speed(mut self, degrees_per_sec: f64) -> Self {
+        self.rotation_speed = degrees_per_sec;
+        self
+    }
+
+    /// User frame `number` as it is on the controller.
+    pub fn uframe(mut self, number: u8, frame: FrameData) -> Self {
+        self.program.uframe(number, frame);
+        self
+    }
+
+    /// User tool `number` as it is on the controller.
+    pub fn utool(mut self, number: u8, tool: FrameData) -> Self {
+        self.program.utool(number, tool);
+        self
+    }
+
+    /// Where the robot is when the program starts, in user frame
+    /// `configuration.u_frame_number` with tool `configuration.u_tool_number`.
+    pub fn start(mut self, configuration: Configuration, position: &Position) -> Self {
+        self.program.start(configuration, position);
+        self.start_joints = None;
+        self
+    }
+
+    /// Where the robot is when the program starts, as joint angles.
+    pub fn start_joints(mut self, joints: JointAngles) -> Self {
+        self.start_joints = Some(joints);
+        self.program.configuration = Configuration::default();
+        self.program.start = None;
+        self
+    }
+
+    pub fn estimate<'a>(&self, packets: impl IntoIterator<Item = &'a PacketEnum>) -> CycleTime {
+        let mut state = EstimateState {
+            estimator: self,
+            program: self.program.begin(),
+            override_percent: self.override_percent,
+            joints: self.start_joints,
+        };
+        if let Some(position) = &self.program.start {
+            state.arrive(&self.program.configuration, position);
+        } else if let Some(joints) = &self.start_joints {
+            state.program.current = state.forward(joints);
+        }
+
+        let mut timings: Vec<(StepTime, Option<Motion>)> = Vec::new();
+        for (step, packet) in packets.into_iter().enumerate() {
+            let (seconds, lower_bound, motion, sequence_id) = match packet {
+                PacketEnum::Instruction(instruction) => {
+                    let (seconds, lower_bound, motion) = state.instruction(instruction);
+                    (seconds, lower_bound, motion, Some(instruction.get_sequence_id()))
+                }
+                PacketEnum::Command(command) => {
+                    state.command(command);
+                    (0.0, false, None, None)
+                }
+                _ => (0.0, false, None, None),
+            };
+            let time = StepTime { step, sequence_id, duration: Duration::from_secs_f64(seconds), lower_bound };
+            timings.push((time, motion));
+        }
+
+        // A blending move hands over to the next one while still slowing down.
+        for index in 0..timings.len() {
+            let Some(motion) = timings[index].1 else { continue };
+            let Some(Some(next)) = timings.get(index + 1).map(|(_, next)| *next) else { continue };
+            let saved = motion.blend * motion.ramp.min(next.ramp);
+            let time = &mut timings[index].0;
+            time.duration = time.duration.saturating_sub(Duration::from_secs_f64(saved));
+        }
+
+        let steps: Vec<StepTime> = timings.into_iter().map(|(time, _)| time).collect();
+        let total = steps.iter().map(|step| step.duration).sum();
+        CycleTime { steps, total }
+    }
+}
+
+/// What blending needs to know about a motion.
+#[derive(Debug, Clone, Copy)]
+struct Motion {
+    /// Seconds spent ramping down at the end.
+    ramp: f64,
+    /// Share of the ramp that overlaps with the next move, 0 to 1.
+    blend: f64,
+}
+
+/// Where [`CycleTimeEstimator::estimate`] has got to in the program.
+struct EstimateState<'a> {
+    estimator: &'a CycleTimeEstimator,
+    program: ProgramState,
+    override_percent: u8,
+    joints: Option<JointAngles>,
+}
+
+/// Which profile a move follows.
+enum Path {
+    /// TCP travel in mm and orientation change in degrees.
+    Cartesian { length: f64, rotation: f64 },
+    /// Change of J1..J6 in degrees.
+    Joint([f64; 6]),
+}
+
+impl EstimateState<'_> {
+    /// Seconds the instruction takes, whether that is a lower bound, and its
+    /// blending for motions.
+    fn instruction(&mut self, instruction: &Instruction) -> (f64, bool, Option<Motion>) {
+        self.program.instruction(instruction);
+        match instruction {
+            Instruction::FrcWaitTime(wait) => (wait.time as f64, false, None),
+            // Depends on the input, and on what the called program does.
+            Instruction::FrcWaitDIN(_) => (0.0, true, None),
+            Instruction::FrcCall(_) => {
+                self.forget();
+                (0.0, true, None)
+            }
+            Instruction::FrcSetUTool(_) => {
+                self.program.current = self.joints.as_ref().and_then(|joints| self.forward(joints));
+                (0.0, false, None)
+            }
+            Instruction::FrcSetUFrame(_) | Instruction::FrcSetPayLoad(_) => (0.0, false, None),
+            Instruction::FrcLinearMotion(motion) => {
+                let path = self.linear_to(&motion.configuration, &motion.position);
+                self.motion(path, false, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcLinearRelative(motion) => {
+                let target = self.offset(&motion.configuration, &motion.position);
+                let path = match target {
+                    Some(target) => self.linear_to(&motion.configuration, &target),
+                    None => {
+                        self.forget();
+                        None
+                    }
+                };
+                self.motion(path, false, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcJointMotion(motion) => {
+                let path = self.joint_to(&motion.configuration, &motion.position);
+                self.motion(path, true, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcJointRelative(motion) => {
+                let target = self.offset(&motion.configuration, &motion.position);
+                let path = match target {
+                    Some(target) => self.joint_to(&motion.configuration, &target),
+                    None => {
+                        self.forget();
+                        None
+                    }
+                };
+                self.motion(path, true, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcCircularMotion(motion) => {
+                let path = self.arc_to(&motion.configuration, &motion.via_position, &motion.position);
+                self.motion(path, false, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcCircularRelative(motion) => {
+                let via = self.offset(&motion.configuration, &motion.via_position);
+                let to = self.offset(&motion.configuration, &motion.position);
+                let path = match via.zip(to) {
+                    Some((via, to)) => self.arc_to(&motion.configuration, &via, &to),
+                    None => {
+                        self.forget();
+                        None
+                    }
+                };
+                self.motion(path, false, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcJointMotionJRep(motion) => {
+                let path = self.joints_to(motion.joint_angles);
+                self.motion(path, true, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcLinearMotionJRep(motion) => {
+                let path = self.joints_to(motion.joint_angles).map(|path| self.as_linear(path));
+                self.motion(path, false, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcJointRelativeJRep(motion) => {
+                let path = self.joints.map(|joints| add_joints(&joints, &motion.joint_angles)).and_then(|to| self.joints_to(to));
+                if path.is_none() {
+                    self.forget();
+                }
+                self.motion(path, true, &motion.speed, &motion.termination, &motion.options)
+            }
+            Instruction::FrcLinearRelativeJRep(motion) => {
+                let path = self
+                    .joints
+                    .map(|joints| add_joints(&joints, &motion.joint_angles))
+                    .and_then(|to| self.joints_to(to))
+                    .map(|path| self.as_linear(path));
+                if path.is_none() {
+                    self.forget();
+                }
+                self.motion(path, false, &motion.speed, &motion.termination, &motion.options)
+            }
+        }
+    }
+
+    fn command(&mut self, command: &Command) {
+        self.program.command(command);
+        match command {
+            Command::FrcSetOverride(set) => self.override_percent = set.value.clamp(1, 100),
+            Command::FrcSetUFrameUTool(_) => {
+                self.program.current = self.joints.as_ref().and_then(|joints| self.forward(joints));
+            }
+            _ => {}
+        }
+    }
+
+    /// Times a move along `path`; `None` when the start is unknown, which
+    /// counts as standing still.
+    fn motion(
+        &self,
+        path: Option<Path>,
+        joint: bool,
+        speed: &Speed,
+        termination: &Termination,
+        options: &MotionOptions,
+    ) -> (f64, bool, Option<Motion>) {
+        let scale = self.override_percent as f64 / 100.0;
+        let accel_time = self.estimator.accel_time.as_secs_f64() * 100.0 / options.acc.unwrap_or(100).max(1) as f64;
+        let blend = match termination {
+            Termination::Fine => 0.0,
+            Termination::Cnt(value) => *value as f64 / 100.0,
+            Termination::Cr(_) => 1.0,
+        };
+        let Some(path) = path else {
+            return (0.0, true, Some(Motion { ramp: 0.0, blend }));
+        };
+
+        // The share of full speed the move runs at, for moves whose speed is
+        // not a TCP speed.
+        let fraction = match speed {
+            Speed::Percent(percent) => *percent as f64 / 100.0,
+            _ => speed.mm_per_sec().unwrap_or(0.0) / *MM_SEC_RANGE.end() as f64,
+        };
+        let (mut seconds, mut ramp) = match (&path, joint) {
+            (Path::Joint(delta), _) => joint_time(delta, &self.estimator.robot.max_joint_speeds, fraction * scale, accel_time),
+            (&Path::Cartesian { length, rotation }, false) => {
+                let speed = match speed {
+                    Speed::Percent(_) => fraction * *MM_SEC_RANGE.end() as f64,
+                    _ => speed.mm_per_sec().unwrap_or(0.0),
+                };
+                let (travel, travel_ramp) = trapezoid(length, speed * scale, accel_time);
+                let (turn, turn_ramp) = trapezoid(rotation, self.estimator.rotation_speed * scale, accel_time);
+                if travel >= turn {
+                    (travel, travel_ramp)
+                } else {
+                    (turn, turn_ramp)
+                }
+            }
+            // A joint move whose joint angles are not known: as if it were linear at full speed.
+            (&Path::Cartesian { length, rotation }, true) => {
+                let top = *MM_SEC_RANGE.end() as f64 * fraction * scale;
+                let (travel, travel_ramp) = trapezoid(length, top, accel_time);
+                let (turn, turn_ramp) = trapezoid(rotation, self.estimator.rotation_speed * fraction * scale, accel_time);
+                if travel >= turn {
+                    (travel, travel_ramp)
+                } else {
+                    (turn, turn_ramp)
+                }
+            }
+        };
+        // Moves given a duration take it, stretched by the override.
+        if let Some(duration) = speed.duration() {
+            seconds = duration.as_secs_f64() / scale;
+            ramp = ramp.min(seconds / 2.0);
+        }
+        (seconds, false, Some(Motion { ramp, blend }))
+    }
+
+    fn linear_to(&mut self, configuration: &Configuration, position: &Position) -> Option<Path> {
+        let from = self.program.current;
+        self.arrive(configuration, position);
+        let (from, to) = from.zip(self.program.current)?;
+        Some(cartesian(&from, &to, None))
+    }
+
+    fn arc_to(&mut self, configuration: &Configuration, via: &Position, position: &Position) -> Option<Path> {
+        let from = self.program.current;
+        let via = self.program.in_world(via, configuration.u_frame_number);
+        self.arrive(configuration, position);
+        let (from, to) = from.zip(self.program.current)?;
+        Some(cartesian(&from, &to, via.as_ref()))
+    }
+
+    fn joint_to(&mut self, configuration: &Configuration, position: &Position) -> Option<Path> {
+        let (from_pose, from_joints) = (self.program.current, self.joints);
+        self.arrive(configuration, position);
+        if let Some((from, to)) = from_joints.zip(self.joints) {
+            return Some(Path::Joint(joint_delta(&from, &to)));
+        }
+        // Without joint solutions the move is timed by its TCP travel.
+        let (from, to) = from_pose.zip(self.program.current)?;
+        Some(cartesian(&from, &to, None))
+    }
+
+    fn joints_to(&mut self, joints: JointAngles) -> Option<Path> {
+        let from = self.joints.replace(joints);
+        self.program.current = self.forward(&joints);
+        from.map(|from| Path::Joint(joint_delta(&from, &joints)))
+    }
+
+    /// The TCP travel of a joint-space move made in a straight line.
+    fn as_linear(&self, path: Path) -> Path {
+        match path {
+            Path::Joint(delta) => {
+                let Some(to) = self.joints else { return Path::Joint(delta) };
+                let from = JointAngles {
+                    j1: to.j1 - delta[0] as f32,
+                    j2: to.j2 - delta[1] as f32,
+                    j3: to.j3 - delta[2] as f32,
+                    j4: to.j4 - delta[3] as f32,
+                    j5: to.j5 - delta[4] as f32,
+                    j6: to.j6 - delta[5] as f32,
+                    ..to
+                };
+                match self.forward(&from).zip(self.program.current) {
+                    Some((from, to)) => cartesian(&from, &to, None),
+                    None => Path::Joint(delta),
+                }
+            }
+            path => path,
+        }
+    }
+
+    /// Moves the robot to `position`, in the frame and tool of `configuration`.
+    fn arrive(&mut self, configuration: &Configuration, position: &Position) {
+        self.program.arrive(configuration, position);
+        let robot = &self.estimator.robot;
+        self.joints = self.program.current.zip(self.program.utool_data()).and_then(|(pose, tool)| {
+            robot
+                .inverse_with_config(&pose, tool, &ArmConfig::from(configuration))
+                .or_else(|| robot.inverse(&pose, tool).first().map(|solution| solution.joints))
+        });
+    }
+
+    fn forget(&mut self) {
+        self.program.current = None;
+        self.joints = None;
+    }
+
+    /// The current position moved by `offset`, in the user frame of `configuration`.
+    fn offset(&self, configuration: &Configuration, offset: &Position) -> Option<Position> {
+        self.program.offset(configuration.u_frame_number, offset)
+    }
+
+    fn forward(&self, joints: &JointAngles) -> Option<Position> {
+        let tool = self.program.utool_data()?;
+        Some(self.estimator.robot.forward(joints, tool).0)
+    }
+}
+
+/// Seconds to cover `distance` at `speed`, ramping over `accel_time` at each
+/// end, and the seconds actually spent ramping down.
+fn trapezoid(distance: f64, speed: f64, accel_time: f64) -> (f64, f64) {
+    if distance <= 0.0 || speed <= 0.0 {
+        return (0.0, 0.0);
+    }
+    if accel_time <= 0.0 {
+        return (distance / speed, 0.0);
+    }
+    if distance >= speed * accel_time {
+        (distance / speed + accel_time, accel_time)
+    } else {
+        // Never reaches full speed: ramps up and straight back down.
+        let half = (distance * accel_time / speed).sqrt();
+        (2.0 * half, half)
+    }
+}
+
+/// The slowest axis sets the time; the others are slowed down to match.
+fn joint_time(delta: &[f64; 6], max_speeds: &[f64; 6], fraction: f64, accel_time: f64) -> (f64, f64) {
+    delta
+        .iter()
+        .zip(max_speeds)
+        .map(|(delta, max)| trapezoid(delta.abs(), max * fraction, accel_time))
+        .fold((0.0, 0.0), |slowest, axis| if axis.0 > slowest.0 { axis } else { slowest })
+}
+
+fn joint_delta(from: &JointAngles, to: &JointAngles) -> [f64; 6] {
+    [to.j1 - from.j1, to.j2 - from.j2, to.j3 - from.j3, to.j4 - from.j4, to.j5 - from.j5, to.j6 - from.j6].map(|d| d as f64)
+}
+
+fn add_joints(joints: &JointAngles, offset: &JointAngles) -> JointAngles {
+    JointAngles {
+        j1: joints.j1 + offset.j1,
+        j2: joints.j2 + offset.j2,
+        j3: joints.j3 + offset.j3,
+        j4: joints.j4 + offset.j4,
+        j5: joints.j5 + offset.j5,
+        j6: joints.j6 + offset.j6,
+        j7: joints.j7 + offset.j7,
+        j8: joints.j8 + offset.j8,
+        j9: joints.j9 + offset.j9,
+    }
+}
+
+/// TCP travel from `from` to `to`, through `via` on an arc when given.
+fn cartesian(from: &Position, to: &Position, via: Option<&Position>) -> Path {
+    let (a, c) = (xyz(from), xyz(to));
+    let length = match via {
+        // The controller rejects an arc without a proper via point; the chords
+        // are as good a guess as any.
+        Some(via) => CircularArc::through(from, via, to)
+            .map(|arc| arc.length())
+            .unwrap_or_else(|_| norm(&sub(&xyz(via), &a)) + norm(&sub(&c, &xyz(via)))),
+        None => norm(&sub(&c, &a)),
+    };
+    let dot = from.to_quaternion().dot(&to.to_quaternion()).abs().min(1.0);
+    let rotation = (2.0 * dot.acos()).to_degrees();
+    Path::Cartesian { length, rotation }
+}
+
+fn xyz(position: &Position) -> [f64; 3] {
+    [position.x as f64, position.y as f64, position.z as f64]
+}
+
+fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
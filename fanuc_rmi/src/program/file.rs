@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{FrcWriteUFrameData, FrcWriteUToolData};
+use crate::gcode::CodeAction;
+use crate::instructions::{FrcSetUFrame, MotionOptions, Move, DEFAULT_SPEED_MM_SEC};
+use crate::packets::{Command, Instruction, OnOff};
+use crate::{
+    Configuration, FrameData, FrcError, JointAngles, PacketEnum, Position, Speed, Termination, UFRAME_RANGE,
+    UTOOL_RANGE,
+};
+
+/// Text formats a [`ProgramFile`] can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramFormat {
+    Json,
+    /// ASCII TP listing; see [`parse_ls`](super::parse_ls) for the supported subset.
+    Ls,
+    /// Needs the `toml` feature.
+    Toml,
+    /// Needs the `yaml` feature.
+    Yaml,
+}
+
+impl ProgramFormat {
+    /// Picks the format from a `.json`, `.ls`, `.toml`, `.yaml` or `.yml` extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(ProgramFormat::Json),
+            "ls" => Some(ProgramFormat::Ls),
+            "toml" => Some(ProgramFormat::Toml),
+            "yaml" | "yml" => Some(ProgramFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A robot program as data; see the [module docs](super) for the layout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProgramFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub defaults: Defaults,
+    /// User frames written with `FRC_WriteUFrameData` before the first step.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<FrameDefinition>,
+    /// User tools written with `FRC_WriteUToolData` before the first step.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<FrameDefinition>,
+    /// Poses that steps can refer to by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub poses: BTreeMap<String, Pose>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+/// Settings for steps that leave them out.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    /// UFrame used until a `set_uframe` step.
+    pub frame: u8,
+    /// UTool used until a `set_utool` step.
+    pub tool: u8,
+    pub speed: Speed,
+    pub termination: Termination,
+}
+
+impl Default for Defaults {
+    /// The world frame, tool 1, [`DEFAULT_SPEED_MM_SEC`] and `FINE`.
+    fn default() -> Self {
+        Self {
+            frame: 0,
+            tool: 1,
+            speed: Speed::MmSec(DEFAULT_SPEED_MM_SEC),
+            termination: Termination::Fine,
+        }
+    }
+}
+
+/// A numbered user frame or tool.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FrameDefinition {
+    pub number: u8,
+    /// X, Y, Z in mm and W, P, R in degrees.
+    pub xyzwpr: [f32; 6],
+}
+
+impl FrameDefinition {
+    pub fn frame_data(&self) -> FrameData {
+        let [x, y, z, w, p, r] = self.xyzwpr;
+        FrameData { x, y, z, w, p, r }
+    }
+}
+
+/// A Cartesian or joint pose; exactly one of `xyzwpr` and `joints` is set.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Pose {
+    /// X, Y, Z in mm and W, P, R in degrees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xyzwpr: Option<[f32; 6]>,
+    /// J1 to J6 in degrees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joints: Option<[f32; 6]>,
+    /// Extended axes, E1 to E3 or J7 to J9.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ext: Option<[f32; 3]>,
+    /// Arm configuration of a Cartesian pose. Its frame and tool numbers are
+    /// replaced by the step's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Configuration>,
+}
+
+impl Pose {
+    pub fn cartesian(xyzwpr: [f32; 6]) -> Self {
+        Self { xyzwpr: Some(xyzwpr), ..Default::default() }
+    }
+
+    pub fn joints(joints: [f32; 6]) -> Self {
+        Self { joints: Some(joints), ..Default::default() }
+    }
+
+    fn resolve(&self) -> Result<Resolved, String> {
+        let [ext1, ext2, ext3] = self.ext.unwrap_or_default();
+        match (self.xyzwpr, self.joints) {
+            (Some([x, y, z, w, p, r]), None) => Ok(Resolved::Cartesian(
+                Position { x, y, z, w, p, r, ext1, ext2, ext3 },
+                self.config.unwrap_or_default(),
+            )),
+            (None, Some([j1, j2, j3, j4, j5, j6])) if self.config.is_none() => Ok(Resolved::Joints(JointAngles {
+                j1, j2, j3, j4, j5, j6, j7: ext1, j8: ext2, j9: ext3,
+            })),
+            (None, Some(_)) => Err("a joint pose has no configuration".to_string()),
+            (Some(_), Some(_)) => Err("a pose has either xyzwpr or joints, not both".to_string()),
+            (None, None) => Err("a pose needs xyzwpr or joints".to_string()),
+        }
+    }
+}
+
+enum Resolved {
+    Cartesian(Position, Configuration),
+    Joints(JointAngles),
+}
+
+/// A pose given inline or by its name in [`ProgramFile::poses`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Target {
+    Named(String),
+    Pose(Pose),
+}
+
+/// Destination and settings of a motion step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MoveStep {
+    pub to: Target,
+    /// Via point of a circular step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via: Option<Target>,
+    /// `to` and `via` are offsets from the current position.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub relative: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<Speed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination: Option<Termination>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<u8>,
+    /// Motion options with their RMI field names, e.g. `{ "Acc": 80 }`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<MotionOptions>,
+}
+
+impl MoveStep {
+    pub fn to(target: Target) -> Self {
+        Self { to: target, via: None, relative: false, speed: None, termination: None, frame: None, tool: None, options: None }
+    }
+}
+
+/// One entry of [`ProgramFile::steps`], tagged by `type`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    Linear(MoveStep),
+    Joint(MoveStep),
+    /// Needs a `via` point; both points must be Cartesian.
+    Circular(MoveStep),
+    /// Dwell in seconds.
+    WaitTime { seconds: f32 },
+    WaitDin { port: u32, value: OnOff },
+    /// Sent as `FRC_WriteDOUT` once the steps before it have finished.
+    WriteDout { port: u16, value: OnOff },
+    /// Also the frame of the motion steps that follow.
+    SetUframe { frame: u8 },
+    /// Also the tool of the motion steps that follow.
+    SetUtool { tool: u8 },
+    SetPayload { schedule: u8 },
+    /// Runs a TP program on the controller.
+    Call { program: String },
+}
+
+impl ProgramFile {
+    /// Reads a program, picking the format from the file extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FrcError> {
+        let path = path.as_ref();
+        let format = ProgramFormat::from_path(path).ok_or_else(|| FrcError::Program {
+            step: None,
+            message: format!("{}: unknown program format", path.display()),
+        })?;
+        let text = std::fs::read_to_string(path).map_err(|e| FrcError::Program {
+            step: None,
+            message: format!("{}: {}", path.display(), e),
+        })?;
+        Self::parse(&text, format)
+    }
+
+    pub fn parse(text: &str, format: ProgramFormat) -> Result<Self, FrcError> {
+        let parsed = match format {
+            ProgramFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+            ProgramFormat::Ls => return super::parse_ls(text),
+            #[cfg(feature = "toml")]
+            ProgramFormat::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            ProgramFormat::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+            #[cfg(not(all(feature = "toml", feature = "yaml")))]
+            _ => Err(format!("{:?} programs need the `toml` or `yaml` feature", format)),
+        };
+        parsed.map_err(|message| FrcError::Program { step: None, message })
+    }
+
+    pub fn to_text(&self, format: ProgramFormat) -> Result<String, FrcError> {
+        let text = match format {
+            ProgramFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            ProgramFormat::Ls => {
+                let name = self.name.as_deref().unwrap_or(super::DEFAULT_LS_NAME);
+                return super::write_ls(name, &self.to_queue(1)?);
+            }
+            #[cfg(feature = "toml")]
+            ProgramFormat::Toml => toml::to_string(self).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            ProgramFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+            #[cfg(not(all(feature = "toml", feature = "yaml")))]
+            _ => Err(format!("{:?} programs need the `toml` or `yaml` feature", format)),
+        };
+        text.map_err(|message| FrcError::Program { step: None, message })
+    }
+
+    /// Builds the packet queue: frame and tool writes, then one packet per
+    /// step. Instructions are numbered from `first_sequence_id` and checked
+    /// like [`Instruction::validate`] does before sending.
+    pub fn to_queue(&self, first_sequence_id: u32) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let mut queue = VecDeque::new();
+        for frame in &self.frames {
+            if frame.number == 0 || !UFRAME_RANGE.contains(&frame.number) {
+                return Err(FrcError::Program { step: None, message: format!("UFrame {} cannot be written", frame.number) });
+            }
+            let write = FrcWriteUFrameData::new(None, frame.number, frame.frame_data());
+            queue.push_back(PacketEnum::Command(Command::FrcWriteUFrameData(write)));
+        }
+        for tool in &self.tools {
+            if !UTOOL_RANGE.contains(&tool.number) {
+                return Err(FrcError::Program { step: None, message: format!("UTool {} is outside {:?}", tool.number, UTOOL_RANGE) });
+            }
+            let write = FrcWriteUToolData::new(None, tool.number, tool.frame_data());
+            queue.push_back(PacketEnum::Command(Command::FrcWriteUToolData(write)));
+        }
+
+        let mut frame = self.defaults.frame;
+        let mut tool = self.defaults.tool;
+        let mut sequence_id = first_sequence_id;
+        for (index, step) in self.steps.iter().enumerate() {
+            let error = |message: String| FrcError::Program { step: Some(index), message };
+            let packet = match step {
+                Step::Linear(motion) | Step::Joint(motion) | Step::Circular(motion) => {
+                    PacketEnum::Instruction(self.motion(step, motion, frame, tool).map_err(error)?)
+                }
+                Step::WaitTime { seconds } => CodeAction::WaitTime(*seconds).to_packet(),
+                Step::WaitDin { port, value } => CodeAction::WaitDin { port: *port, value: *value }.to_packet(),
+                Step::WriteDout { port, value } => CodeAction::WriteDout { port: *port, value: *value }.to_packet(),
+                Step::SetUframe { frame: number } => {
+                    frame = *number;
+                    PacketEnum::Instruction(FrcSetUFrame::new(0, *number).into())
+                }
+                Step::SetUtool { tool: number } => {
+                    tool = *number;
+                    CodeAction::SetUTool(*number).to_packet()
+                }
+                Step::SetPayload { schedule } => CodeAction::SetPayload(*schedule).to_packet(),
+                Step::Call { program } => CodeAction::Call(program.clone()).to_packet(),
+            };
+            match packet {
+                PacketEnum::Instruction(mut instruction) => {
+                    instruction.set_sequence_id(sequence_id);
+                    instruction.validate().map_err(|e| error(e.to_string()))?;
+                    sequence_id += 1;
+                    queue.push_back(PacketEnum::Instruction(instruction));
+                }
+                packet => queue.push_back(packet),
+            }
+        }
+        Ok(queue)
+    }
+
+    fn pose(&self, target: &Target) -> Result<Resolved, String> {
+        match target {
+            Target::Named(name) => match self.poses.get(name) {
+                Some(pose) => pose.resolve().map_err(|e| format!("pose {}: {}", name, e)),
+                None => Err(format!("no pose named {}", name)),
+            },
+            Target::Pose(pose) => pose.resolve(),
+        }
+    }
+
+    fn motion(&self, step: &Step, motion: &MoveStep, frame: u8, tool: u8) -> Result<Instruction, String> {
+        let speed = motion.speed.unwrap_or(self.defaults.speed);
+        let termination = motion.termination.unwrap_or(self.defaults.termination);
+        let options = motion.options.unwrap_or_default();
+        let with_frame = |mut config: Configuration| {
+            config.u_frame_number = motion.frame.unwrap_or(frame);
+            config.u_tool_number = motion.tool.unwrap_or(tool);
+            config
+        };
+        if !matches!(step, Step::Circular(_)) && motion.via.is_some() {
+            return Err("only circular steps take a via point".to_string());
+        }
+
+        let instruction: Instruction = match (step, self.pose(&motion.to)?) {
+            (Step::Circular(_), Resolved::Cartesian(position, config)) => {
+                let Some(via) = &motion.via else {
+                    return Err("a circular step needs a via point".to_string());
+                };
+                let Resolved::Cartesian(via, via_config) = self.pose(via)? else {
+                    return Err("circular points must be Cartesian".to_string());
+                };
+                let arc = Move::circular()
+                    .via(via)
+                    .via_config(with_frame(via_config))
+                    .speed(speed)
+                    .termination(termination)
+                    .options(options);
+                if motion.relative {
+                    arc.by(position).config(with_frame(config)).build(0).map_err(|e| e.to_string())?.into()
+                } else {
+                    arc.to(position).config(with_frame(config)).build(0).map_err(|e| e.to_string())?.into()
+                }
+            }
+            (Step::Circular(_), Resolved::Joints(_)) => return Err("circular points must be Cartesian".to_string()),
+            (Step::Linear(_), pose) => {
+                let builder = Move::linear().speed(speed).termination(termination).options(options);
+                match (pose, motion.relative) {
+                    (Resolved::Cartesian(position, config), false) => builder.to(position).config(with_frame(config)).build(0).map_err(|e| e.to_string())?.into(),
+                    (Resolved::Cartesian(position, config), true) => builder.by(position).config(with_frame(config)).build(0).map_err(|e| e.to_string())?.into(),
+                    (Resolved::Joints(joints), false) => builder.to_joints(joints).build(0).map_err(|e| e.to_string())?.into(),
+                    (Resolved::Joints(joints), true) => builder.by_joints(joints).build(0).map_err(|e| e.to_string())?.into(),
+                }
+            }
+            (_, pose) => {
+                let builder = Move::joint().speed(speed).termination(termination).options(options);
+                match (pose, motion.relative) {
+                    (Resolved::Cartesian(position, config), false) => builder.to(position).config(with_frame(config)).build(0).map_err(|e| e.to_string())?.into(),
+                    (Resolved::Cartesian(position, config), true) => builder.by(position).config(with_frame(config)).build(0).map_err(|e| e.to_string())?.into(),
+                    (Resolved::Joints(joints), false) => builder.to_joints(joints).build(0).map_err(|e| e.to_string())?.into(),
+                    (Resolved::Joints(joints), true) => builder.by_joints(joints).build(0).map_err(|e| e.to_string())?.into(),
+                }
+            }
+        };
+        Ok(instruction)
+    }
+}
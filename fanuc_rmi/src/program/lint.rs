@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::commands::FrcGetStatusResponse;
+use crate::instructions::ViaProblem;
+use crate::kinematics::RobotModel;
+use crate::packets::{Command, Instruction};
+use crate::{Configuration, FanucErrorCode, FrameData, JointAngles, PacketEnum, Position, Termination};
+
+use super::state::ProgramState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Runs, but probably not as intended.
+    Warning,
+    /// The controller would reject it or stop on it.
+    Error,
+}
+
+/// A problem [`Linter`] found in one packet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    /// The sequence ID was already used by the instruction at `first_step`.
+    DuplicateSequenceId { sequence_id: u32, first_step: usize },
+    /// The sequence ID is lower than the one of the instruction before it.
+    NonMonotonicSequenceId { sequence_id: u32, previous: u32 },
+    /// The last motion blends with `CNT` or `CR`, so the controller waits for a
+    /// motion that never comes.
+    BlendOnFinalMove(Termination),
+    /// [`via_problem`](crate::instructions::via_problem) rejected the via point of a circular move.
+    InvalidVia(ViaProblem),
+    /// [`Instruction::validate`] rejected a speed, termination, configuration or option.
+    InvalidValue(String),
+    /// Higher than the number of user frames the controller reports.
+    FrameOutOfRange { frame: u8, available: u8 },
+    /// Higher than the number of user tools the controller reports.
+    ToolOutOfRange { tool: u8, available: u8 },
+    /// No joint solution within the robot's limits reaches the target.
+    Unreachable,
+    /// A relative motion before any motion fixed where the robot is.
+    RelativeWithoutStart,
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::BlendOnFinalMove(_) | Lint::RelativeWithoutStart => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// The error the controller answers with, where it is known.
+    pub fn error_code(&self) -> Option<FanucErrorCode> {
+        match self {
+            Lint::DuplicateSequenceId { .. } | Lint::NonMonotonicSequenceId { .. } => {
+                Some(FanucErrorCode::InvalidSequenceIDNumber)
+            }
+            Lint::InvalidVia(_) => Some(FanucErrorCode::InvalidVIAPosition),
+            Lint::FrameOutOfRange { .. } => Some(FanucErrorCode::InvalidUFrameNumber),
+            Lint::ToolOutOfRange { .. } => Some(FanucErrorCode::InvalidUToolNumber),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::DuplicateSequenceId { sequence_id, first_step } => {
+                write!(f, "sequence ID {} is already used at step {}", sequence_id, first_step)
+            }
+            Lint::NonMonotonicSequenceId { sequence_id, previous } => {
+                write!(f, "sequence ID {} follows {}", sequence_id, previous)
+            }
+            Lint::BlendOnFinalMove(termination) => {
+                write!(f, "final motion ends with {:?} {}", termination.term_type(), termination.value())
+            }
+            Lint::InvalidVia(problem) => write!(f, "invalid via point: {}", problem),
+            Lint::InvalidValue(message) => write!(f, "{}", message),
+            Lint::FrameOutOfRange { frame, available } => {
+                write!(f, "user frame {} is beyond the {} on the controller", frame, available)
+            }
+            Lint::ToolOutOfRange { tool, available } => {
+                write!(f, "user tool {} is beyond the {} on the controller", tool, available)
+            }
+            Lint::Unreachable => write!(f, "target is out of reach"),
+            Lint::RelativeWithoutStart => write!(f, "relative motion from an unknown position"),
+        }
+    }
+}
+
+/// A [`Lint`] and the index of the packet it was found in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub step: usize,
+    /// Set for instructions.
+    pub sequence_id: Option<u32>,
+    pub lint: Lint,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity() {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{} at step {}: {}", severity, self.step, self.lint)
+    }
+}
+
+/// Checks a packet queue before it is sent.
+///
+/// Without [`status`](Self::status) frame and tool numbers are not range
+/// checked, and without [`robot`](Self::robot) poses are not checked for
+/// reach. User frames and tools written by the program itself are picked up
+/// as they come.
+///
+/// ```
+/// use fanuc_rmi::gcode::GcodeLoader;
+/// use fanuc_rmi::program::Linter;
+///
+/// let queue = GcodeLoader::new().load_str("G1 X100 F3000\nG1 X200").unwrap();
+/// let diagnostics = Linter::new().lint(&queue);
+/// assert!(diagnostics.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    frames_available: Option<u8>,
+    tools_available: Option<u8>,
+    robot: Option<RobotModel>,
+    program: ProgramState,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Range checks frame and tool numbers against `NumberUFrame` and `NumberUTool`.
+    pub fn status(mut self, status: &FrcGetStatusResponse) -> Self {
+        self.frames_available = Some(status.number_uframe.max(0) as u8);
+        self.tools_available = Some(status.number_utool.max(0) as u8);
+        self
+    }
+
+    /// Checks that every pose can be reached by `robot`.
+    pub fn robot(mut self, robot: &RobotModel) -> Self {
+        self.robot = Some(robot.clone());
+        self
+    }
+
+    /// User frame `number` as it is on the controller.
+    pub fn uframe(mut self, number: u8, frame: FrameData) -> Self {
+        self.program.uframe(number, frame);
+        self
+    }
+
+    /// User tool `number` as it is on the controller.
+    pub fn utool(mut self, number: u8, tool: FrameData) -> Self {
+        self.program.utool(number, tool);
+        self
+    }
+
+    /// Where the robot is when the program starts, in user frame
+    /// `configuration.u_frame_number` with tool `configuration.u_tool_number`,
+    /// as [`FanucDriver::read_cartesian_position`](crate::drivers::FanucDriver::read_cartesian_position)
+    /// returns it.
+    pub fn start(mut self, configuration: Configuration, position: &Position) -> Self {
+        self.program.start(configuration, position);
+        self
+    }
+
+    /// Every problem found in `packets`, in step order.
+    pub fn lint<'a>(&self, packets: impl IntoIterator<Item = &'a PacketEnum>) -> Vec<Diagnostic> {
+        let mut state = LintState {
+            linter: self,
+            program: self.program.begin(),
+            started: self.program.start.is_some(),
+            sequence_ids: HashMap::new(),
+            previous_id: None,
+            last_motion: None,
+            diagnostics: Vec::new(),
+        };
+
+        for (step, packet) in packets.into_iter().enumerate() {
+            match packet {
+                PacketEnum::Instruction(instruction) => state.instruction(step, instruction),
+                PacketEnum::Command(command) => state.command(step, command),
+                _ => {}
+            }
+        }
+
+        if let Some((step, sequence_id, termination)) = state.last_motion {
+            if termination != Termination::Fine {
+                state.diagnostics.push(Diagnostic {
+                    step,
+                    sequence_id: Some(sequence_id),
+                    lint: Lint::BlendOnFinalMove(termination),
+                });
+            }
+        }
+        state.diagnostics.sort_by_key(|diagnostic| diagnostic.step);
+        state.diagnostics
+    }
+}
+
+/// What [`Linter::lint`] knows about the controller at the current step.
+struct LintState<'a> {
+    linter: &'a Linter,
+    program: ProgramState,
+    /// Whether an absolute motion or the start position fixed where the robot is.
+    started: bool,
+    sequence_ids: HashMap<u32, usize>,
+    previous_id: Option<u32>,
+    last_motion: Option<(usize, u32, Termination)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl LintState<'_> {
+    fn instruction(&mut self, step: usize, instruction: &Instruction) {
+        let sequence_id = instruction.get_sequence_id();
+        let mut found = Vec::new();
+
+        match self.sequence_ids.get(&sequence_id) {
+            Some(&first_step) => found.push(Lint::DuplicateSequenceId { sequence_id, first_step }),
+            None => {
+                if let Some(previous) = self.previous_id.filter(|&previous| sequence_id < previous) {
+                    found.push(Lint::NonMonotonicSequenceId { sequence_id, previous });
+                }
+                self.sequence_ids.insert(sequence_id, step);
+            }
+        }
+        self.previous_id = Some(sequence_id);
+
+        if let Err(e) = instruction.validate() {
+            found.push(Lint::InvalidValue(e.to_string()));
+        }
+
+        self.program.instruction(instruction);
+        let lints = match instruction {
+            Instruction::FrcSetUFrame(set) => self.check_frame(set.frame_number).into_iter().collect(),
+            Instruction::FrcSetUTool(set) => self.check_tool(set.tool_number).into_iter().collect(),
+            Instruction::FrcLinearMotion(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.absolute(&motion.configuration, &motion.position)
+            }
+            Instruction::FrcJointMotion(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.absolute(&motion.configuration, &motion.position)
+            }
+            Instruction::FrcLinearRelative(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.relative(&motion.configuration, &motion.position)
+            }
+            Instruction::FrcJointRelative(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.relative(&motion.configuration, &motion.position)
+            }
+            Instruction::FrcCircularMotion(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                let frame = motion.configuration.u_frame_number;
+                let start = self.program.current.and_then(|start| self.program.in_frame(&start, frame));
+                let mut lints = Vec::new();
+                if let Some(start) = start {
+                    lints.extend(motion.via_problem(&start).map(Lint::InvalidVia));
+                }
+                lints.extend(self.reach(&motion.via_configuration, &motion.via_position));
+                lints.extend(self.absolute(&motion.configuration, &motion.position));
+                lints
+            }
+            Instruction::FrcCircularRelative(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                let mut lints = Vec::new();
+                lints.extend(motion.via_problem().map(Lint::InvalidVia));
+                lints.extend(self.relative(&motion.configuration, &motion.position));
+                lints
+            }
+            Instruction::FrcLinearMotionJRep(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.joints(&motion.joint_angles)
+            }
+            Instruction::FrcJointMotionJRep(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.joints(&motion.joint_angles)
+            }
+            Instruction::FrcLinearRelativeJRep(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.program.current = None;
+                self.relative_start()
+            }
+            Instruction::FrcJointRelativeJRep(motion) => {
+                self.last_motion = Some((step, sequence_id, motion.termination));
+                self.program.current = None;
+                self.relative_start()
+            }
+            Instruction::FrcCall(_) => {
+                // The called program can leave the robot anywhere.
+                self.program.current = None;
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+        found.extend(lints);
+        self.diagnostics
+            .extend(found.into_iter().map(|lint| Diagnostic { step, sequence_id: Some(sequence_id), lint }));
+    }
+
+    fn command(&mut self, step: usize, command: &Command) {
+        self.program.command(command);
+        let lints = match command {
+            Command::FrcWriteUFrameData(write) => self.check_frame(write.frame_number).into_iter().collect(),
+            Command::FrcWriteUToolData(write) => self.check_tool(write.tool_number).into_iter().collect(),
+            Command::FrcSetUFrameUTool(set) => {
+                self.check_frame(set.uframe_number).into_iter().chain(self.check_tool(set.utool_number)).collect()
+            }
+            _ => Vec::new(),
+        };
+        self.diagnostics.extend(lints.into_iter().map(|lint| Diagnostic { step, sequence_id: None, lint }));
+    }
+
+    fn check_frame(&self, frame: u8) -> Option<Lint> {
+        let available = self.linter.frames_available?;
+        (frame > available).then_some(Lint::FrameOutOfRange { frame, available })
+    }
+
+    fn check_tool(&self, tool: u8) -> Option<Lint> {
+        let available = self.linter.tools_available?;
+        (tool > available).then_some(Lint::ToolOutOfRange { tool, available })
+    }
+
+    fn check_configuration(&self, configuration: &Configuration) -> Vec<Lint> {
+        self.check_frame(configuration.u_frame_number)
+            .into_iter()
+            .chain(self.check_tool(configuration.u_tool_number))
+            .collect()
+    }
+
+    fn absolute(&mut self, configuration: &Configuration, position: &Position) -> Vec<Lint> {
+        let mut lints = self.check_configuration(configuration);
+        lints.extend(self.reach(configuration, position));
+        self.started = true;
+        self.program.arrive(configuration, position);
+        lints
+    }
+
+    fn relative(&mut self, configuration: &Configuration, offset: &Position) -> Vec<Lint> {
+        let mut lints = self.check_configuration(configuration);
+        lints.extend(self.relative_start());
+        match self.program.offset(configuration.u_frame_number, offset) {
+            Some(target) => {
+                lints.extend(self.reach(configuration, &target));
+                self.program.current = self.program.in_world(&target, configuration.u_frame_number);
+            }
+            None => self.program.current = None,
+        }
+        lints
+    }
+
+    fn relative_start(&mut self) -> Vec<Lint> {
+        if self.started {
+            return Vec::new();
+        }
+        // Reported once; after this motion the robot is wherever it went.
+        self.started = true;
+        vec![Lint::RelativeWithoutStart]
+    }
+
+    fn joints(&mut self, joints: &JointAngles) -> Vec<Lint> {
+        self.started = true;
+        let Some(robot) = &self.linter.robot else {
+            self.program.current = None;
+            return Vec::new();
+        };
+        self.program.current = self.program.utool_data().map(|tool| robot.forward(joints, tool).0);
+        if robot.within_limits(joints) {
+            Vec::new()
+        } else {
+            vec![Lint::Unreachable]
+        }
+    }
+
+    fn reach(&self, configuration: &Configuration, position: &Position) -> Option<Lint> {
+        let robot = self.linter.robot.as_ref()?;
+        let tool = self.program.utools.get(&configuration.u_tool_number)?;
+        let world = self.program.in_world(position, configuration.u_frame_number)?;
+        (!robot.is_reachable(&world, tool)).then_some(Lint::Unreachable)
+    }
+}
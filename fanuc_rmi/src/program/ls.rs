@@ -0,0 +1,627 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::instructions::MotionOptions;
+use crate::packets::{Command, Instruction, OnOff};
+use crate::{
+    Configuration, FlipNoFlip, FrcError, FrontBack, JointAngles, LeftRight, PacketEnum, Position, Speed, Termination,
+    UpDown,
+};
+
+use super::{MoveStep, Pose, ProgramFile, Step, Target};
+
+/// Program name used by [`ProgramFile::to_text`](super::ProgramFile::to_text) when the file has none.
+pub const DEFAULT_LS_NAME: &str = "RMI_PROG";
+
+/// Reads an ASCII TP listing into a [`ProgramFile`].
+///
+/// Supported statements are `J`, `L` and `C` motions to `P[]` positions of
+/// group 1 with `ACC`, `Offset,PR[]`, `VOFFSET,VR[]`, `MROT` and `INC`,
+/// `UFRAME_NUM=`, `UTOOL_NUM=`, `WAIT <sec>(sec)`, `WAIT DI[]=`, `DO[]=`,
+/// `CALL` and `PAYLOAD[]`. Remarks and commented-out lines are skipped;
+/// anything else is an error. A motion uses the frame and tool its position
+/// was taught in.
+///
+/// ```
+/// use fanuc_rmi::program::parse_ls;
+///
+/// let program = parse_ls("/PROG  DEMO
+/// /MN
+///    1:  UTOOL_NUM=1 ;
+///    2:L P[1] 250mm/sec CNT50    ;
+/// /POS
+/// P[1]{
+///    GP1:
+///     UF : 0, UT : 1,     CONFIG : 'N U T, 0, 0, 0',
+///     X =   500.000  mm,  Y =     0.000  mm,  Z =   300.000  mm,
+///     W =   180.000 deg,  P =     0.000 deg,  R =     0.000 deg
+/// };
+/// /END
+/// ").unwrap();
+/// assert_eq!(program.to_queue(1).unwrap().len(), 2);
+/// ```
+pub fn parse_ls(text: &str) -> Result<ProgramFile, FrcError> {
+    let mut program = ProgramFile::default();
+    let mut section = "";
+    let mut statement = String::new();
+    let mut statement_line = 0;
+    let mut motions = Vec::new();
+    let mut position: Option<(usize, String)> = None;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.starts_with('/') && position.is_none() && statement.is_empty() {
+            let mut words = trimmed.split_whitespace();
+            section = words.next().unwrap_or_default();
+            if section == "/PROG" {
+                program.name = words.next().map(str::to_string);
+            }
+            continue;
+        }
+        match section {
+            "/MN" => {
+                // `   3:L P[1] ...` and continuation lines `    :  P[2] ...`.
+                let Some((number, body)) = raw.split_once(':') else {
+                    return Err(ls_error(line, "expected a numbered line"));
+                };
+                if !number.trim().chars().all(|c| c.is_ascii_digit()) {
+                    return Err(ls_error(line, "expected a numbered line"));
+                }
+                if statement.is_empty() {
+                    statement_line = line;
+                }
+                statement.push_str(body);
+                statement.push(' ');
+                if let Some(complete) = statement.trim_end().strip_suffix(';') {
+                    let complete = strip_comments(complete);
+                    statement.clear();
+                    if let Some(step) = parse_statement(&complete).map_err(|message| ls_error(statement_line, message))? {
+                        if matches!(step, Step::Linear(_) | Step::Joint(_) | Step::Circular(_)) {
+                            motions.push((program.steps.len(), statement_line));
+                        }
+                        program.steps.push(step);
+                    }
+                }
+            }
+            "/POS" => {
+                let (start, mut body) = position.take().unwrap_or((line, String::new()));
+                body.push_str(trimmed);
+                body.push('\n');
+                if trimmed.ends_with("};") {
+                    let (name, pose) = parse_position(&body).map_err(|message| ls_error(start, message))?;
+                    program.poses.insert(name, pose);
+                } else {
+                    position = Some((start, body));
+                }
+            }
+            _ => {}
+        }
+    }
+    if !statement.trim().is_empty() {
+        return Err(ls_error(statement_line, "statement without a closing `;`"));
+    }
+    if let Some((start, _)) = position {
+        return Err(ls_error(start, "position without a closing `};`"));
+    }
+
+    // Motions run in the frame and tool of their position data.
+    for (index, line) in motions {
+        let (Step::Linear(motion) | Step::Joint(motion) | Step::Circular(motion)) = &mut program.steps[index] else {
+            continue;
+        };
+        for target in std::iter::once(&motion.to).chain(motion.via.as_ref()) {
+            let Target::Named(name) = target else { continue };
+            if !program.poses.contains_key(name) {
+                return Err(ls_error(line, format!("{} has no position data", name)));
+            }
+        }
+        let Target::Named(name) = &motion.to else { continue };
+        if let Some(config) = program.poses[name].config {
+            motion.frame = Some(config.u_frame_number);
+            motion.tool = Some(config.u_tool_number);
+        }
+    }
+    Ok(program)
+}
+
+/// Writes a program queue as an ASCII TP listing named `name`.
+///
+/// Every motion gets its own `P[]`, and `UFRAME_NUM`/`UTOOL_NUM` lines are
+/// added where a motion's frame or tool differs from the active one. Packets
+/// without a TP equivalent, such as commands other than `FRC_WriteDOUT` or
+/// motions with a local condition, port output or `NoBlend`, are rejected.
+pub fn write_ls<'a>(name: &str, packets: impl IntoIterator<Item = &'a PacketEnum>) -> Result<String, FrcError> {
+    let name = name.to_ascii_uppercase();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || name.len() > 36
+    {
+        return Err(FrcError::Program { step: None, message: format!("{} is not a valid TP program name", name) });
+    }
+
+    let mut writer = LsWriter::default();
+    for (index, packet) in packets.into_iter().enumerate() {
+        writer.packet(packet).map_err(|message| FrcError::Program { step: Some(index), message })?;
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "/PROG  {}", name);
+    out.push_str(concat!(
+        "/ATTR\n",
+        "OWNER\t\t= MNEDITOR;\n",
+        "COMMENT\t\t= \"\";\n",
+        "PROTECT\t\t= READ_WRITE;\n",
+        "TCD:  STACK_SIZE\t= 0,\n",
+        "      TASK_PRIORITY\t= 50,\n",
+        "      TIME_SLICE\t= 0,\n",
+        "      BUSY_LAMP_OFF\t= 0,\n",
+        "      ABORT_REQUEST\t= 0,\n",
+        "      PAUSE_REQUEST\t= 0;\n",
+        "DEFAULT_GROUP\t= 1,*,*,*,*;\n",
+        "CONTROL_CODE\t= 00000000 00000000;\n",
+        "/MN\n",
+    ));
+    for (number, line) in (1..).zip(&writer.lines) {
+        let mut parts = line.split('\n');
+        let _ = writeln!(out, "{:>4}:{}", number, parts.next().unwrap_or_default());
+        for part in parts {
+            let _ = writeln!(out, "    :{}", part);
+        }
+    }
+    out.push_str("/POS\n");
+    out.push_str(&writer.positions);
+    out.push_str("/END\n");
+    Ok(out)
+}
+
+fn ls_error(line: usize, message: impl Into<String>) -> FrcError {
+    FrcError::Ls { line, message: message.into() }
+}
+
+/// Drops `:comment` from `P[1:comment]`, `DI[2:comment]` and the like.
+fn strip_comments(statement: &str) -> String {
+    let mut out = String::with_capacity(statement.len());
+    let mut in_comment = false;
+    let mut depth = 0;
+    for c in statement.chars() {
+        match c {
+            '[' => {
+                depth += 1;
+                out.push(c);
+            }
+            ']' => {
+                depth -= 1;
+                in_comment = false;
+                out.push(c);
+            }
+            ':' if depth > 0 => in_comment = true,
+            _ if in_comment => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn parse_statement(statement: &str) -> Result<Option<Step>, String> {
+    let statement = statement.trim();
+    if statement.is_empty() || statement.starts_with('!') || statement.starts_with("//") {
+        return Ok(None);
+    }
+    let mut words = statement.split_whitespace();
+    let first = words.next().unwrap_or_default();
+    if matches!(first, "J" | "L" | "C") {
+        return parse_motion(first, words).map(Some);
+    }
+
+    let compact: String = statement.split_whitespace().collect();
+    let step = if let Some(frame) = compact.strip_prefix("UFRAME_NUM=") {
+        Step::SetUframe { frame: parse_number(frame)? }
+    } else if let Some(tool) = compact.strip_prefix("UTOOL_NUM=") {
+        Step::SetUtool { tool: parse_number(tool)? }
+    } else if let Some(wait) = compact.strip_prefix("WAITDI[") {
+        let (port, value) = parse_io(wait)?;
+        Step::WaitDin { port: parse_number(port)?, value }
+    } else if let Some(seconds) = compact.strip_prefix("WAIT").and_then(|wait| wait.strip_suffix("(sec)")) {
+        Step::WaitTime { seconds: seconds.parse().map_err(|_| format!("bad wait time {}", seconds))? }
+    } else if let Some(output) = compact.strip_prefix("DO[") {
+        let (port, value) = parse_io(output)?;
+        Step::WriteDout { port: parse_number(port)?, value }
+    } else if let Some(schedule) = compact.strip_prefix("PAYLOAD[").and_then(|payload| payload.strip_suffix(']')) {
+        Step::SetPayload { schedule: parse_number(schedule)? }
+    } else if first == "CALL" {
+        match words.collect::<Vec<_>>()[..] {
+            [program] if program.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                Step::Call { program: program.to_string() }
+            }
+            _ => return Err("only CALL without arguments is supported".to_string()),
+        }
+    } else {
+        return Err(format!("unsupported statement `{}`", statement));
+    };
+    Ok(Some(step))
+}
+
+/// `1]=ON` after `DO[` or `DI[`.
+fn parse_io(text: &str) -> Result<(&str, OnOff), String> {
+    let (port, value) = text.split_once("]=").ok_or_else(|| format!("expected `]=` in {}", text))?;
+    let value = match value {
+        "ON" => OnOff::ON,
+        "OFF" => OnOff::OFF,
+        other => return Err(format!("unsupported I/O value {}", other)),
+    };
+    Ok((port, value))
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("bad number {}", text))
+}
+
+fn parse_motion<'a>(kind: &str, mut words: impl Iterator<Item = &'a str>) -> Result<Step, String> {
+    let mut target = || -> Result<Target, String> {
+        match words.next() {
+            Some(word) if word.starts_with("P[") && word.ends_with(']') => Ok(Target::Named(word.to_string())),
+            Some(word) => Err(format!("only P[] positions are supported, got {}", word)),
+            None => Err("missing position".to_string()),
+        }
+    };
+    let first = target()?;
+    let (via, to) = if kind == "C" { (Some(first), target()?) } else { (None, first) };
+
+    let speed = parse_speed(words.next().ok_or("missing speed")?)?;
+    let termination = match words.next().ok_or("missing termination")? {
+        "FINE" => Termination::Fine,
+        word => match (word.strip_prefix("CNT"), word.strip_prefix("CR")) {
+            (Some(value), _) => Termination::Cnt(parse_number(value)?),
+            (_, Some(value)) => Termination::Cr(parse_number(value)?),
+            _ => return Err(format!("unsupported termination {}", word)),
+        },
+    };
+
+    let mut motion = MoveStep { via, speed: Some(speed), termination: Some(termination), ..MoveStep::to(to) };
+    let mut options = MotionOptions::default();
+    for word in words {
+        if let Some(acc) = word.strip_prefix("ACC") {
+            options.acc = Some(parse_number(acc)?);
+        } else if let Some(register) = word.strip_prefix("Offset,PR[").and_then(|pr| pr.strip_suffix(']')) {
+            options.offset_pr_number = Some(parse_number(register)?);
+        } else if let Some(register) = word.strip_prefix("VOFFSET,VR[").and_then(|vr| vr.strip_suffix(']')) {
+            options.vision_pr_number = Some(parse_number(register)?);
+        } else if word == "MROT" {
+            options.mrot = true;
+        } else if word == "INC" {
+            motion.relative = true;
+        } else {
+            return Err(format!("unsupported motion option {}", word));
+        }
+    }
+    if options != MotionOptions::default() {
+        motion.options = Some(options);
+    }
+    Ok(match kind {
+        "J" => Step::Joint(motion),
+        "L" => Step::Linear(motion),
+        _ => Step::Circular(motion),
+    })
+}
+
+fn parse_speed(word: &str) -> Result<Speed, String> {
+    let split = word.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(word.len());
+    let (value, unit) = word.split_at(split);
+    let value: f64 = parse_number(value)?;
+    let speed = match unit {
+        "%" => Speed::Percent(value.round() as u16),
+        "mm/sec" => Speed::MmSec(value.round() as u16),
+        "cm/min" => Speed::MmSec((value / 6.0).round() as u16),
+        "inch/min" => Speed::InchMin(value.round() as u16),
+        "sec" => Speed::Time((value * 10.0).round() as u16),
+        "msec" => Speed::Millis(value.round() as u16),
+        _ => return Err(format!("unsupported speed {}", word)),
+    };
+    Ok(speed)
+}
+
+/// Parses `P[n]{ GP1: ... };` into its name and pose.
+fn parse_position(body: &str) -> Result<(String, Pose), String> {
+    let (header, rest) = body.split_once('{').ok_or("expected `{`")?;
+    let name = strip_comments(header.trim());
+    if !name.starts_with("P[") {
+        return Err(format!("expected P[] position data, got {}", name));
+    }
+    let rest = rest.trim_end().strip_suffix("};").ok_or("expected `};`")?;
+
+    let mut config = Configuration::default();
+    let mut rest = rest.to_string();
+    if let Some(start) = rest.find("CONFIG") {
+        let open = rest[start..].find('\'').map(|i| start + i).ok_or("CONFIG without quotes")?;
+        let close = rest[open + 1..].find('\'').map(|i| open + 1 + i).ok_or("CONFIG without closing quote")?;
+        parse_config(&rest[open + 1..close], &mut config)?;
+        rest.replace_range(start..=close, "");
+    }
+
+    let mut values = BTreeMap::new();
+    for item in rest.split([',', '\n']).map(str::trim).filter(|item| !item.is_empty()) {
+        if let Some(group) = item.strip_prefix("GP") {
+            if group != "1:" {
+                return Err(format!("only group 1 is supported, got GP{}", group));
+            }
+            continue;
+        }
+        let (key, value) = item.split_once(['=', ':']).ok_or_else(|| format!("unexpected {}", item))?;
+        let value = value.split_whitespace().next().unwrap_or_default();
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+    let mut take = |key: &str| -> Result<Option<f32>, String> {
+        values.remove(key).map(|value| parse_number::<f32>(&value)).transpose()
+    };
+    let mut axes = |keys: &[&str]| -> Result<Option<Vec<f32>>, String> {
+        let found: Vec<_> = keys.iter().map(|key| take(key)).collect::<Result<_, _>>()?;
+        if found.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+        found.into_iter().zip(keys).map(|(value, key)| value.ok_or(format!("missing {}", key))).collect::<Result<_, _>>().map(Some)
+    };
+    let xyzwpr = axes(&["X", "Y", "Z", "W", "P", "R"])?;
+    let joints = axes(&["J1", "J2", "J3", "J4", "J5", "J6"])?;
+    let ext = axes(&["E1", "E2", "E3"])?;
+    for (key, field) in [("UF", &mut config.u_frame_number), ("UT", &mut config.u_tool_number)] {
+        if let Some(value) = values.remove(key) {
+            *field = parse_number(&value)?;
+        }
+    }
+    if let Some(key) = values.keys().next() {
+        return Err(format!("unsupported position field {}", key));
+    }
+
+    let pose = match (xyzwpr, joints) {
+        (Some(xyzwpr), None) => Pose { config: Some(config), ..Pose::cartesian(to_array(&xyzwpr)) },
+        (None, Some(joints)) => Pose::joints(to_array(&joints)),
+        _ => return Err("expected either X to R or J1 to J6".to_string()),
+    };
+    let ext = ext.map(|ext| to_array(&ext)).filter(|ext| ext.iter().any(|&e| e != 0.0));
+    Ok((name, Pose { ext, ..pose }))
+}
+
+fn to_array<const N: usize>(values: &[f32]) -> [f32; N] {
+    let mut array = [0.0; N];
+    array.copy_from_slice(values);
+    array
+}
+
+/// `N U T, 0, 0, 0`
+fn parse_config(text: &str, config: &mut Configuration) -> Result<(), String> {
+    let mut parts = text.split(',').map(str::trim);
+    for flag in parts.next().unwrap_or_default().split_whitespace() {
+        match flag {
+            "N" => config.flip = FlipNoFlip::NoFlip,
+            "F" => config.flip = FlipNoFlip::Flip,
+            "U" => config.up = UpDown::Up,
+            "D" => config.up = UpDown::Down,
+            "T" => config.front = FrontBack::Front,
+            "B" => config.front = FrontBack::Back,
+            "L" => config.left = LeftRight::Left,
+            "R" => config.left = LeftRight::Right,
+            other => return Err(format!("unsupported CONFIG flag {}", other)),
+        }
+    }
+    let turns: Vec<i8> = parts.map(parse_number).collect::<Result<_, _>>()?;
+    match turns[..] {
+        [turn4, turn5, turn6] => {
+            config.turn4 = turn4;
+            config.turn5 = turn5;
+            config.turn6 = turn6;
+        }
+        _ => return Err(format!("expected three turn numbers in CONFIG '{}'", text)),
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct LsWriter {
+    /// `/MN` lines without their numbers; a `\n` starts a continuation line.
+    lines: Vec<String>,
+    positions: String,
+    next_position: u32,
+    frame: Option<u8>,
+    tool: Option<u8>,
+}
+
+enum LsPoint<'a> {
+    Cartesian(&'a Configuration, &'a Position),
+    Joints(&'a JointAngles),
+}
+
+impl LsWriter {
+    fn packet(&mut self, packet: &PacketEnum) -> Result<(), String> {
+        let instruction = match packet {
+            PacketEnum::Command(Command::FrcWriteDOUT(write)) => {
+                self.lines.push(format!("  DO[{}]={} ;", write.port_number, on_off(write.port_value)));
+                return Ok(());
+            }
+            PacketEnum::Instruction(instruction) => instruction,
+            PacketEnum::Command(_) | PacketEnum::Communication(_) => {
+                return Err("only FRC_WriteDOUT commands can be written to a TP program".to_string())
+            }
+        };
+        let line = match instruction {
+            Instruction::FrcWaitDIN(wait) => format!("  WAIT DI[{}]={} ;", wait.port_number, on_off(wait.port_value)),
+            Instruction::FrcSetUFrame(set) => {
+                self.frame = Some(set.frame_number);
+                format!("  UFRAME_NUM={} ;", set.frame_number)
+            }
+            Instruction::FrcSetUTool(set) => {
+                self.tool = Some(set.tool_number);
+                format!("  UTOOL_NUM={} ;", set.tool_number)
+            }
+            Instruction::FrcWaitTime(wait) => format!("  WAIT {:>6.2}(sec) ;", wait.time),
+            Instruction::FrcSetPayLoad(payload) => format!("  PAYLOAD[{}] ;", payload.schedule_number),
+            Instruction::FrcCall(call) => format!("  CALL {} ;", call.program_name),
+            Instruction::FrcLinearMotion(m) => {
+                self.motion("L", None, LsPoint::Cartesian(&m.configuration, &m.position), &m.speed, &m.termination, &m.options, false)?
+            }
+            Instruction::FrcLinearRelative(m) => {
+                self.motion("L", None, LsPoint::Cartesian(&m.configuration, &m.position), &m.speed, &m.termination, &m.options, true)?
+            }
+            Instruction::FrcLinearMotionJRep(m) => {
+                self.motion("L", None, LsPoint::Joints(&m.joint_angles), &m.speed, &m.termination, &m.options, false)?
+            }
+            Instruction::FrcLinearRelativeJRep(m) => {
+                self.motion("L", None, LsPoint::Joints(&m.joint_angles), &m.speed, &m.termination, &m.options, true)?
+            }
+            Instruction::FrcJointMotion(m) => {
+                self.motion("J", None, LsPoint::Cartesian(&m.configuration, &m.position), &m.speed, &m.termination, &m.options, false)?
+            }
+            Instruction::FrcJointRelative(m) => {
+                self.motion("J", None, LsPoint::Cartesian(&m.configuration, &m.position), &m.speed, &m.termination, &m.options, true)?
+            }
+            Instruction::FrcJointMotionJRep(m) => {
+                self.motion("J", None, LsPoint::Joints(&m.joint_angles), &m.speed, &m.termination, &m.options, false)?
+            }
+            Instruction::FrcJointRelativeJRep(m) => {
+                self.motion("J", None, LsPoint::Joints(&m.joint_angles), &m.speed, &m.termination, &m.options, true)?
+            }
+            Instruction::FrcCircularMotion(m) => {
+                let via = LsPoint::Cartesian(&m.via_configuration, &m.via_position);
+                self.motion("C", Some(via), LsPoint::Cartesian(&m.configuration, &m.position), &m.speed, &m.termination, &m.options, false)?
+            }
+            Instruction::FrcCircularRelative(m) => {
+                let via = LsPoint::Cartesian(&m.via_configuration, &m.via_position);
+                self.motion("C", Some(via), LsPoint::Cartesian(&m.configuration, &m.position), &m.speed, &m.termination, &m.options, true)?
+            }
+        };
+        self.lines.push(line);
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn motion(
+        &mut self,
+        kind: &str,
+        via: Option<LsPoint>,
+        to: LsPoint,
+        speed: &Speed,
+        termination: &Termination,
+        options: &MotionOptions,
+        relative: bool,
+    ) -> Result<String, String> {
+        if let LsPoint::Cartesian(config, _) = &to {
+            if self.frame != Some(config.u_frame_number) {
+                self.frame = Some(config.u_frame_number);
+                self.lines.push(format!("  UFRAME_NUM={} ;", config.u_frame_number));
+            }
+            if self.tool != Some(config.u_tool_number) {
+                self.tool = Some(config.u_tool_number);
+                self.lines.push(format!("  UTOOL_NUM={} ;", config.u_tool_number));
+            }
+        }
+
+        let speed = match *speed {
+            Speed::MmSec(value) => format!("{}mm/sec", value),
+            Speed::InchMin(value) => format!("{}inch/min", value),
+            Speed::Percent(value) => format!("{}%", value),
+            Speed::Time(tenths) => format!("{:.1}sec", tenths as f32 / 10.0),
+            Speed::Millis(millis) => format!("{}msec", millis),
+        };
+        let termination = match *termination {
+            Termination::Fine => "FINE".to_string(),
+            Termination::Cnt(value) => format!("CNT{}", value),
+            Termination::Cr(value) => format!("CR{}", value),
+        };
+        let mut suffix = String::new();
+        let MotionOptions { acc, offset_pr_number, vision_pr_number, mrot, lcb, port_output, no_blend } = options;
+        if lcb.is_some() || port_output.is_some() || *no_blend {
+            return Err("local conditions, port outputs and NoBlend have no TP equivalent".to_string());
+        }
+        if let Some(acc) = acc {
+            let _ = write!(suffix, " ACC{}", acc);
+        }
+        if let Some(register) = offset_pr_number {
+            let _ = write!(suffix, " Offset,PR[{}]", register);
+        }
+        if let Some(register) = vision_pr_number {
+            let _ = write!(suffix, " VOFFSET,VR[{}]", register);
+        }
+        if *mrot {
+            suffix.push_str(" MROT");
+        }
+        if relative {
+            suffix.push_str(" INC");
+        }
+
+        Ok(match via {
+            Some(via) => {
+                let via = self.position(via);
+                let to = self.position(to);
+                format!("{} {}    \n  {} {} {}{}    ;", kind, via, to, speed, termination, suffix)
+            }
+            None => format!("{} {} {} {}{}    ;", kind, self.position(to), speed, termination, suffix),
+        })
+    }
+
+    fn position(&mut self, point: LsPoint) -> String {
+        self.next_position += 1;
+        let number = self.next_position;
+        let out = &mut self.positions;
+        let _ = writeln!(out, "P[{}]{{\n   GP1:", number);
+        let (values, ext): (Vec<(&str, f32, &str)>, [f32; 3]) = match point {
+            LsPoint::Cartesian(config, position) => {
+                // `R` is the default and left out, as the controller does.
+                let flags: Vec<&str> = [
+                    Some(if config.flip == FlipNoFlip::Flip { "F" } else { "N" }),
+                    (config.left == LeftRight::Left).then_some("L"),
+                    Some(if config.up == UpDown::Up { "U" } else { "D" }),
+                    Some(if config.front == FrontBack::Front { "T" } else { "B" }),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                let _ = writeln!(
+                    out,
+                    "\tUF : {}, UT : {},\t\tCONFIG : '{}, {}, {}, {}',",
+                    config.u_frame_number, config.u_tool_number, flags.join(" "), config.turn4, config.turn5, config.turn6
+                );
+                let values = vec![
+                    ("X", position.x, " mm"),
+                    ("Y", position.y, " mm"),
+                    ("Z", position.z, " mm"),
+                    ("W", position.w, "deg"),
+                    ("P", position.p, "deg"),
+                    ("R", position.r, "deg"),
+                ];
+                (values, [position.ext1, position.ext2, position.ext3])
+            }
+            LsPoint::Joints(joints) => {
+                let _ = writeln!(out, "\tUF : {}, UT : {},", self.frame.unwrap_or(0), self.tool.unwrap_or(1));
+                let values = vec![
+                    ("J1", joints.j1, "deg"),
+                    ("J2", joints.j2, "deg"),
+                    ("J3", joints.j3, "deg"),
+                    ("J4", joints.j4, "deg"),
+                    ("J5", joints.j5, "deg"),
+                    ("J6", joints.j6, "deg"),
+                ];
+                (values, [joints.j7, joints.j8, joints.j9])
+            }
+        };
+        let mut values = values;
+        if ext.iter().any(|&e| e != 0.0) {
+            values.extend([("E1", ext[0], " mm"), ("E2", ext[1], " mm"), ("E3", ext[2], " mm")]);
+        }
+        let rows: Vec<String> = values
+            .chunks(3)
+            .map(|row| {
+                let row: Vec<String> = row.iter().map(|(key, value, unit)| format!("{:<2}= {:>9.3} {}", key, value, unit)).collect();
+                format!("\t{}", row.join(",\t"))
+            })
+            .collect();
+        let _ = writeln!(out, "{}\n}};", rows.join(",\n"));
+        format!("P[{}]", number)
+    }
+}
+
+fn on_off(value: OnOff) -> &'static str {
+    match value {
+        OnOff::ON => "ON",
+        OnOff::OFF => "OFF",
+    }
+}
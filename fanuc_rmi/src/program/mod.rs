@@ -0,0 +1,63 @@
+//! Robot programs written as data.
+//!
+//! A [`ProgramFile`] lists user frames and tools to write, named poses and the
+//! steps to run, and loads into the same packet queue as
+//! [`FanucDriver::start_program`](crate::drivers::FanucDriver::start_program)
+//! takes. JSON and ASCII TP listings (`.LS`) are always available; TOML and
+//! YAML need the `toml` and `yaml` features. Speeds and terminations use their RMI field names.
+//! [`PathGenerator`] builds queues for raster, spiral, contour and weave
+//! patterns directly.
+//!
+//! ```toml
+//! name = "pick"
+//!
+//! [defaults]
+//! frame = 1
+//! tool = 1
+//! speed = { SpeedType = "mmSec", Speed = 200 }
+//! termination = { TermType = "CNT", TermValue = 50 }
+//!
+//! [[frames]]
+//! number = 1
+//! xyzwpr = [600, 0, 200, 0, 0, 0]
+//!
+//! [poses]
+//! home = { joints = [0, 0, 0, 0, -90, 0] }
+//! above = { xyzwpr = [0, 0, 100, 180, 0, 0] }
+//!
+//! [[steps]]
+//! type = "joint"
+//! to = "home"
+//!
+//! [[steps]]
+//! type = "linear"
+//! to = "above"
+//! termination = { TermType = "FINE", TermValue = 1 }
+//!
+//! [[steps]]
+//! type = "linear"
+//! to = { xyzwpr = [0, 0, -100, 0, 0, 0] }
+//! relative = true
+//!
+//! [[steps]]
+//! type = "write_dout"
+//! port = 1
+//! value = "ON"
+//!
+//! [[steps]]
+//! type = "wait_time"
+//! seconds = 0.5
+//! ```
+
+mod estimate;
+mod file;
+mod lint;
+mod ls;
+mod paths;
+mod state;
+
+pub use estimate::*;
+pub use file::*;
+pub use lint::*;
+pub use ls::*;
+pub use paths::*;
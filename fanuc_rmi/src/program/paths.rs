@@ -0,0 +1,468 @@
+use std::collections::VecDeque;
+use std::f64::consts::{FRAC_PI_8, TAU};
+
+use crate::instructions::{FrcLinearMotion, MotionSettings, DEFAULT_SPEED_MM_SEC};
+use crate::{Configuration, FrcError, PacketEnum, Speed, Termination};
+
+/// Longest straight move used to follow a curve unless
+/// [`PathGenerator::max_segment`] is set, in mm.
+pub const DEFAULT_MAX_SEGMENT: f64 = 5.0;
+
+/// Below this length, in mm, two points are treated as the same.
+const LENGTH_EPSILON: f64 = 1e-6;
+
+/// Side-to-side motion of [`PathGenerator::weave`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weave {
+    /// Straight moves between the two sides.
+    Zigzag,
+    /// A sine wave, followed in moves of at most the generator's `max_segment`.
+    Sine,
+}
+
+/// Generates `FrcLinearMotion` instructions for raster, spiral, contour and
+/// weave patterns in the XY plane of a user frame.
+///
+/// Points are given in mm in the frame and run at the generator's height,
+/// orientation and speed. Every move but the last blends with the
+/// generator's termination, `CNT 100` by default; the last terminates `FINE`
+/// so the pattern ends at rest.
+///
+/// ```
+/// use fanuc_rmi::program::PathGenerator;
+///
+/// // Sand a 200 x 100 mm panel in passes 25 mm apart.
+/// let queue = PathGenerator::new()
+///     .frame(2)
+///     .tool(1)
+///     .height(10.0)
+///     .speed_mm_s(150)
+///     .raster_rect([0.0, 0.0], 200.0, 100.0, 25.0)
+///     .unwrap();
+/// assert_eq!(queue.len(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PathGenerator {
+    settings: MotionSettings,
+    height: f64,
+    travel_height: Option<f64>,
+    speed: Speed,
+    termination: Termination,
+    max_segment: f64,
+    first_sequence_id: u32,
+}
+
+impl Default for PathGenerator {
+    fn default() -> Self {
+        Self {
+            settings: MotionSettings::default(),
+            height: 0.0,
+            travel_height: None,
+            speed: Speed::MmSec(DEFAULT_SPEED_MM_SEC),
+            termination: Termination::Cnt(100),
+            max_segment: DEFAULT_MAX_SEGMENT,
+            first_sequence_id: 1,
+        }
+    }
+}
+
+impl PathGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configuration and orientation of every move.
+    pub fn settings(mut self, settings: MotionSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Configuration sent with every move, including its UFrame and UTool.
+    pub fn configuration(mut self, configuration: Configuration) -> Self {
+        self.settings = self.settings.configuration(configuration);
+        self
+    }
+
+    pub fn frame(mut self, u_frame_number: u8) -> Self {
+        self.settings = self.settings.frame(u_frame_number);
+        self
+    }
+
+    pub fn tool(mut self, u_tool_number: u8) -> Self {
+        self.settings = self.settings.tool(u_tool_number);
+        self
+    }
+
+    /// Tool orientation W/P/R, in degrees, held for every move; tool down by default.
+    pub fn orientation(mut self, w: f32, p: f32, r: f32) -> Self {
+        self.settings = self.settings.orientation(w, p, r);
+        self
+    }
+
+    /// Z of every point in the frame, in mm.
+    pub fn height(mut self, z: f64) -> Self {
+        self.height = z;
+        self
+    }
+
+    /// Z in the frame, in mm, that [`raster`](Self::raster) lifts to where
+    /// moving on would cross outside a concave outline.
+    pub fn travel_height(mut self, z: f64) -> Self {
+        self.travel_height = Some(z);
+        self
+    }
+
+    pub fn speed(mut self, speed: Speed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn speed_mm_s(self, speed: u16) -> Self {
+        self.speed(Speed::MmSec(speed))
+    }
+
+    /// Termination of every move but the last.
+    pub fn termination(mut self, termination: Termination) -> Self {
+        self.termination = termination;
+        self
+    }
+
+    /// Longest straight move used to follow spirals and sine weaves, in mm.
+    pub fn max_segment(mut self, mm: f64) -> Self {
+        self.max_segment = mm;
+        self
+    }
+
+    pub fn first_sequence_id(mut self, sequence_id: u32) -> Self {
+        self.first_sequence_id = sequence_id;
+        self
+    }
+
+    /// Boustrophedon fill of the polygon `outline`, in passes `spacing` apart
+    /// that run `angle_degrees` from the frame's X axis and alternate in
+    /// direction. The passes are centered between the outline's extremes.
+    /// Where going on to the next pass, or across a gap in a pass, would leave
+    /// a concave outline, the robot lifts to the
+    /// [`travel_height`](Self::travel_height); without one such outlines are
+    /// rejected.
+    pub fn raster(&self, outline: &[[f64; 2]], spacing: f64, angle_degrees: f64) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let outline = polygon(outline)?;
+        positive("spacing", spacing)?;
+        let (sin, cos) = angle_degrees.to_radians().sin_cos();
+        // Passes run along X in the rotated outline.
+        let rotated: Vec<[f64; 2]> = outline.iter().map(|&[x, y]| [x * cos + y * sin, y * cos - x * sin]).collect();
+        let low = rotated.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min);
+        let high = rotated.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max);
+        let passes = ((high - low) / spacing + LENGTH_EPSILON).floor() as usize + 1;
+        let margin = (high - low - (passes - 1) as f64 * spacing) / 2.0;
+
+        let mut strokes: Vec<[[f64; 2]; 2]> = Vec::new();
+        for pass in 0..passes {
+            let y = low + margin + pass as f64 * spacing;
+            // Nudged inside so passes along an edge still cross the outline.
+            let mut crossings = crossings(&rotated, y.clamp(low + LENGTH_EPSILON, high - LENGTH_EPSILON));
+            if pass % 2 == 1 {
+                crossings.reverse();
+            }
+            let unrotated = |x: f64| [x * cos - y * sin, x * sin + y * cos];
+            strokes.extend(crossings.chunks_exact(2).map(|pair| [unrotated(pair[0]), unrotated(pair[1])]));
+        }
+
+        let mut points = Vec::new();
+        for (i, [from, to]) in strokes.iter().enumerate() {
+            if let Some([_, previous]) = i.checked_sub(1).map(|previous| strokes[previous]) {
+                if !stays_inside(&outline, &previous, from) {
+                    let Some(travel) = self.travel_height else {
+                        return Err(path_error("the raster would cross outside the concave outline; set a travel height to lift over it"));
+                    };
+                    points.push([previous[0], previous[1], travel]);
+                    points.push([from[0], from[1], travel]);
+                }
+            }
+            points.extend([[from[0], from[1], self.height], [to[0], to[1], self.height]]);
+        }
+        self.moves_xyz(&points)
+    }
+
+    /// [`raster`](Self::raster) over the rectangle from `corner` spanning
+    /// `width` along X and `height` along Y, in passes along X.
+    pub fn raster_rect(&self, corner: [f64; 2], width: f64, height: f64, spacing: f64) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let [x, y] = corner;
+        self.raster(&[[x, y], [x + width, y], [x + width, y + height], [x, y + height]], spacing, 0.0)
+    }
+
+    /// Archimedean spiral around `center` from `from_radius` to `to_radius`,
+    /// `pitch` mm further out (or in) each counterclockwise turn. It starts
+    /// on the +X side of the center.
+    pub fn spiral(&self, center: [f64; 2], from_radius: f64, to_radius: f64, pitch: f64) -> Result<VecDeque<PacketEnum>, FrcError> {
+        positive("pitch", pitch)?;
+        if from_radius < 0.0 || to_radius < 0.0 {
+            return Err(path_error("spiral radii cannot be negative"));
+        }
+        if (to_radius - from_radius).abs() < LENGTH_EPSILON {
+            return Err(path_error("a spiral needs different start and end radii"));
+        }
+        self.segment_length()?;
+        let turn = pitch.copysign(to_radius - from_radius);
+        let sweep = TAU * (to_radius - from_radius) / turn;
+        let radius = |angle: f64| from_radius + turn * angle / TAU;
+        let point = |angle: f64| {
+            let (sin, cos) = angle.sin_cos();
+            [center[0] + radius(angle) * cos, center[1] + radius(angle) * sin]
+        };
+
+        let mut points = vec![point(0.0)];
+        let mut angle = 0.0;
+        while angle < sweep {
+            let widest = radius(angle).max(radius((angle + FRAC_PI_8).min(sweep)));
+            angle = (angle + (self.max_segment / widest).min(FRAC_PI_8)).min(sweep);
+            points.push(point(angle));
+        }
+        self.moves(&points)
+    }
+
+    /// Closed contours of the polygon `outline`, the first on the outline and
+    /// each next one `spacing` further inside, or outside when `spacing` is
+    /// negative. Stops after `count` contours or once an inward contour would
+    /// collapse. Corners are offset to a sharp point, which suits convex and
+    /// gently concave outlines.
+    pub fn contours(&self, outline: &[[f64; 2]], spacing: f64, count: usize) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let mut outline = polygon(outline)?;
+        if spacing.abs() < LENGTH_EPSILON {
+            return Err(path_error("contour spacing cannot be zero"));
+        }
+        if count == 0 {
+            return Err(path_error("at least one contour is needed"));
+        }
+        // Counterclockwise, so the inside is on the left of every edge.
+        if area(&outline) < 0.0 {
+            outline.reverse();
+        }
+
+        let mut points = Vec::new();
+        for contour in 0..count {
+            let Some(offset) = offset(&outline, spacing * contour as f64) else { break };
+            points.extend(offset.iter().chain(offset.first()));
+        }
+        self.moves(&points)
+    }
+
+    /// Weave along the straight line from `from` to `to`, swinging
+    /// `amplitude` mm to either side once per `wavelength` mm. The wavelength
+    /// is stretched so that a whole number of cycles fits and the weave ends
+    /// on `to`.
+    pub fn weave(&self, from: [f64; 2], to: [f64; 2], pattern: Weave, amplitude: f64, wavelength: f64) -> Result<VecDeque<PacketEnum>, FrcError> {
+        positive("amplitude", amplitude)?;
+        positive("wavelength", wavelength)?;
+        let along = sub(&to, &from);
+        let length = norm(&along);
+        if length < LENGTH_EPSILON {
+            return Err(path_error("a weave needs different start and end points"));
+        }
+        let direction = scale(&along, 1.0 / length);
+        let side = [-direction[1], direction[0]];
+        let cycles = (length / wavelength).round().max(1.0) as usize;
+        let wavelength = length / cycles as f64;
+        let point = |distance: f64, offset: f64| add(&add(&from, &scale(&direction, distance)), &scale(&side, offset));
+
+        let points: Vec<[f64; 2]> = match pattern {
+            Weave::Zigzag => {
+                let peaks = (0..2 * cycles).map(|k| {
+                    let offset = if k % 2 == 0 { amplitude } else { -amplitude };
+                    point((k as f64 + 0.5) * wavelength / 2.0, offset)
+                });
+                std::iter::once(from).chain(peaks).chain(std::iter::once(to)).collect()
+            }
+            Weave::Sine => {
+                self.segment_length()?;
+                // Steps by quarter cycles land on every peak.
+                let cycle_length = wavelength.hypot(4.0 * amplitude);
+                let steps = ((cycle_length / self.max_segment / 4.0).ceil() as usize).max(2) * 4;
+                (0..=cycles * steps)
+                    .map(|i| {
+                        let distance = i as f64 * wavelength / steps as f64;
+                        point(distance, amplitude * (TAU * i as f64 / steps as f64).sin())
+                    })
+                    .collect()
+            }
+        };
+        self.moves(&points)
+    }
+
+    fn segment_length(&self) -> Result<(), FrcError> {
+        positive("max_segment", self.max_segment)
+    }
+
+    /// One linear move to each point at the generator's height.
+    fn moves(&self, points: &[[f64; 2]]) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let points: Vec<[f64; 3]> = points.iter().map(|&[x, y]| [x, y, self.height]).collect();
+        self.moves_xyz(&points)
+    }
+
+    /// One linear move to each point, skipping repeats.
+    fn moves_xyz(&self, points: &[[f64; 3]]) -> Result<VecDeque<PacketEnum>, FrcError> {
+        let kept = without_repeats(points);
+        if kept.is_empty() {
+            return Err(path_error("the pattern has no points"));
+        }
+        let last = kept.len() - 1;
+        Ok(kept
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let position = self.settings.position(*point);
+                let termination = if i == last { Termination::Fine } else { self.termination };
+                let configuration = self.settings.configuration;
+                let motion = FrcLinearMotion::new(self.first_sequence_id + i as u32, configuration, position, self.speed, termination);
+                PacketEnum::Instruction(motion.into())
+            })
+            .collect())
+    }
+}
+
+fn path_error(message: &str) -> FrcError {
+    FrcError::Program { step: None, message: message.to_string() }
+}
+
+fn positive(name: &str, value: f64) -> Result<(), FrcError> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(FrcError::Program { step: None, message: format!("{} must be positive, got {}", name, value) })
+    }
+}
+
+/// The outline without repeated points, including a closing repeat of the first.
+fn polygon(outline: &[[f64; 2]]) -> Result<Vec<[f64; 2]>, FrcError> {
+    let mut points = without_repeats(outline);
+    if points.len() > 1 && norm(&sub(&points[0], &points[points.len() - 1])) < LENGTH_EPSILON {
+        points.pop();
+    }
+    if points.len() < 3 || area(&points).abs() < LENGTH_EPSILON {
+        return Err(path_error("an outline needs at least three points enclosing an area"));
+    }
+    Ok(points)
+}
+
+fn without_repeats<const N: usize>(points: &[[f64; N]]) -> Vec<[f64; N]> {
+    let mut kept: Vec<[f64; N]> = Vec::with_capacity(points.len());
+    for point in points {
+        let distance = |last: &[f64; N]| (0..N).map(|i| (point[i] - last[i]).powi(2)).sum::<f64>().sqrt();
+        if kept.last().is_none_or(|last| distance(last) >= LENGTH_EPSILON) {
+            kept.push(*point);
+        }
+    }
+    kept
+}
+
+/// Signed area, positive when counterclockwise.
+fn area(points: &[[f64; 2]]) -> f64 {
+    let n = points.len();
+    (0..n).map(|i| cross(&points[i], &points[(i + 1) % n])).sum::<f64>() / 2.0
+}
+
+/// X of every crossing of the horizontal line at `y` with the outline, in
+/// order. Each edge includes its lower end only, so vertices count once.
+fn crossings(outline: &[[f64; 2]], y: f64) -> Vec<f64> {
+    let n = outline.len();
+    let mut xs: Vec<f64> = (0..n)
+        .filter_map(|i| {
+            let (a, b) = (outline[i], outline[(i + 1) % n]);
+            let (low, high) = if a[1] <= b[1] { (a, b) } else { (b, a) };
+            (low[1] <= y && y < high[1]).then(|| low[0] + (y - low[1]) / (high[1] - low[1]) * (high[0] - low[0]))
+        })
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs
+}
+
+/// Whether the straight move from `from` to `to` stays inside the outline or
+/// on its edges.
+fn stays_inside(outline: &[[f64; 2]], from: &[f64; 2], to: &[f64; 2]) -> bool {
+    let n = outline.len();
+    let along = sub(to, from);
+    let crosses_edge = (0..n).any(|i| {
+        let (a, b) = (outline[i], outline[(i + 1) % n]);
+        let edge = sub(&b, &a);
+        let (side_a, side_b) = (cross(&along, &sub(&a, from)), cross(&along, &sub(&b, from)));
+        let (side_from, side_to) = (cross(&edge, &sub(from, &a)), cross(&edge, &sub(to, &a)));
+        side_a * side_b < 0.0 && side_from * side_to < 0.0
+    });
+    if crosses_edge {
+        return false;
+    }
+    // Without crossing an edge, the move is all inside or all outside; its
+    // midpoint tells which.
+    let middle = scale(&add(from, to), 0.5);
+    let on_edge = (0..n).any(|i| distance_to_segment(&middle, &outline[i], &outline[(i + 1) % n]) < LENGTH_EPSILON.sqrt());
+    let inside = (0..n)
+        .filter(|&i| {
+            let (a, b) = (outline[i], outline[(i + 1) % n]);
+            (a[1] > middle[1]) != (b[1] > middle[1]) && middle[0] < a[0] + (middle[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+        })
+        .count()
+        % 2
+        == 1;
+    on_edge || inside
+}
+
+fn distance_to_segment(point: &[f64; 2], a: &[f64; 2], b: &[f64; 2]) -> f64 {
+    let edge = sub(b, a);
+    let t = (dot(&sub(point, a), &edge) / dot(&edge, &edge)).clamp(0.0, 1.0);
+    norm(&sub(point, &add(a, &scale(&edge, t))))
+}
+
+/// The counterclockwise outline moved `distance` to the left of every edge,
+/// or `None` once an edge would turn around.
+fn offset(outline: &[[f64; 2]], distance: f64) -> Option<Vec<[f64; 2]>> {
+    let n = outline.len();
+    let edges: Vec<([f64; 2], [f64; 2])> = (0..n)
+        .map(|i| {
+            let along = sub(&outline[(i + 1) % n], &outline[i]);
+            let direction = scale(&along, 1.0 / norm(&along));
+            let normal = [-direction[1], direction[0]];
+            (add(&outline[i], &scale(&normal, distance)), direction)
+        })
+        .collect();
+    let points: Vec<[f64; 2]> = (0..n)
+        .map(|i| {
+            let (previous, direction) = edges[(i + n - 1) % n];
+            let (start, next) = edges[i];
+            let turn = cross(&direction, &next);
+            if turn.abs() < LENGTH_EPSILON {
+                start
+            } else {
+                add(&previous, &scale(&direction, cross(&sub(&start, &previous), &next) / turn))
+            }
+        })
+        .collect();
+    let keeps_direction = (0..n).all(|i| {
+        let along = sub(&points[(i + 1) % n], &points[i]);
+        norm(&along) >= LENGTH_EPSILON && dot(&along, &edges[i].1) > 0.0
+    });
+    keeps_direction.then_some(points)
+}
+
+fn add(a: &[f64; 2], b: &[f64; 2]) -> [f64; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: &[f64; 2], b: &[f64; 2]) -> [f64; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(a: &[f64; 2], factor: f64) -> [f64; 2] {
+    [a[0] * factor, a[1] * factor]
+}
+
+fn dot(a: &[f64; 2], b: &[f64; 2]) -> f64 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: &[f64; 2], b: &[f64; 2]) -> f64 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn norm(a: &[f64; 2]) -> f64 {
+    dot(a, a).sqrt()
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crate::packets::{Command, Instruction};
+use crate::{Configuration, FrameData, Position};
+
+/// The user frames and tools a program runs with, and which of them are
+/// active as it goes; what [`Linter`](super::Linter) and
+/// [`CycleTimeEstimator`](super::CycleTimeEstimator) both follow.
+#[derive(Debug, Clone)]
+pub(super) struct ProgramState {
+    pub(super) uframes: HashMap<u8, FrameData>,
+    pub(super) utools: HashMap<u8, FrameData>,
+    /// Frame and tool of `start`.
+    pub(super) configuration: Configuration,
+    pub(super) start: Option<Position>,
+    pub(super) frame: u8,
+    pub(super) tool: u8,
+    /// TCP in world coordinates, while it can be followed.
+    pub(super) current: Option<Position>,
+}
+
+impl Default for ProgramState {
+    fn default() -> Self {
+        let configuration = Configuration::default();
+        Self {
+            uframes: HashMap::from([(0, FrameData::default())]),
+            utools: HashMap::from([(0, FrameData::default())]),
+            frame: configuration.u_frame_number,
+            tool: configuration.u_tool_number,
+            configuration,
+            start: None,
+            current: None,
+        }
+    }
+}
+
+impl ProgramState {
+    pub(super) fn uframe(&mut self, number: u8, frame: FrameData) {
+        self.uframes.insert(number, frame);
+    }
+
+    pub(super) fn utool(&mut self, number: u8, tool: FrameData) {
+        self.utools.insert(number, tool);
+    }
+
+    pub(super) fn start(&mut self, configuration: Configuration, position: &Position) {
+        self.configuration = configuration;
+        self.start = Some(*position);
+    }
+
+    /// A copy to follow a program with, at its start.
+    pub(super) fn begin(&self) -> Self {
+        let mut state = self.clone();
+        state.frame = self.configuration.u_frame_number;
+        state.tool = self.configuration.u_tool_number;
+        state.current = self.start.and_then(|start| state.in_world(&start, state.frame));
+        state
+    }
+
+    /// Follows frame and tool writes and selections.
+    pub(super) fn command(&mut self, command: &Command) {
+        match command {
+            Command::FrcWriteUFrameData(write) => self.uframe(write.frame_number, write.frame),
+            Command::FrcWriteUToolData(write) => self.utool(write.tool_number, write.frame),
+            Command::FrcSetUFrameUTool(set) => {
+                self.frame = set.uframe_number;
+                self.tool = set.utool_number;
+                self.current = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Follows frame and tool selections.
+    pub(super) fn instruction(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::FrcSetUFrame(set) => self.frame = set.frame_number,
+            Instruction::FrcSetUTool(set) => {
+                self.tool = set.tool_number;
+                self.current = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves the TCP to `position`, in the frame and tool of `configuration`.
+    pub(super) fn arrive(&mut self, configuration: &Configuration, position: &Position) {
+        self.tool = configuration.u_tool_number;
+        self.current = self.in_world(position, configuration.u_frame_number);
+    }
+
+    /// The current position moved by `offset`, in user frame `frame`.
+    pub(super) fn offset(&self, frame: u8, offset: &Position) -> Option<Position> {
+        let current = self.in_frame(&self.current?, frame)?;
+        Some(Position {
+            x: current.x + offset.x,
+            y: current.y + offset.y,
+            z: current.z + offset.z,
+            w: current.w + offset.w,
+            p: current.p + offset.p,
+            r: current.r + offset.r,
+            ext1: current.ext1 + offset.ext1,
+            ext2: current.ext2 + offset.ext2,
+            ext3: current.ext3 + offset.ext3,
+        })
+    }
+
+    /// The active tool.
+    pub(super) fn utool_data(&self) -> Option<&FrameData> {
+        self.utools.get(&self.tool)
+    }
+
+    pub(super) fn in_world(&self, position: &Position, frame: u8) -> Option<Position> {
+        let frame = self.uframes.get(&frame)?;
+        Some(position.change_frame(frame, &FrameData::default()))
+    }
+
+    pub(super) fn in_frame(&self, position: &Position, frame: u8) -> Option<Position> {
+        let frame = self.uframes.get(&frame)?;
+        Some(position.change_frame(&FrameData::default(), frame))
+    }
+}
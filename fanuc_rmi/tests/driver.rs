@@ -0,0 +1,489 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use fanuc_rmi::commands::FrcSetOverride;
+use fanuc_rmi::drivers::{
+    program_channel, DriverEvent, FanucDriver, FanucDriverConfig, ProgramControl, ProgramProgress, ProgramSummary, RecoveryPolicy,
+    RecoveryReport, RecoveryStep, MAX_IN_FLIGHT,
+};
+use fanuc_rmi::instructions::Move;
+use fanuc_rmi::packets::Command;
+use fanuc_rmi::{FanucErrorCode, FrcError, PacketEnum, Position, Termination};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// The RMI session socket of a fake controller, scripted packet by packet.
+struct Session {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Session {
+    fn new(stream: TcpStream) -> Self {
+        let (reader, writer) = stream.into_split();
+        Self { lines: BufReader::new(reader).lines(), writer }
+    }
+
+    async fn receive(&mut self) -> Value {
+        let line = timeout(WAIT, self.lines.next_line()).await.expect("no packet in time").unwrap().expect("socket closed");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    async fn send(&mut self, packet: Value) {
+        self.writer.write_all(format!("{}\r\n", packet).as_bytes()).await.unwrap();
+    }
+
+    /// Receives `command` and answers it with `error_id` and `fields`, returning the request.
+    async fn answer_with(&mut self, command: &str, error_id: u32, fields: Value) -> Value {
+        let request = self.receive().await;
+        assert_eq!(request["Command"], command, "{}", request);
+        let mut reply = json!({ "Command": command, "ErrorID": error_id });
+        if let (Some(reply), Some(fields)) = (reply.as_object_mut(), fields.as_object()) {
+            reply.extend(fields.clone());
+        }
+        self.send(reply).await;
+        request
+    }
+
+    async fn answer(&mut self, command: &str, fields: Value) -> Value {
+        self.answer_with(command, 0, fields).await
+    }
+
+    /// Receives an instruction, returning its sequence ID.
+    async fn receive_instruction(&mut self) -> u32 {
+        let request = self.receive().await;
+        assert!(request["Instruction"].is_string(), "{}", request);
+        request["SequenceID"].as_u64().unwrap() as u32
+    }
+
+    /// Reports the instruction with `sequence_id` as done.
+    async fn finish(&mut self, sequence_id: u32, error_id: u32) {
+        self.send(json!({ "Instruction": "FRC_LinearRelative", "ErrorID": error_id, "SequenceID": sequence_id })).await;
+    }
+}
+
+fn connect_reply(port: u16) -> Value {
+    json!({ "Communication": "FRC_Connect", "ErrorID": 0, "PortNumber": port, "MajorVersion": 1, "MinorVersion": 0 })
+}
+
+/// Answers the `FRC_Connect` handshake with `reply(session_port)`, returning the
+/// driver config and the listener for the session port.
+async fn controller(reply: impl FnOnce(u16) -> Value + Send + 'static) -> (FanucDriverConfig, TcpListener) {
+    let session = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let session_port = session.local_addr().unwrap().port();
+    let primary = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = FanucDriverConfig::new("127.0.0.1", primary.local_addr().unwrap().port() as u32);
+    tokio::spawn(async move {
+        let (stream, _) = primary.accept().await.unwrap();
+        let mut handshake = Session::new(stream);
+        assert_eq!(handshake.receive().await["Communication"], "FRC_Connect");
+        handshake.send(reply(session_port)).await;
+    });
+    (config, session)
+}
+
+async fn connect() -> (FanucDriver, Session) {
+    let (config, listener) = controller(connect_reply).await;
+    let (driver, accepted) = tokio::join!(FanucDriver::connect(config), listener.accept());
+    (driver.unwrap(), Session::new(accepted.unwrap().0))
+}
+
+#[tokio::test]
+async fn dropping_the_last_handle_closes_the_socket() {
+    let (driver, session) = connect().await;
+    let Session { lines, writer } = session;
+    let mut stream = lines.into_inner().into_inner().reunite(writer).unwrap();
+
+    let clone = driver.clone();
+    drop(driver);
+    // A handle is still alive, so the session stays open.
+    let mut byte = [0; 1];
+    assert!(timeout(Duration::from_millis(100), stream.read(&mut byte)).await.is_err());
+
+    drop(clone);
+    let read = timeout(WAIT, stream.read(&mut byte)).await.expect("socket left open");
+    assert_eq!(read.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn commands_fail_once_the_controller_closes_the_socket() {
+    let (driver, session) = connect().await;
+    let mut events = driver.subscribe();
+    drop(session);
+    assert_eq!(timeout(WAIT, events.recv()).await.unwrap().unwrap(), DriverEvent::Disconnected);
+
+    let result = timeout(WAIT, driver.abort()).await.expect("command left waiting");
+    assert!(matches!(result, Err(FrcError::Disconnected())));
+}
+
+#[tokio::test]
+async fn initialize_reports_controller_errors() {
+    let (driver, mut session) = connect().await;
+    let code = |result: Result<(), FrcError>| result.unwrap_err().error_code().cloned();
+
+    // Error replies may leave out the data fields.
+    let in_hold = FanucErrorCode::RMIInHoldState.id();
+    let (result, _) = tokio::join!(driver.initialize(), session.answer_with("FRC_Initialize", in_hold, json!({})));
+    assert_eq!(code(result), Some(FanucErrorCode::RMIInHoldState));
+
+    let (result, _) = tokio::join!(driver.initialize(), session.answer_with("FRC_Initialize", in_hold, json!({ "GroupMask": 1 })));
+    assert_eq!(code(result), Some(FanucErrorCode::RMIInHoldState));
+
+    // A packet the crate does not know still carries its ErrorID.
+    let mystery = async {
+        session.receive().await;
+        session.send(json!({ "Command": "FRC_Mystery", "ErrorID": FanucErrorCode::WaitForCommandDone.id() })).await;
+    };
+    let (result, _) = tokio::join!(driver.initialize(), mystery);
+    assert_eq!(code(result), Some(FanucErrorCode::WaitForCommandDone));
+
+    let wrong_reply = async {
+        session.receive().await;
+        session.send(json!({ "Command": "FRC_Abort", "ErrorID": 0 })).await;
+    };
+    let (result, _) = tokio::join!(driver.initialize(), wrong_reply);
+    assert!(matches!(result, Err(FrcError::UnrecognizedPacket)));
+
+    let malformed = async {
+        session.receive().await;
+        session.send(json!({ "Command": "FRC_Initialize", "ErrorID": 0, "GroupMask": "all" })).await;
+    };
+    let (result, _) = tokio::join!(driver.initialize(), malformed);
+    assert!(matches!(result, Err(FrcError::Serialization(_))));
+
+    let (result, _) = tokio::join!(driver.initialize(), session.answer("FRC_Initialize", json!({ "GroupMask": 1 })));
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn connect_fails_without_a_session_port() {
+    let not_running = FanucErrorCode::RMINotRunning.id();
+    let replies = [
+        json!({ "Communication": "FRC_Connect", "ErrorID": not_running }),
+        json!({ "Communication": "FRC_Connect", "ErrorID": not_running, "PortNumber": 0, "MajorVersion": 1, "MinorVersion": 0 }),
+    ];
+    for reply in replies {
+        let (config, _session) = controller(move |_| reply).await;
+        let error = timeout(WAIT, FanucDriver::connect(config)).await.expect("connect hung").unwrap_err();
+        assert_eq!(error.error_code(), Some(&FanucErrorCode::RMINotRunning));
+    }
+
+    let replies = [
+        json!({ "Communication": "FRC_Connect", "ErrorID": 0 }),
+        json!({ "Communication": "FRC_Connect", "ErrorID": 0, "PortNumber": 0, "MajorVersion": 1, "MinorVersion": 0 }),
+        json!({ "Communication": "FRC_Connect", "ErrorID": 0, "PortNumber": 70000, "MajorVersion": 1, "MinorVersion": 0 }),
+    ];
+    for reply in replies {
+        let (config, _session) = controller(move |_| reply).await;
+        let error = timeout(WAIT, FanucDriver::connect(config)).await.expect("connect hung").unwrap_err();
+        assert!(matches!(error, FrcError::Serialization(_)), "{:?}", error);
+    }
+
+    let (config, _session) = controller(|_| json!({ "Communication": "FRC_Disconnect", "ErrorID": 0 })).await;
+    let error = timeout(WAIT, FanucDriver::connect(config)).await.expect("connect hung").unwrap_err();
+    assert!(matches!(error, FrcError::UnrecognizedPacket));
+}
+
+fn steps(report: &RecoveryReport) -> Vec<RecoveryStep> {
+    report.actions.iter().map(|action| action.step).collect()
+}
+
+async fn recover(driver: &FanucDriver, code: FanucErrorCode) -> RecoveryReport {
+    let error = FrcError::from_error_id(code.id(), "FRC_LinearMotion", Some(3));
+    timeout(WAIT, driver.recover(&error, &RecoveryPolicy::default())).await.expect("recovery hung")
+}
+
+async fn answer_read_error(session: &mut Session) {
+    let alarm = "SRVO-007 External emergency stops";
+    session.answer("FRC_ReadError", json!({ "Count": 1, "ErrorData": alarm })).await;
+}
+
+#[tokio::test]
+async fn rejected_and_busy_requests_need_no_recovery() {
+    use RecoveryStep::*;
+    let (driver, mut session) = connect().await;
+
+    // Reading the alarm changes nothing; the packet has to be fixed first.
+    let (report, _) = tokio::join!(recover(&driver, FanucErrorCode::InvalidSpeedValue), answer_read_error(&mut session));
+    assert_eq!(steps(&report), vec![ReadError]);
+    assert_eq!(report.alarm.as_deref(), Some("SRVO-007 External emergency stops"));
+    assert!(!report.recovered);
+
+    // A busy controller only needs the packet sent again.
+    let report = recover(&driver, FanucErrorCode::WaitForCommandDone).await;
+    assert!(report.actions.is_empty());
+    assert!(report.recovered);
+
+    let (report, _) = tokio::join!(recover(&driver, FanucErrorCode::PleaseCyclePower), answer_read_error(&mut session));
+    assert_eq!(steps(&report), vec![ReadError]);
+    assert!(!report.recovered && report.requires_power_cycle);
+
+    let (report, _) = tokio::join!(recover(&driver, FanucErrorCode::Unrecognized(42)), answer_read_error(&mut session));
+    assert_eq!(steps(&report), vec![ReadError]);
+    assert!(!report.recovered && !report.requires_power_cycle);
+}
+
+#[tokio::test]
+async fn controller_state_errors_reset_and_restore_the_session() {
+    use RecoveryStep::*;
+    let (driver, mut session) = connect().await;
+    let setup = async {
+        driver.set_group_uframe_utool(1, 2, 3).await.unwrap();
+        driver.set_override(40).await.unwrap();
+    };
+    let script = async {
+        session.answer("FRC_SetUFrameUTool", json!({ "Group": 1 })).await;
+        session.answer("FRC_SetOverride", json!({})).await;
+    };
+    tokio::join!(setup, script);
+
+    let script = async {
+        answer_read_error(&mut session).await;
+        session.answer("FRC_Reset", json!({})).await;
+        session.answer("FRC_Abort", json!({})).await;
+        session.answer("FRC_Initialize", json!({ "GroupMask": 1 })).await;
+        let frames = session.answer("FRC_SetUFrameUTool", json!({ "Group": 1 })).await;
+        let speed = session.answer("FRC_SetOverride", json!({})).await;
+        (frames, speed)
+    };
+    let (report, (frames, speed)) = tokio::join!(recover(&driver, FanucErrorCode::RMIInHoldState), script);
+    assert_eq!(steps(&report), vec![ReadError, Reset, Abort, Initialize, RestoreFrames, RestoreOverride]);
+    assert!(report.actions.iter().all(|action| action.result.is_ok()));
+    assert!(report.recovered);
+    assert_eq!((frames["UFrameNumber"].as_u64(), frames["UToolNumber"].as_u64()), (Some(2), Some(3)));
+    assert_eq!(speed["Value"], 40);
+
+    // RMI not running only needs initializing.
+    let script = async {
+        session.answer("FRC_Initialize", json!({ "GroupMask": 1 })).await;
+        session.answer("FRC_SetUFrameUTool", json!({ "Group": 1 })).await;
+        session.answer("FRC_SetOverride", json!({})).await;
+    };
+    let (report, _) = tokio::join!(recover(&driver, FanucErrorCode::RMINotRunning), script);
+    assert_eq!(steps(&report), vec![Initialize, RestoreFrames, RestoreOverride]);
+    assert!(report.recovered);
+
+    // Recovery stops at the first failed step.
+    let script = async {
+        answer_read_error(&mut session).await;
+        session.answer_with("FRC_Reset", FanucErrorCode::CannotResetController.id(), json!({})).await;
+    };
+    let (report, _) = tokio::join!(recover(&driver, FanucErrorCode::ControllerServoOff), script);
+    assert_eq!(steps(&report), vec![ReadError, Reset]);
+    assert!(report.actions[1].result.is_err());
+    assert!(!report.recovered);
+}
+
+fn step(sequence_id: u32) -> PacketEnum {
+    let offset = Position::new(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    PacketEnum::Instruction(Move::linear().by(offset).cnt(100).build(sequence_id).unwrap().into())
+}
+
+async fn run(driver: &FanucDriver, program: Vec<PacketEnum>, control: &ProgramControl) -> ProgramSummary {
+    timeout(WAIT, driver.run_program(program, control)).await.expect("program hung")
+}
+
+fn progress(updates: &mut tokio::sync::broadcast::Receiver<ProgramProgress>) -> Vec<ProgramProgress> {
+    std::iter::from_fn(|| updates.try_recv().ok()).collect()
+}
+
+#[tokio::test]
+async fn run_program_reports_each_step() {
+    use ProgramProgress::*;
+    let (driver, mut session) = connect().await;
+    let control = ProgramControl::new();
+    let mut updates = control.subscribe();
+
+    let program = vec![step(1), step(2), PacketEnum::Command(Command::FrcSetOverride(FrcSetOverride::new(50))), step(3)];
+    let script = async {
+        assert_eq!(session.receive_instruction().await, 1);
+        assert_eq!(session.receive_instruction().await, 2);
+        session.finish(1, 0).await;
+        session.finish(2, 0).await;
+        // The command waits for the instructions before it.
+        session.answer("FRC_SetOverride", json!({})).await;
+        assert_eq!(session.receive_instruction().await, 3);
+        session.finish(3, 0).await;
+    };
+    let (summary, _) = tokio::join!(run(&driver, program, &control), script);
+
+    assert!(summary.is_success());
+    assert_eq!((summary.sent, summary.completed.clone()), (4, vec![1, 2, 3]));
+    assert!(!summary.aborted && !summary.cancelled);
+    assert_eq!(
+        progress(&mut updates),
+        vec![
+            Sent { step: 0, sequence_id: Some(1) },
+            Sent { step: 1, sequence_id: Some(2) },
+            Completed { step: 0, sequence_id: Some(1) },
+            Completed { step: 1, sequence_id: Some(2) },
+            Sent { step: 2, sequence_id: None },
+            Completed { step: 2, sequence_id: None },
+            Sent { step: 3, sequence_id: Some(3) },
+            Completed { step: 3, sequence_id: Some(3) },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn failed_steps_abort_queued_motion() {
+    let (driver, mut session) = connect().await;
+    let control = ProgramControl::new();
+    let mut updates = control.subscribe();
+
+    let speed = FanucErrorCode::InvalidSpeedValue.id();
+    let script = async {
+        for _ in 1..=3 {
+            session.receive_instruction().await;
+        }
+        session.finish(1, speed).await;
+        session.answer("FRC_Abort", json!({})).await;
+    };
+    let (summary, _) = tokio::join!(run(&driver, vec![step(1), step(2), step(3)], &control), script);
+    assert_eq!(summary.sent, 3);
+    assert!(summary.completed.is_empty());
+    assert!(summary.aborted && !summary.cancelled);
+    assert_eq!(summary.errors.len(), 1);
+    assert_eq!(summary.errors[0].0, Some(0));
+    assert_eq!(summary.errors[0].1.error_code(), Some(&FanucErrorCode::InvalidSpeedValue));
+    assert!(progress(&mut updates).iter().any(|update| matches!(update, ProgramProgress::Failed { step: 0, .. })));
+
+    // A packet that cannot be sent stops the run as well, and the abort's own
+    // failure is recorded.
+    let mut invalid = Move::linear().by(Position::default()).build(2).unwrap();
+    invalid.termination = Termination::Cnt(0);
+    let script = async {
+        session.receive_instruction().await;
+        session.answer_with("FRC_Abort", FanucErrorCode::RMIInHoldState.id(), json!({})).await;
+    };
+    let program = vec![step(1), PacketEnum::Instruction(invalid.into())];
+    let (summary, _) = tokio::join!(run(&driver, program, &control), script);
+    assert_eq!(summary.sent, 1);
+    assert!(summary.aborted);
+    assert!(matches!(summary.errors[0], (Some(1), FrcError::InvalidInstruction(_))));
+    assert_eq!(summary.errors[1].0, None);
+    assert_eq!(summary.errors[1].1.error_code(), Some(&FanucErrorCode::RMIInHoldState));
+}
+
+#[tokio::test]
+async fn pause_resume_and_cancel_map_to_commands() {
+    use ProgramProgress::*;
+    let (driver, mut session) = connect().await;
+    let control = ProgramControl::new();
+    let mut updates = control.subscribe();
+
+    let script = async {
+        for _ in 1..=3 {
+            session.receive_instruction().await;
+        }
+        control.pause();
+        session.answer("FRC_Pause", json!({})).await;
+        control.resume();
+        session.answer("FRC_Continue", json!({})).await;
+        session.finish(1, 0).await;
+        // Cancel once the completion is reported.
+        let mut seen = Vec::new();
+        while !matches!(seen.last(), Some(Completed { .. })) {
+            seen.push(updates.recv().await.unwrap());
+        }
+        control.cancel();
+        session.answer("FRC_Abort", json!({})).await;
+        seen
+    };
+    let (summary, mut seen) = tokio::join!(run(&driver, vec![step(1), step(2), step(3)], &control), script);
+
+    assert!(summary.cancelled && summary.aborted);
+    assert!(summary.errors.is_empty());
+    assert!(!summary.is_success());
+    assert_eq!((summary.sent, summary.completed), (3, vec![1]));
+    seen.extend(progress(&mut updates));
+    assert_eq!(
+        seen,
+        vec![
+            Sent { step: 0, sequence_id: Some(1) },
+            Sent { step: 1, sequence_id: Some(2) },
+            Sent { step: 2, sequence_id: Some(3) },
+            Paused,
+            Resumed,
+            Completed { step: 0, sequence_id: Some(1) },
+            Cancelled,
+        ]
+    );
+}
+
+#[test]
+fn cancel_is_final() {
+    let control = ProgramControl::new();
+    control.cancel();
+    control.pause();
+    control.resume();
+    assert!(control.is_cancelled());
+}
+
+#[tokio::test]
+async fn in_flight_sequence_ids_are_not_reused() {
+    let (driver, mut session) = connect().await;
+    let offset = Position::new(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    let first = tokio::spawn({
+        let driver = driver.clone();
+        async move { driver.run_instruction(Move::linear().by(offset).cnt(100).build(7).unwrap()).await }
+    });
+    assert_eq!(session.receive_instruction().await, 7);
+
+    let second = timeout(WAIT, driver.run_instruction(Move::linear().by(offset).cnt(100).build(7).unwrap())).await.unwrap();
+    assert!(matches!(second, Err(FrcError::DuplicateSequenceId(7))));
+    session.finish(7, 0).await;
+    timeout(WAIT, first).await.unwrap().unwrap().unwrap();
+    // Nothing of the refused instruction reached the socket.
+    assert!(timeout(Duration::from_millis(100), session.receive()).await.is_err());
+}
+
+#[tokio::test]
+async fn streamed_programs_hold_back_the_producer() {
+    const STEPS: u32 = 20;
+    let (driver, mut session) = connect().await;
+    let (sender, program) = program_channel(1);
+    let produced = Arc::new(AtomicUsize::new(0));
+    let producer = tokio::spawn({
+        let produced = produced.clone();
+        async move {
+            for sequence_id in 1..=STEPS {
+                sender.send(step(sequence_id)).await.unwrap();
+                produced.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    });
+
+    let script = async {
+        for sequence_id in 1..=MAX_IN_FLIGHT as u32 {
+            assert_eq!(session.receive_instruction().await, sequence_id);
+        }
+        // The controller's buffer is full: one more packet waits in the
+        // channel and the producer blocks on the next.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(produced.load(Ordering::SeqCst), MAX_IN_FLIGHT + 1);
+        assert!(timeout(Duration::from_millis(50), session.lines.next_line()).await.is_err());
+
+        // Each acknowledgement makes room for exactly one more.
+        for sequence_id in 1..=STEPS {
+            session.finish(sequence_id, 0).await;
+            if sequence_id + (MAX_IN_FLIGHT as u32) <= STEPS {
+                assert_eq!(session.receive_instruction().await, sequence_id + MAX_IN_FLIGHT as u32);
+            }
+        }
+    };
+    let control = ProgramControl::new();
+    let (summary, _) = tokio::join!(timeout(WAIT, driver.run_stream(program, &control)), script);
+    let summary = summary.expect("program hung");
+    producer.await.unwrap();
+
+    assert!(summary.is_success());
+    assert_eq!(summary.completed, (1..=STEPS).collect::<Vec<_>>());
+    assert_eq!(produced.load(Ordering::SeqCst), STEPS as usize);
+}
@@ -0,0 +1,103 @@
+use fanuc_rmi::commands::{Alarm, AlarmFacility, AlarmSeverity, FrcReadErrorResponse};
+use fanuc_rmi::{FanucErrorClass, FanucErrorCode, FrcError};
+
+const FIRST_ID: u32 = 2556929;
+const LAST_ID: u32 = 2556981;
+
+#[test]
+fn every_known_error_id_round_trips() {
+    for id in FIRST_ID..=LAST_ID {
+        let code = FanucErrorCode::from_id(id);
+        assert!(!matches!(code, FanucErrorCode::Unrecognized(_)), "{} is not mapped", id);
+        assert_eq!(code.id(), id);
+        assert_ne!(code.class(), FanucErrorClass::Unknown);
+        assert!(code.to_string().ends_with(&format!("({})", id)));
+    }
+    assert_eq!(FanucErrorCode::from_id(2556937), FanucErrorCode::RMINotRunning);
+    assert_eq!(FanucErrorCode::from_id(LAST_ID), FanucErrorCode::InvalidCallProgramName);
+}
+
+#[test]
+fn unknown_error_ids_are_kept() {
+    for id in [0, 1, FIRST_ID - 1, LAST_ID + 1, u32::MAX] {
+        let code = FanucErrorCode::from_id(id);
+        assert_eq!(code, FanucErrorCode::Unrecognized(id));
+        assert_eq!(code.id(), id);
+        assert_eq!(code.class(), FanucErrorClass::Unknown);
+        assert!(!code.is_recoverable());
+        assert_eq!(code.to_string(), format!("Unrecognized FANUC Error ID ({})", id));
+    }
+
+    let error = FrcError::from_error_id(LAST_ID + 1, "FRC_Initialize", None);
+    assert_eq!(error.error_code(), Some(&FanucErrorCode::Unrecognized(LAST_ID + 1)));
+    assert!(!error.is_recoverable());
+}
+
+#[test]
+fn error_classes_decide_recovery() {
+    let cases = [
+        (FanucErrorCode::InvalidSpeedValue, FanucErrorClass::InvalidRequest, true, false, false),
+        (FanucErrorCode::WaitForCommandDone, FanucErrorClass::Busy, true, false, false),
+        (FanucErrorCode::RMIInHoldState, FanucErrorClass::ControllerState, true, true, false),
+        (FanucErrorCode::PleaseCyclePower, FanucErrorClass::Fatal, false, false, true),
+    ];
+    for (code, class, recoverable, reset, power_cycle) in cases {
+        assert_eq!(code.class(), class, "{:?}", code);
+        let error = FrcError::from_error_id(code.id(), "FRC_LinearMotion", Some(7));
+        assert_eq!(error.error_code(), Some(&code));
+        assert_eq!(error.is_recoverable(), recoverable, "{:?}", code);
+        assert_eq!(error.requires_reset(), reset, "{:?}", code);
+        assert_eq!(error.requires_power_cycle(), power_cycle, "{:?}", code);
+    }
+    assert_eq!(
+        FrcError::from_error_id(2556959, "FRC_LinearMotion", Some(7)).to_string(),
+        "fanuc returned error Invalid Speed Value. (2556959) for FRC_LinearMotion (sequence id 7)"
+    );
+}
+
+#[test]
+fn alarms_decode_facility_number_and_severity() {
+    use AlarmFacility as F;
+    use AlarmSeverity as S;
+
+    let other = |prefix: &str| F::Other(prefix.to_string());
+    let cases = [
+        ("SRVO-002 Teach pendant E-stop", F::Servo, 2, None, "Teach pendant E-stop"),
+        ("SRVO-007 SERVO External emergency stops", F::Servo, 7, Some(S::Servo), "External emergency stops"),
+        ("MOTN-017 STOP.G Limit error (G:1^2, A:1^2)", F::Motion, 17, Some(S::StopGlobal), "Limit error (G:1^2, A:1^2)"),
+        ("INTP-105 PAUSE.L (MAIN, 12) Run request failed", F::Interpreter, 105, Some(S::PauseLocal), "(MAIN, 12) Run request failed"),
+        ("SYST-011 WARN Failed to run task", F::System, 11, Some(S::Warn), "Failed to run task"),
+        ("PRIO-063 Bad I/O asg: rack 0 slot 1", F::ProcessIO, 63, None, "Bad I/O asg: rack 0 slot 1"),
+        ("HOST-005 ABORT.G Unknown host", F::Host, 5, Some(S::AbortGlobal), "Unknown host"),
+        ("RMIT-029 Invalid UFrame number", other("RMIT"), 29, None, "Invalid UFrame number"),
+        ("CVIS-020 STOP.L Not enough features", other("CVIS"), 20, Some(S::StopLocal), "Not enough features"),
+        ("  srvo-001   SYSTEM   Operator panel E-stop  ", F::Servo, 1, Some(S::System), "Operator panel E-stop"),
+        ("SRVO-062 SERVO2", F::Servo, 62, Some(S::Servo2), ""),
+        ("SRVO-050", F::Servo, 50, None, ""),
+    ];
+    for (error_data, facility, number, severity, message) in cases {
+        let alarm = Alarm::parse(4, error_data);
+        assert_eq!(alarm.index, 4);
+        assert_eq!(alarm.facility, facility, "{}", error_data);
+        assert_eq!(alarm.number, number, "{}", error_data);
+        assert_eq!(alarm.severity, severity, "{}", error_data);
+        assert_eq!(alarm.message, message, "{}", error_data);
+        assert_eq!(alarm.raw, error_data);
+    }
+    assert!(S::PauseLocal < S::StopGlobal && S::StopGlobal < S::System);
+}
+
+#[test]
+fn malformed_alarms_keep_their_text() {
+    for error_data in ["E-stop pressed", "SRVO- missing number", "SRVO 002 no dash", "123-456 numeric prefix", "SRVO-99999 too large", "-001 no prefix"] {
+        let alarm = Alarm::parse(1, error_data);
+        assert_eq!(alarm.facility, AlarmFacility::Other(String::new()), "{}", error_data);
+        assert_eq!((alarm.number, alarm.severity), (0, None), "{}", error_data);
+        assert_eq!(alarm.message, error_data);
+    }
+
+    let response = |error_data: &str| FrcReadErrorResponse { error_id: 0, count: 2, error_data: error_data.to_string() };
+    assert_eq!(response("   ").alarm(), None);
+    let alarm = response("MOTN-023 STOP.L In singularity").alarm().unwrap();
+    assert_eq!((alarm.index, alarm.facility, alarm.number), (2, AlarmFacility::Motion, 23));
+}
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use fanuc_rmi::gcode::{CodeAction, CodeMap, GcodeLoader};
+use fanuc_rmi::instructions::FrcWaitTime;
+use fanuc_rmi::packets::OnOff;
+use fanuc_rmi::{FrcError, PacketEnum, Termination};
+use serde_json::{json, Value};
+
+fn load(program: &str) -> Vec<Value> {
+    packets(GcodeLoader::new().frame(1).tool(1).load_str(program).unwrap())
+}
+
+fn packets(queue: VecDeque<PacketEnum>) -> Vec<Value> {
+    queue.iter().map(|packet| serde_json::to_value(packet).unwrap()).collect()
+}
+
+fn assert_xyz(position: &Value, expected: [f64; 3]) {
+    let actual = [&position["X"], &position["Y"], &position["Z"]].map(|v| v.as_f64().unwrap());
+    for i in 0..3 {
+        assert!((actual[i] - expected[i]).abs() < 1e-3, "expected {expected:?}, got {actual:?}");
+    }
+}
+
+#[test]
+fn linear_moves_follow_units_and_distance_mode() {
+    let moves = load("G21 G90\nG0 X10 Y20 Z30\nG91 G1 X5 F600\nG20 G1 Y1 F60");
+    assert_eq!(moves.len(), 3);
+    assert_eq!(moves[0]["Instruction"], "FRC_LinearMotion");
+    assert_eq!(moves[0]["Speed"], 500);
+    assert_xyz(&moves[0]["Position"], [10.0, 20.0, 30.0]);
+    assert_xyz(&moves[1]["Position"], [15.0, 20.0, 30.0]);
+    assert_eq!(moves[1]["Speed"], 10);
+    assert_xyz(&moves[2]["Position"], [15.0, 45.4, 30.0]);
+    assert_eq!(moves[2]["Speed"], 25);
+    assert_eq!(moves[2]["Configuration"]["UFrameNumber"], 1);
+    let ids: Vec<_> = moves.iter().map(|m| m["SequenceID"].as_u64().unwrap()).collect();
+    assert_eq!(ids, [1, 2, 3]);
+}
+
+#[test]
+fn quarter_arc_passes_through_its_midpoint() {
+    let moves = load("G1 X10 Y0 F600\nG3 X0 Y10 I-10 J0");
+    assert_eq!(moves[1]["Instruction"], "FRC_CircularMotion");
+    let h = 10.0 * std::f64::consts::FRAC_1_SQRT_2;
+    assert_xyz(&moves[1]["ViaPosition"], [h, h, 0.0]);
+    assert_xyz(&moves[1]["Position"], [0.0, 10.0, 0.0]);
+
+    // Clockwise the same end point is three quarters of a turn away.
+    let moves = load("G1 X10 Y0 F600\nG2 X0 Y10 I-10 J0");
+    assert_eq!(moves.len(), 3);
+    assert_xyz(&moves[1]["Position"], [-h, -h, 0.0]);
+}
+
+#[test]
+fn full_circles_are_split_in_two() {
+    let moves = load("G1 X10 F600\nG17 G2 I-10");
+    assert_eq!(moves.len(), 3);
+    assert_xyz(&moves[1]["ViaPosition"], [0.0, -10.0, 0.0]);
+    assert_xyz(&moves[1]["Position"], [-10.0, 0.0, 0.0]);
+    assert_xyz(&moves[2]["ViaPosition"], [0.0, 10.0, 0.0]);
+    assert_xyz(&moves[2]["Position"], [10.0, 0.0, 0.0]);
+
+    // A helix climbs evenly along the normal over both halves.
+    let moves = load("G1 X10 F600\nG17 G3 I-10 Z8");
+    assert_eq!(moves.len(), 3);
+    assert_xyz(&moves[1]["ViaPosition"], [0.0, 10.0, 2.0]);
+    assert_xyz(&moves[1]["Position"], [-10.0, 0.0, 4.0]);
+    assert_xyz(&moves[2]["Position"], [10.0, 0.0, 8.0]);
+}
+
+#[test]
+fn radius_arcs_pick_the_short_or_long_side() {
+    // G18 arcs are seen from +Y, with Z to the right and X up.
+    let moves = load("G18 G2 X20 Z0 R10 F600");
+    assert_xyz(&moves[0]["ViaPosition"], [10.0, 0.0, -10.0]);
+    let moves = load("G19 G3 Y20 Z0 R10 F600");
+    assert_xyz(&moves[0]["ViaPosition"], [0.0, 10.0, -10.0]);
+    // Centered on (0, 10) for the short arc and on (10, 0) for the long one.
+    let h = 10.0 * std::f64::consts::FRAC_1_SQRT_2;
+    let moves = load("G3 X10 Y10 R10 F600");
+    assert_xyz(&moves[0]["ViaPosition"], [h, 10.0 - h, 0.0]);
+    let moves = load("G3 X10 Y10 R-10 F600");
+    assert_eq!(moves.len(), 2);
+    assert_xyz(&moves[0]["Position"], [10.0 + h, -h, 0.0]);
+}
+
+#[test]
+fn comments_and_program_end() {
+    let moves = load("%\nN10 G1 X1 F600 (first) ; trailing\n( whole line )\nM30\nG1 X2");
+    assert_eq!(moves.len(), 1);
+}
+
+#[test]
+fn mcode_hook_inserts_instructions() {
+    let queue = GcodeLoader::new()
+        .on_mcode(|code| match code {
+            3 => Ok(vec![PacketEnum::Instruction(FrcWaitTime::new(0, 0.5).into())]),
+            5 => Ok(vec![]),
+            _ => Err("no mapping".to_string()),
+        })
+        .load_str("G1 X1 F600\nM3 G1 X2\nM5")
+        .unwrap();
+    let moves = packets(queue);
+    let names: Vec<_> = moves.iter().map(|m| m["Instruction"].as_str().unwrap()).collect();
+    assert_eq!(names, ["FRC_LinearMotion", "FRC_WaitTime", "FRC_LinearMotion"]);
+    assert_eq!(moves[1]["SequenceID"], 2);
+
+    let error = GcodeLoader::new().on_mcode(|_| Err("no mapping".to_string())).load_str("G1 X1 F600\nM8");
+    assert!(matches!(error, Err(FrcError::Gcode { line: 2, .. })));
+}
+
+#[test]
+fn code_map_drives_io_and_tool_changes() {
+    let codes = CodeMap::new()
+        .mcode(3, [CodeAction::WriteDout { port: 7, value: OnOff::ON }, CodeAction::WaitDin { port: 8, value: OnOff::ON }])
+        .mcode(5, [CodeAction::WriteDout { port: 7, value: OnOff::OFF }])
+        .mcode(6, [])
+        .mcode(98, [CodeAction::Call("PURGE".to_string())])
+        .tools_as_utools()
+        .tcode(11, [CodeAction::SetUTool(3), CodeAction::SetPayload(3)]);
+    let queue = GcodeLoader::new()
+        .codes(codes.clone())
+        .load_str("G1 X1 F600\nT2 M6\nM3 G1 X2\nM5 M98\nT11\nG1 X3\nM30")
+        .unwrap();
+    let moves = packets(queue);
+    let names: Vec<_> = moves
+        .iter()
+        .map(|m| m.get("Instruction").or(m.get("Command")).unwrap().as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "FRC_LinearMotion",
+            "FRC_SetUTool",
+            "FRC_WriteDOUT",
+            "FRC_WaitDIN",
+            "FRC_LinearMotion",
+            "FRC_WriteDOUT",
+            "FRC_Call",
+            "FRC_SetUTool",
+            "FRC_SetPayLoad",
+            "FRC_LinearMotion",
+        ]
+    );
+    assert_eq!(moves[2]["PortValue"], "ON");
+    assert_eq!(moves[4]["Configuration"]["UToolNumber"], 2);
+    assert_eq!(moves[9]["Configuration"]["UToolNumber"], 3);
+    let ids: Vec<_> = moves.iter().filter_map(|m| m.get("SequenceID")).map(|id| id.as_u64().unwrap()).collect();
+    assert_eq!(ids, (1..=8).collect::<Vec<_>>());
+
+    let json = serde_json::to_string(&codes).unwrap();
+    assert_eq!(serde_json::from_str::<CodeMap>(&json).unwrap(), codes);
+
+    let error = GcodeLoader::new().codes(CodeMap::new()).load_str("G1 X1 F600\nM7");
+    assert!(matches!(error, Err(FrcError::Gcode { line: 2, .. })));
+}
+
+#[test]
+fn outputs_switch_with_the_next_motion() {
+    let codes = CodeMap::new()
+        .mcode(3, [CodeAction::WriteDout { port: 7, value: OnOff::ON }])
+        .mcode(5, [CodeAction::WriteDout { port: 7, value: OnOff::OFF }]);
+    let program = "G1 X1 F600\nM3\nG1 X2\nG3 X4 Y2 I0 J2\nM5\nM30";
+    let moves = packets(GcodeLoader::new().codes(codes.clone()).termination(Termination::Cnt(100)).load_str(program).unwrap());
+    assert_eq!(moves.len(), 4);
+    assert_eq!(moves[1]["Instruction"], "FRC_LinearMotion");
+    assert_eq!((&moves[1]["PortType"], &moves[1]["PortNumber"], &moves[1]["PortValue"]), (&json!("DO"), &json!(7), &json!("ON")));
+    assert!(moves[0].get("PortNumber").is_none() && moves[2].get("PortNumber").is_none());
+    // Nothing moves after M5, so it has to stop and switch as a command.
+    assert_eq!((&moves[3]["Command"], &moves[3]["PortValue"]), (&json!("FRC_WriteDOUT"), &json!("OFF")));
+
+    let moves = packets(GcodeLoader::new().codes(codes).outputs_on_motion(false).load_str(program).unwrap());
+    assert_eq!(moves.len(), 5);
+    assert_eq!(moves[1]["Command"], "FRC_WriteDOUT");
+    assert!(moves[2].get("PortNumber").is_none());
+}
+
+#[test]
+fn errors_carry_the_line_number() {
+    for (program, line) in [
+        ("G1 X1 F600\nG1 X2 Q4", 2),
+        ("X5", 1),
+        ("G1 X1 F600\n\nG2 X5 Y0 I1", 3),
+        ("G1 X1 F600\nG1 X2 F99999999", 2),
+        ("G1 X1 (open", 1),
+        ("G54", 1),
+        ("G1 X1 F600\nT1", 2),
+    ] {
+        match GcodeLoader::new().load_str(program) {
+            Err(FrcError::Gcode { line: actual, .. }) => assert_eq!(actual, line, "{program}"),
+            other => panic!("{program}: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use fanuc_rmi::kinematics::{ArmConfig, RobotModel};
+use fanuc_rmi::{FlipNoFlip, FrameData, FrontBack, JointAngles, Position, UpDown};
+
+const ANGLE_EPS: f32 = 0.01;
+const LENGTH_EPS: f32 = 0.01;
+
+fn joints([j1, j2, j3, j4, j5, j6]: [f32; 6]) -> JointAngles {
+    JointAngles { j1, j2, j3, j4, j5, j6, j7: 0.0, j8: 0.0, j9: 0.0 }
+}
+
+fn same_joints(a: &JointAngles, b: &JointAngles) -> bool {
+    [a.j1 - b.j1, a.j2 - b.j2, a.j3 - b.j3, a.j4 - b.j4, a.j5 - b.j5, a.j6 - b.j6].iter().all(|d| d.abs() < ANGLE_EPS)
+}
+
+fn assert_same_pose(a: &Position, b: &Position) {
+    let (ta, tb) = (a.to_transform(), b.to_transform());
+    for i in 0..3 {
+        assert!((ta.translation[i] - tb.translation[i]).abs() < LENGTH_EPS as f64, "{:?} vs {:?}", a, b);
+        for j in 0..3 {
+            assert!((ta.rotation[i][j] - tb.rotation[i][j]).abs() < 1e-4, "{:?} vs {:?}", a, b);
+        }
+    }
+}
+
+/// Joint sets within the model's limits covering both sides, both elbows,
+/// both wrist flips and more than one turn of J4 and J6, clear of the
+/// stretched-elbow and shoulder singularities, where the inverse is
+/// ill-conditioned.
+fn joint_grid(model: &RobotModel) -> Vec<JointAngles> {
+    let elbow_offset = model.a3.atan2(model.d4).to_degrees() as f32;
+    let mut grid = Vec::new();
+    for j1 in [-120.0, 0.0, 75.0] {
+        for j2 in [-60.0, -20.0, 20.0, 60.0] {
+            for j3 in [-50.0, 0.0, 75.0, 150.0] {
+                for j4 in [-185.0, 0.0, 100.0] {
+                    for j5 in [-60.0, 45.0] {
+                        for j6 in [-170.0, 30.0, 200.0] {
+                            let candidate = joints([j1, j2, j3, j4, j5, j6]);
+                            let stretched = (j2 + j3 + elbow_offset).to_radians().cos().abs() < 0.05;
+                            let (s2, (s3, c3)) = (j2.to_radians().sin(), j3.to_radians().sin_cos());
+                            let radial = model.a1 as f32 + model.a2 as f32 * s2 + model.d4 as f32 * c3 - model.a3 as f32 * s3;
+                            let on_j1_axis = radial.abs() < 10.0;
+                            if model.within_limits(&candidate) && !stretched && !on_j1_axis {
+                                grid.push(candidate);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    grid
+}
+
+#[test]
+fn forward_and_inverse_round_trip_for_every_model_and_config() {
+    let tool = FrameData { x: 0.0, y: 0.0, z: 120.0, w: 0.0, p: 0.0, r: 0.0 };
+    for model in RobotModel::all() {
+        let mut configs = HashSet::new();
+        for start in joint_grid(model) {
+            let (pos, config) = model.forward(&start, &tool);
+            let solutions = model.inverse(&pos, &tool);
+            assert!(
+                solutions.iter().any(|solution| same_joints(&solution.joints, &start) && solution.config == config),
+                "{}: {:?} not among the solutions for {:?}",
+                model.name,
+                start,
+                pos
+            );
+            for solution in &solutions {
+                assert!(model.within_limits(&solution.joints));
+                let (again, again_config) = model.forward(&solution.joints, &tool);
+                assert_same_pose(&again, &pos);
+                assert_eq!(again_config, solution.config);
+            }
+            let chosen = model.inverse_with_config(&pos, &tool, &config).unwrap();
+            assert_same_pose(&model.forward(&chosen, &tool).0, &pos);
+            configs.insert((config.front, config.up, config.flip));
+        }
+
+        for front in [FrontBack::Front, FrontBack::Back] {
+            for up in [UpDown::Up, UpDown::Down] {
+                for flip in [FlipNoFlip::Flip, FlipNoFlip::NoFlip] {
+                    assert!(configs.contains(&(front, up, flip)), "{}: no pose in {:?} {:?} {:?}", model.name, front, up, flip);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn turn_numbers_follow_j4_and_j6() {
+    let model = &RobotModel::LR_MATE_200ID;
+    let tool = FrameData::default();
+    let (pos, config) = model.forward(&joints([0.0, 10.0, 20.0, -185.0, 45.0, 200.0]), &tool);
+    assert_eq!((config.turn4, config.turn5, config.turn6), (-1, 0, 1));
+    assert_eq!(config.flip, FlipNoFlip::Flip);
+
+    // The same pose with J4 and J6 a turn apart differs only in turn numbers.
+    let turns: HashSet<(i8, i8)> = model
+        .inverse(&pos, &tool)
+        .iter()
+        .filter(|solution| solution.config.flip == FlipNoFlip::Flip && solution.config.up == config.up)
+        .map(|solution| (solution.config.turn4, solution.config.turn6))
+        .collect();
+    assert_eq!(turns, HashSet::from([(-1, 0), (-1, 1), (0, 0), (0, 1)]));
+    let turned = ArmConfig { turn4: 0, turn6: 0, ..config };
+    let moved = model.inverse_with_config(&pos, &tool, &turned).unwrap();
+    assert!((moved.j4 - 175.0).abs() < ANGLE_EPS && (moved.j6 - -160.0).abs() < ANGLE_EPS);
+}
+
+#[test]
+fn joint_limits_bound_solutions_and_reach() {
+    let model = &RobotModel::LR_MATE_200ID;
+    let tool = FrameData::default();
+    let [(min1, max1), (_, max2), ..] = model.joint_limits;
+    assert!(model.within_limits(&joints([max1 as f32, max2 as f32, 0.0, 0.0, -30.0, 0.0])));
+    assert!(model.within_limits(&joints([min1 as f32, 0.0, 0.0, 0.0, -30.0, 0.0])));
+    assert!(!model.within_limits(&joints([max1 as f32 + 0.01, 0.0, 0.0, 0.0, -30.0, 0.0])));
+    assert!(!model.within_limits(&joints([0.0, 0.0, 0.0, 0.0, 125.5, 0.0])));
+
+    // Straight behind the base needs J1 at 180, beyond its ±170 range.
+    let behind = model.forward(&joints([180.0, 0.0, 0.0, 0.0, -30.0, 0.0]), &tool).0;
+    assert!(model.inverse(&behind, &tool).iter().all(|solution| solution.joints.j1.abs() <= 170.0));
+    assert!(model.inverse(&behind, &tool).iter().all(|solution| solution.config.front == FrontBack::Back));
+
+    // Beyond the arm's reach.
+    let reach = (model.a2 + model.a3.hypot(model.d4) + model.d6 + model.a1) as f32;
+    let far = Position::new(reach + 50.0, 0.0, 0.0, 180.0, 0.0, 0.0);
+    assert!(!model.is_reachable(&far, &tool));
+    let near = Position::new(400.0, 0.0, 100.0, 180.0, 0.0, 0.0);
+    assert!(model.is_reachable(&near, &tool));
+}
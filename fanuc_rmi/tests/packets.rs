@@ -0,0 +1,259 @@
+use fanuc_rmi::commands::*;
+use fanuc_rmi::instructions::*;
+use fanuc_rmi::packets::*;
+use fanuc_rmi::{Configuration, FrameData, FrcError, JointAngles, Position, Speed, Termination};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+// Packets as laid out in the RMI manual. Float values are chosen to be exact in f32.
+const CONFIG: &str = r#"{"UToolNumber":1,"UFrameNumber":1,"Front":1,"Up":1,"Left":0,"Flip":0,"Turn4":0,"Turn5":0,"Turn6":0}"#;
+const POSITION: &str = r#"{"X":500.5,"Y":-120.25,"Z":300.0,"W":180.0,"P":0.0,"R":-90.0,"Ext1":0.0,"Ext2":0.0,"Ext3":0.0}"#;
+const JOINTS: &str = r#"{"J1":10.5,"J2":-20.0,"J3":30.25,"J4":0.0,"J5":-45.0,"J6":90.0,"J7":0.0,"J8":0.0,"J9":0.0}"#;
+const FRAME: &str = r#"{"X":10.0,"Y":20.5,"Z":-30.0,"W":0.0,"P":90.0,"R":180.0}"#;
+
+fn fill(template: &str) -> String {
+    template
+        .replace("$CONFIG", CONFIG)
+        .replace("$POSITION", POSITION)
+        .replace("$JOINTS", JOINTS)
+        .replace("$FRAME", FRAME)
+}
+
+/// Parses `golden` as `T`, writes it back and requires the same JSON.
+fn round_trip<T: Serialize + DeserializeOwned>(golden: &str) {
+    let golden = fill(golden);
+    let expected: Value = serde_json::from_str(&golden).unwrap();
+    let packet: T = serde_json::from_str(&golden).unwrap_or_else(|e| panic!("{golden}: {e}"));
+    let actual = serde_json::to_value(&packet).unwrap();
+    assert_eq!(actual, expected, "round trip of {golden}");
+}
+
+fn assert_serializes<T: Serialize>(packet: &T, golden: &str) {
+    let expected: Value = serde_json::from_str(&fill(golden)).unwrap();
+    assert_eq!(serde_json::to_value(packet).unwrap(), expected);
+}
+
+#[test]
+fn communication_packets() {
+    for golden in [
+        r#"{"Communication":"FRC_Connect"}"#,
+        r#"{"Communication":"FRC_Disconnect"}"#,
+        r#"{"Communication":"FRC_Terminate"}"#,
+        r#"{"Communication":"FRC_SystemFault"}"#,
+    ] {
+        round_trip::<Communication>(golden);
+    }
+}
+
+#[test]
+fn communication_responses() {
+    for golden in [
+        r#"{"Communication":"FRC_Connect","ErrorID":0,"PortNumber":16002,"MajorVersion":1,"MinorVersion":0}"#,
+        r#"{"Communication":"FRC_Disconnect","ErrorID":0}"#,
+        r#"{"Communication":"FRC_Terminate","ErrorID":0}"#,
+        r#"{"Communication":"FRC_SystemFault","SequenceID":12}"#,
+    ] {
+        round_trip::<CommunicationResponse>(golden);
+    }
+}
+
+#[test]
+fn command_packets() {
+    for golden in [
+        r#"{"Command":"FRC_Initialize","GroupMask":1}"#,
+        r#"{"Command":"FRC_Abort"}"#,
+        r#"{"Command":"FRC_Pause"}"#,
+        r#"{"Command":"FRC_Continue"}"#,
+        r#"{"Command":"FRC_ReadError","Count":1}"#,
+        r#"{"Command":"FRC_SetUFrameUTool","UFrameNumber":1,"UToolNumber":2,"Group":1}"#,
+        r#"{"Command":"FRC_GetStatus"}"#,
+        r#"{"Command":"FRC_GetUFrameUTool","Group":1}"#,
+        r#"{"Command":"FRC_ReadUFrameData","FrameNumber":3,"Group":1}"#,
+        r#"{"Command":"FRC_WriteUFrameData","FrameNumber":3,"Frame":$FRAME,"Group":1}"#,
+        r#"{"Command":"FRC_ReadUToolData","ToolNumber":2,"Group":1}"#,
+        r#"{"Command":"FRC_WriteUToolData","ToolNumber":2,"Frame":$FRAME,"Group":1}"#,
+        r#"{"Command":"FRC_ReadDIN","PortNumber":81}"#,
+        r#"{"Command":"FRC_WriteDOUT","PortNumber":81,"PortValue":"ON"}"#,
+        r#"{"Command":"FRC_ReadCartesianPosition","Group":1}"#,
+        r#"{"Command":"FRC_ReadJointAngles","Group":1}"#,
+        r#"{"Command":"FRC_SetOverride","Value":50}"#,
+        r#"{"Command":"FRC_ReadPositionRegister","RegisterNumber":10,"Group":1}"#,
+        r#"{"Command":"FRC_WritePositionRegister","RegisterNumber":10,"Configuration":$CONFIG,"Position":$POSITION,"Group":1}"#,
+        r#"{"Command":"FRC_Reset"}"#,
+        r#"{"Command":"FRC_ReadTCPSpeed"}"#,
+    ] {
+        round_trip::<Command>(golden);
+    }
+}
+
+#[test]
+fn command_responses() {
+    for golden in [
+        r#"{"Command":"FRC_Initialize","ErrorID":0,"GroupMask":1}"#,
+        r#"{"Command":"FRC_Abort","ErrorID":0}"#,
+        r#"{"Command":"FRC_Pause","ErrorID":0}"#,
+        r#"{"Command":"FRC_Continue","ErrorID":0}"#,
+        r#"{"Command":"FRC_ReadError","ErrorID":0,"Count":1,"ErrorData":"SRVO-002 Teach pendant E-stop"}"#,
+        r#"{"Command":"FRC_SetUFrameUTool","ErrorID":0,"Group":1}"#,
+        r#"{"Command":"FRC_GetStatus","ErrorID":0,"ServoReady":1,"TPMode":1,"RMIMotionStatus":0,"ProgramStatus":0,"SingleStepMode":0,"NumberUTool":10,"NextSequenceID":1,"NumberUFrame":9}"#,
+        r#"{"Command":"FRC_GetUFrameUTool","ErrorID":0,"UFrameNumber":1,"UToolNumber":2,"Group":1}"#,
+        r#"{"Command":"FRC_ReadUFrameData","ErrorID":0,"FrameNumber":3,"Group":1,"Frame":$FRAME}"#,
+        r#"{"Command":"FRC_WriteUFrameData","ErrorID":0,"Group":1}"#,
+        r#"{"Command":"FRC_ReadUToolData","ErrorID":0,"ToolNumber":2,"Frame":$FRAME,"Group":1}"#,
+        r#"{"Command":"FRC_WriteUToolData","ErrorID":0,"Group":1}"#,
+        r#"{"Command":"FRC_ReadDIN","ErrorID":0,"PortNumber":81,"PortValue":"OFF"}"#,
+        r#"{"Command":"FRC_WriteDOUT","ErrorID":0}"#,
+        r#"{"Command":"FRC_ReadCartesianPosition","ErrorID":0,"TimeTag":1024,"Configuration":$CONFIG,"Position":$POSITION,"Group":1}"#,
+        r#"{"Command":"FRC_ReadJointAngles","ErrorID":0,"TimeTag":1024,"JointAngles":$JOINTS,"Group":1}"#,
+        r#"{"Command":"FRC_SetOverride","ErrorID":0}"#,
+        r#"{"Command":"FRC_ReadPositionRegister","ErrorID":0,"RegisterNumber":10,"Configuration":$CONFIG,"Position":$POSITION,"Group":1}"#,
+        r#"{"Command":"FRC_WritePositionRegister","ErrorID":0}"#,
+        r#"{"Command":"FRC_Reset","ErrorID":0}"#,
+        r#"{"Command":"FRC_ReadTCPSpeed","ErrorID":0,"TimeTag":1024,"Speed":125.5}"#,
+    ] {
+        round_trip::<CommandResponse>(golden);
+    }
+}
+
+#[test]
+fn instruction_packets() {
+    for golden in [
+        r#"{"Instruction":"FRC_WaitDIN","SequenceID":1,"PortNumber":81,"PortValue":"ON"}"#,
+        r#"{"Instruction":"FRC_SetUFrame","SequenceID":2,"FrameNumber":3}"#,
+        r#"{"Instruction":"FRC_SetUTool","SequenceID":3,"ToolNumber":2}"#,
+        r#"{"Instruction":"FRC_WaitTime","SequenceID":4,"Time":1.5}"#,
+        r#"{"Instruction":"FRC_SetPayLoad","SequenceID":5,"ScheduleNumber":1}"#,
+        r#"{"Instruction":"FRC_Call","SequenceID":6,"ProgramName":"PNS0001"}"#,
+        r#"{"Instruction":"FRC_LinearMotion","SequenceID":7,"Configuration":$CONFIG,"Position":$POSITION,"SpeedType":"mmSec","Speed":100,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_LinearRelative","SequenceID":8,"Configuration":$CONFIG,"Position":$POSITION,"SpeedType":"mmSec","Speed":100,"TermType":"CNT","TermValue":50}"#,
+        r#"{"Instruction":"FRC_LinearRelativeJRep","SequenceID":9,"JointAngles":$JOINTS,"SpeedType":"mmSec","Speed":100,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_JointMotion","SequenceID":10,"Configuration":$CONFIG,"Position":$POSITION,"SpeedType":"Percent","Speed":50,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_JointRelative","SequenceID":11,"Configuration":$CONFIG,"Position":$POSITION,"SpeedType":"Time","Speed":20,"TermType":"CNT","TermValue":100}"#,
+        r#"{"Instruction":"FRC_CircularMotion","SequenceID":12,"Configuration":$CONFIG,"Position":$POSITION,"ViaConfiguration":$CONFIG,"ViaPosition":$POSITION,"SpeedType":"mmSec","Speed":100,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_CircularRelative","SequenceID":13,"Configuration":$CONFIG,"Position":$POSITION,"ViaConfiguration":$CONFIG,"ViaPosition":$POSITION,"SpeedType":"InchMin","Speed":200,"TermType":"CR","TermValue":5}"#,
+        r#"{"Instruction":"FRC_JointMotionJRep","SequenceID":14,"JointAngles":$JOINTS,"SpeedType":"Percent","Speed":25,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_JointRelativeJRep","SequenceID":15,"JointAngles":$JOINTS,"SpeedType":"mSec","Speed":500,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_LinearMotionJRep","SequenceID":16,"JointAngles":$JOINTS,"SpeedType":"mmSec","Speed":100,"TermType":"FINE","TermValue":1}"#,
+        r#"{"Instruction":"FRC_LinearMotion","SequenceID":17,"Configuration":$CONFIG,"Position":$POSITION,"SpeedType":"mmSec","Speed":100,"TermType":"CNT","TermValue":100,"Acc":80,"OffsetPRNumber":2,"MROT":1,"LCBType":"DO","LCBValue":3,"PortType":"DO","PortNumber":4,"PortValue":"ON","NoBlend":1}"#,
+    ] {
+        round_trip::<Instruction>(golden);
+    }
+}
+
+#[test]
+fn instruction_responses() {
+    for name in [
+        "FRC_WaitDIN",
+        "FRC_SetUFrame",
+        "FRC_SetUTool",
+        "FRC_WaitTime",
+        "FRC_SetPayLoad",
+        "FRC_Call",
+        "FRC_LinearMotion",
+        "FRC_LinearRelative",
+        "FRC_LinearRelativeJRep",
+        "FRC_JointMotion",
+        "FRC_JointRelative",
+        "FRC_CircularMotion",
+        "FRC_CircularRelative",
+        "FRC_JointMotionJRep",
+        "FRC_JointRelativeJRep",
+        "FRC_LinearMotionJRep",
+    ] {
+        round_trip::<InstructionResponse>(&format!(r#"{{"Instruction":"{name}","ErrorID":0,"SequenceID":7}}"#));
+    }
+}
+
+#[test]
+fn constructors_match_the_manual() {
+    let frame = FrameData { x: 10.0, y: 20.5, z: -30.0, w: 0.0, p: 90.0, r: 180.0 };
+    assert_serializes(
+        &Command::FrcWriteUToolData(FrcWriteUToolData::new(None, 2, frame)),
+        r#"{"Command":"FRC_WriteUToolData","ToolNumber":2,"Frame":$FRAME,"Group":1}"#,
+    );
+    assert_serializes(
+        &Command::FrcReadUToolData(FrcReadUToolData::new(None, 2)),
+        r#"{"Command":"FRC_ReadUToolData","ToolNumber":2,"Group":1}"#,
+    );
+    assert_serializes(
+        &Command::FrcWriteDOUT(FrcWriteDOUT::new(81, OnOff::ON)),
+        r#"{"Command":"FRC_WriteDOUT","PortNumber":81,"PortValue":"ON"}"#,
+    );
+
+    let config: Configuration = serde_json::from_str(CONFIG).unwrap();
+    let position = Position::new(500.5, -120.25, 300.0, 180.0, 0.0, -90.0);
+    assert_serializes(
+        &Instruction::from(FrcLinearMotion::new(7, config, position, Speed::MmSec(100), Termination::Fine)),
+        r#"{"Instruction":"FRC_LinearMotion","SequenceID":7,"Configuration":$CONFIG,"Position":$POSITION,"SpeedType":"mmSec","Speed":100,"TermType":"FINE","TermValue":1}"#,
+    );
+
+    let joints = JointAngles::new(10.5, -20.0, 30.25, 0.0, -45.0, 90.0);
+    assert_serializes(
+        &Instruction::from(FrcJointMotionJRep::new(14, joints, Speed::Percent(25), Termination::Fine)),
+        r#"{"Instruction":"FRC_JointMotionJRep","SequenceID":14,"JointAngles":$JOINTS,"SpeedType":"Percent","Speed":25,"TermType":"FINE","TermValue":1}"#,
+    );
+}
+
+#[test]
+fn lenient_parsing_keeps_unknown_packets() {
+    let raw = r#"{"Command":"FRC_ReadVariable","ErrorID":0,"Value":3}"#;
+    let response = CommandResponse::parse_str(raw, ParseMode::Lenient).unwrap();
+    let (name, value) = response.as_unknown().unwrap();
+    assert_eq!(name, "FRC_ReadVariable");
+    assert_eq!(value, &serde_json::from_str::<Value>(raw).unwrap());
+
+    let raw = r#"{"Instruction":"FRC_SplineMotion","ErrorID":2556950,"SequenceID":4}"#;
+    let response = InstructionResponse::parse_str(raw, ParseMode::Lenient).unwrap();
+    assert_eq!(response.get_name(), "FRC_SplineMotion");
+    assert_eq!(response.get_sequence_id(), 4);
+    assert_eq!(response.get_error_id(), 2556950);
+
+    let raw = r#"{"Command":"FRC_Abort","ErrorID":0,"TimeTag":1024}"#;
+    let response = CommandResponse::parse_str(raw, ParseMode::Lenient).unwrap();
+    assert!(matches!(response, CommandResponse::FrcAbort(FrcAbortResponse { error_id: 0 })));
+}
+
+#[test]
+fn strict_parsing_rejects_unknown_packets_and_fields() {
+    for raw in [
+        r#"{"Command":"FRC_ReadVariable","ErrorID":0,"Value":3}"#,
+        r#"{"Command":"FRC_Abort","ErrorID":0,"TimeTag":1024}"#,
+        r#"{"Communication":"FRC_Connect","ErrorID":0}"#,
+    ] {
+        let error = match raw.contains("Communication") {
+            true => CommunicationResponse::parse_str(raw, ParseMode::Strict).err(),
+            false => CommandResponse::parse_str(raw, ParseMode::Strict).err(),
+        };
+        assert!(matches!(error, Some(FrcError::Serialization(_))), "{raw}");
+    }
+    let raw = r#"{"Command":"FRC_Abort","ErrorID":0}"#;
+    assert!(CommandResponse::parse_str(raw, ParseMode::Strict).is_ok());
+
+    // Nested objects are checked too.
+    let read = r#"{"Command":"FRC_ReadCartesianPosition","ErrorID":0,"TimeTag":1024,"Configuration":$CONFIG,"Position":$POSITION,"Group":1}"#;
+    let raw = read.replace("$CONFIG", CONFIG).replace("$POSITION", POSITION);
+    assert!(CommandResponse::parse_str(&raw, ParseMode::Strict).is_ok());
+    let nested = raw.replace(r#""Ext3":0.0"#, r#""Ext3":0.0,"Ext4":0.0"#);
+    match CommandResponse::parse_str(&nested, ParseMode::Strict) {
+        Err(FrcError::Serialization(message)) => assert!(message.contains("`Position.Ext4`"), "{message}"),
+        other => panic!("expected a serialization error, got {:?}", other),
+    }
+    assert!(CommandResponse::parse_str(&nested, ParseMode::Lenient).is_ok());
+}
+
+#[test]
+fn lenient_parsing_rejects_known_packets_that_do_not_decode() {
+    let cases = [
+        r#"{"Command":"FRC_Initialize","ErrorID":"none"}"#,
+        r#"{"Command":"FRC_ReadError","ErrorID":0,"Count":1}"#,
+    ];
+    for raw in cases {
+        let error = CommandResponse::parse_str(raw, ParseMode::Lenient).err();
+        assert!(matches!(error, Some(FrcError::Serialization(_))), "{raw}");
+    }
+    let raw = r#"{"Communication":"FRC_Connect","ErrorID":2556933}"#;
+    assert!(matches!(CommunicationResponse::parse_str(raw, ParseMode::Lenient), Err(FrcError::Serialization(_))));
+    let raw = r#"{"Instruction":"FRC_LinearMotion","SequenceID":"4"}"#;
+    assert!(matches!(InstructionResponse::parse_str(raw, ParseMode::Lenient), Err(FrcError::Serialization(_))));
+}
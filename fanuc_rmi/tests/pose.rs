@@ -0,0 +1,200 @@
+use fanuc_rmi::instructions::{check_via, via_problem, ArcPlane, CircularArc, Move, ViaProblem};
+use fanuc_rmi::pose::{matrix_to_wpr, wpr_to_matrix, Quaternion, Transform};
+use fanuc_rmi::{FrameData, FrcError, Position, Termination};
+
+const EPS: f64 = 1e-6;
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < EPS, "expected {expected}, got {actual}");
+}
+
+fn assert_point(actual: [f64; 3], expected: [f64; 3]) {
+    for i in 0..3 {
+        assert_close(actual[i], expected[i]);
+    }
+}
+
+fn pos(x: f32, y: f32, z: f32, w: f32, p: f32, r: f32) -> Position {
+    Position { x, y, z, w, p, r, ..Default::default() }
+}
+
+fn assert_position(actual: &Position, expected: &Position) {
+    let a = [actual.x, actual.y, actual.z, actual.w, actual.p, actual.r];
+    let e = [expected.x, expected.y, expected.z, expected.w, expected.p, expected.r];
+    for i in 0..6 {
+        assert!((a[i] - e[i]).abs() < 1e-3, "expected {expected:?}, got {actual:?}");
+    }
+}
+
+#[test]
+fn w_rotates_about_x() {
+    // W = 90 turns +Y into +Z.
+    let t = Transform::from_xyzwpr(0.0, 0.0, 0.0, 90.0, 0.0, 0.0);
+    assert_point(t.transform_point([0.0, 1.0, 0.0]), [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn p_rotates_about_y() {
+    // P = 90 turns +Z into +X.
+    let t = Transform::from_xyzwpr(0.0, 0.0, 0.0, 0.0, 90.0, 0.0);
+    assert_point(t.transform_point([0.0, 0.0, 1.0]), [1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn r_rotates_about_z() {
+    // R = 90 turns +X into +Y.
+    let t = Transform::from_xyzwpr(0.0, 0.0, 0.0, 0.0, 0.0, 90.0);
+    assert_point(t.transform_point([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn wpr_applies_w_then_p_then_r_about_fixed_axes() {
+    // W=90 maps +Y to +Z, P=90 then maps +Z to +X, R=90 then maps +X to +Y.
+    let m = wpr_to_matrix(90.0, 90.0, 90.0);
+    let t = Transform { rotation: m, translation: [0.0; 3] };
+    assert_point(t.transform_point([0.0, 1.0, 0.0]), [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn wpr_round_trips_through_matrix() {
+    for &(w, p, r) in &[(10.0, 20.0, 30.0), (-170.0, 45.0, 100.0), (180.0, -60.0, -90.0), (0.0, 0.0, 0.0)] {
+        let (w2, p2, r2) = matrix_to_wpr(&wpr_to_matrix(w, p, r));
+        let again = wpr_to_matrix(w2, p2, r2);
+        let original = wpr_to_matrix(w, p, r);
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_close(again[i][j], original[i][j]);
+            }
+        }
+    }
+}
+
+#[test]
+fn gimbal_lock_keeps_rotation() {
+    let original = wpr_to_matrix(30.0, 90.0, 10.0);
+    let (w, p, r) = matrix_to_wpr(&original);
+    assert_close(w, 0.0);
+    assert_close(p, 90.0);
+    let again = wpr_to_matrix(w, p, r);
+    for i in 0..3 {
+        for j in 0..3 {
+            assert_close(again[i][j], original[i][j]);
+        }
+    }
+}
+
+#[test]
+fn quaternion_matches_known_values() {
+    let q = Quaternion::from_wpr(0.0, 0.0, 90.0);
+    let half = std::f64::consts::FRAC_1_SQRT_2;
+    assert_close(q.w, half);
+    assert_close(q.x, 0.0);
+    assert_close(q.y, 0.0);
+    assert_close(q.z, half);
+
+    let (w, p, r) = Quaternion::from_wpr(15.0, -25.0, 140.0).to_wpr();
+    assert_close(w, 15.0);
+    assert_close(p, -25.0);
+    assert_close(r, 140.0);
+}
+
+#[test]
+fn compose_with_inverse_is_identity() {
+    let a = pos(100.0, -50.0, 300.0, 10.0, 20.0, 30.0);
+    let identity = a.compose(&a.inverse());
+    assert_position(&identity, &Position::default());
+}
+
+#[test]
+fn compose_applies_tool_in_flange_frame() {
+    // A flange pointing down (W=180) with a 100 mm tool along its Z reaches 100 mm lower.
+    let flange = pos(500.0, 0.0, 400.0, 180.0, 0.0, 0.0);
+    let tool = pos(0.0, 0.0, 100.0, 0.0, 0.0, 0.0);
+    assert_position(&flange.compose(&tool), &pos(500.0, 0.0, 300.0, 180.0, 0.0, 0.0));
+}
+
+#[test]
+fn change_frame_moves_between_uframes() {
+    let world = FrameData::default();
+    let uframe = FrameData { x: 100.0, y: 0.0, z: 0.0, w: 0.0, p: 0.0, r: 90.0 };
+
+    let in_uframe = pos(10.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    let in_world = in_uframe.change_frame(&uframe, &world);
+    assert_position(&in_world, &pos(100.0, 10.0, 0.0, 0.0, 0.0, 90.0));
+
+    assert_position(&in_world.change_frame(&world, &uframe), &in_uframe);
+}
+
+#[test]
+fn interpolate_blends_translation_and_rotation() {
+    let a = pos(0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    let b = pos(100.0, 200.0, -50.0, 0.0, 0.0, 90.0);
+    assert_position(&a.interpolate(&b, 0.5), &pos(50.0, 100.0, -25.0, 0.0, 0.0, 45.0));
+    assert_position(&a.interpolate(&b, 0.0), &a);
+    assert_position(&a.interpolate(&b, 1.0), &b);
+}
+
+#[test]
+fn interpolate_takes_short_way_across_180() {
+    let a = pos(0.0, 0.0, 0.0, 0.0, 0.0, 170.0);
+    let b = pos(0.0, 0.0, 0.0, 0.0, 0.0, -170.0);
+    let mid = a.interpolate(&b, 0.5);
+    assert!((mid.r.abs() - 180.0).abs() < 1e-3, "got {mid:?}");
+}
+
+#[test]
+fn full_circle_splits_into_two_blended_moves() {
+    let center = pos(500.0, 0.0, 300.0, 180.0, 0.0, 0.0);
+    let circle = CircularArc::full_circle(ArcPlane::XY, &center, 50.0, 90.0).unwrap();
+    assert_position(&circle.start(), &pos(500.0, 50.0, 300.0, 180.0, 0.0, 0.0));
+    assert_position(&circle.end(), &circle.start());
+    assert_close(circle.length(), 100.0 * std::f64::consts::PI);
+
+    let segments = circle.segments();
+    assert_eq!(segments.len(), 2);
+    assert_position(&segments[0].0, &pos(450.0, 0.0, 300.0, 180.0, 0.0, 0.0));
+    assert_position(&segments[0].1, &pos(500.0, -50.0, 300.0, 180.0, 0.0, 0.0));
+    assert_position(&segments[1].0, &pos(550.0, 0.0, 300.0, 180.0, 0.0, 0.0));
+
+    let motions = Move::circular().arc(&circle).speed_mm_s(80).fine().frame(2).build(7).unwrap();
+    assert_eq!(motions.iter().map(|m| m.sequence_id).collect::<Vec<_>>(), vec![7, 8]);
+    assert_eq!(motions[0].termination, Termination::Cnt(100));
+    assert_eq!(motions[1].termination, Termination::Fine);
+    assert_eq!(motions[1].via_configuration.u_frame_number, 2);
+    assert!(motions[0].check_via(&circle.start()).is_ok());
+
+    // Half a turn still fits in one move; a clockwise quarter in the ZX plane too.
+    let half = CircularArc::in_plane(ArcPlane::XY, &center, 50.0, 0.0, 180.0).unwrap();
+    assert_eq!(half.segments().len(), 1);
+    let quarter = CircularArc::in_plane(ArcPlane::ZX, &center, 50.0, 0.0, -90.0).unwrap();
+    assert_position(&quarter.start(), &pos(500.0, 0.0, 350.0, 180.0, 0.0, 0.0));
+    assert_position(&quarter.end(), &pos(450.0, 0.0, 300.0, 180.0, 0.0, 0.0));
+}
+
+#[test]
+fn arcs_through_three_points_and_via_checks() {
+    let start = pos(100.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+    let via = pos(0.0, 100.0, 0.0, 0.0, 0.0, 0.0);
+    let end = pos(0.0, -100.0, 0.0, 0.0, 0.0, 90.0);
+    let arc = CircularArc::through(&start, &via, &end).unwrap();
+    assert_point(arc.center(), [0.0, 0.0, 0.0]);
+    assert_close(arc.radius(), 100.0);
+    assert_close(arc.sweep_degrees(), 270.0);
+    assert_position(&arc.point(2.0 / 3.0), &pos(-100.0, 0.0, 0.0, 0.0, 0.0, 60.0));
+    assert_eq!(arc.segments().len(), 2);
+
+    let message = |result: Result<(), FrcError>| result.unwrap_err().to_string();
+    let midway = pos(50.0, -50.0, 0.0, 0.0, 0.0, 0.0);
+    assert!(message(check_via(&start, &midway, &end)).contains("in line"));
+    assert!(message(check_via(&start, &start, &end)).contains("on the start"));
+    assert!(message(check_via(&start, &via, &start)).contains("full circle"));
+    assert!(CircularArc::through(&start, &midway, &end).is_err());
+    assert_eq!(via_problem(&start, &via, &end), None);
+    assert_eq!(via_problem(&start, &end, &end), Some(ViaProblem::OnEnd));
+    assert_eq!(via_problem(&start, &midway, &end), Some(ViaProblem::Collinear));
+
+    let relative = Move::circular().via(pos(10.0, 10.0, 0.0, 0.0, 0.0, 0.0)).by(pos(20.0, 20.0, 0.0, 0.0, 0.0, 0.0)).build(1).unwrap();
+    assert!(message(relative.check_via()).contains("InvalidVIAPosition"));
+    assert_eq!(relative.via_problem(), Some(ViaProblem::Collinear));
+    assert!(CircularArc::in_plane(ArcPlane::XY, &start, 0.0, 0.0, 90.0).is_err());
+}
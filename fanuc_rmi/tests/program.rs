@@ -0,0 +1,565 @@
+use std::collections::VecDeque;
+
+use fanuc_rmi::commands::{FrcGetStatusResponse, FrcSetOverride, FrcWriteUFrameData};
+use fanuc_rmi::gcode::GcodeLoader;
+use fanuc_rmi::instructions::{FrcLinearMotion, FrcSetUFrame, FrcWaitDIN, FrcWaitTime, MotionSettings, Move, ViaProblem};
+use fanuc_rmi::kinematics::RobotModel;
+use fanuc_rmi::packets::{Command, Instruction, OnOff};
+use fanuc_rmi::program::{
+    CycleTime, CycleTimeEstimator, Lint, Linter, MoveStep, PathGenerator, Pose, ProgramFile, ProgramFormat, Severity, Step,
+    Target, Weave,
+};
+use fanuc_rmi::{
+    Configuration, FanucErrorCode, FrameData, FrcError, JointAngles, LeftRight, PacketEnum, Position, Speed, Termination,
+};
+use serde_json::Value;
+
+const PROGRAM: &str = r#"{
+    "name": "pick",
+    "defaults": {
+        "frame": 1,
+        "tool": 1,
+        "speed": { "SpeedType": "mmSec", "Speed": 200 },
+        "termination": { "TermType": "CNT", "TermValue": 50 }
+    },
+    "frames": [{ "number": 1, "xyzwpr": [600, 0, 200, 0, 0, 0] }],
+    "tools": [{ "number": 2, "xyzwpr": [0, 0, 150, 0, 0, 0] }],
+    "poses": {
+        "home": { "joints": [0, 0, 0, 0, -90, 0] },
+        "above": { "xyzwpr": [0, 0, 100, 180, 0, 0], "ext": [5, 0, 0] }
+    },
+    "steps": [
+        { "type": "joint", "to": "home", "speed": { "SpeedType": "Percent", "Speed": 30 } },
+        { "type": "linear", "to": "above", "termination": { "TermType": "FINE", "TermValue": 1 } },
+        { "type": "set_utool", "tool": 2 },
+        { "type": "linear", "to": { "xyzwpr": [0, 0, -100, 0, 0, 0] }, "relative": true },
+        { "type": "write_dout", "port": 1, "value": "ON" },
+        { "type": "wait_time", "seconds": 0.5 },
+        { "type": "circular", "via": { "xyzwpr": [50, 50, 0, 180, 0, 0] }, "to": { "xyzwpr": [100, 0, 0, 180, 0, 0] }, "frame": 2 },
+        { "type": "call", "program": "GRIP" }
+    ]
+}"#;
+
+fn names(queue: &[Value]) -> Vec<&str> {
+    queue
+        .iter()
+        .map(|p| p.get("Instruction").or(p.get("Command")).unwrap().as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn json_program_loads_into_a_queue() {
+    let program = ProgramFile::parse(PROGRAM, ProgramFormat::Json).unwrap();
+    let queue: Vec<Value> = program.to_queue(10).unwrap().iter().map(|p| serde_json::to_value(p).unwrap()).collect();
+    assert_eq!(
+        names(&queue),
+        [
+            "FRC_WriteUFrameData",
+            "FRC_WriteUToolData",
+            "FRC_JointMotionJRep",
+            "FRC_LinearMotion",
+            "FRC_SetUTool",
+            "FRC_LinearRelative",
+            "FRC_WriteDOUT",
+            "FRC_WaitTime",
+            "FRC_CircularMotion",
+            "FRC_Call",
+        ]
+    );
+    assert_eq!(queue[0]["Frame"]["X"], 600.0);
+    assert_eq!(queue[2]["JointAngles"]["J5"], -90.0);
+    assert_eq!(queue[2]["SpeedType"], "Percent");
+    assert_eq!(queue[3]["Position"]["Ext1"], 5.0);
+    assert_eq!(queue[3]["TermType"], "FINE");
+    assert_eq!(queue[3]["Configuration"]["UToolNumber"], 1);
+    assert_eq!(queue[5]["Configuration"]["UToolNumber"], 2);
+    assert_eq!(queue[5]["Speed"], 200);
+    assert_eq!(queue[5]["TermValue"], 50);
+    assert_eq!(queue[8]["Configuration"]["UFrameNumber"], 2);
+    assert_eq!(queue[8]["ViaConfiguration"]["UFrameNumber"], 2);
+    let ids: Vec<_> = queue.iter().filter_map(|p| p.get("SequenceID")).map(|id| id.as_u64().unwrap()).collect();
+    assert_eq!(ids, (10..=16).collect::<Vec<_>>());
+
+    let text = program.to_text(ProgramFormat::Json).unwrap();
+    assert_eq!(ProgramFile::parse(&text, ProgramFormat::Json).unwrap(), program);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_matches_json() {
+    let json = ProgramFile::parse(PROGRAM, ProgramFormat::Json).unwrap();
+    let text = json.to_text(ProgramFormat::Toml).unwrap();
+    assert_eq!(ProgramFile::parse(&text, ProgramFormat::Toml).unwrap(), json);
+
+    let toml = r#"
+        [poses]
+        home = { joints = [0, 0, 0, 0, -90, 0] }
+
+        [[steps]]
+        type = "joint"
+        to = "home"
+
+        [[steps]]
+        type = "wait_din"
+        port = 3
+        value = "ON"
+    "#;
+    let queue = ProgramFile::parse(toml, ProgramFormat::Toml).unwrap().to_queue(1).unwrap();
+    assert_eq!(queue.len(), 2);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_matches_json() {
+    let json = ProgramFile::parse(PROGRAM, ProgramFormat::Json).unwrap();
+    let text = json.to_text(ProgramFormat::Yaml).unwrap();
+    assert_eq!(ProgramFile::parse(&text, ProgramFormat::Yaml).unwrap(), json);
+}
+
+#[test]
+fn bad_steps_are_reported_by_index() {
+    let mut program = ProgramFile {
+        steps: vec![
+            Step::Linear(MoveStep::to(Target::Pose(Pose::cartesian([0.0, 0.0, 0.0, 180.0, 0.0, 0.0])))),
+            Step::Linear(MoveStep::to(Target::Named("missing".to_string()))),
+        ],
+        ..Default::default()
+    };
+    assert!(matches!(program.to_queue(1), Err(FrcError::Program { step: Some(1), .. })));
+
+    let arc = Step::Circular(MoveStep::to(Target::Pose(Pose::cartesian([1.0; 6]))));
+    program.steps = vec![arc];
+    assert!(matches!(program.to_queue(1), Err(FrcError::Program { step: Some(0), .. })));
+
+    let mut motion = MoveStep::to(Target::Pose(Pose::joints([0.0; 6])));
+    motion.speed = Some(Speed::Percent(50));
+    program.steps = vec![Step::Joint(motion.clone()), Step::Linear(motion)];
+    assert!(matches!(program.to_queue(1), Err(FrcError::Program { step: Some(1), .. })));
+
+    let typo = r#"{ "steps": [{ "type": "linear", "to": "home", "sped": 10 }] }"#;
+    assert!(matches!(ProgramFile::parse(typo, ProgramFormat::Json), Err(FrcError::Program { step: None, .. })));
+    let pose = r#"{ "poses": { "home": { "xyzwpr": [0, 0, 0, 0, 0, 0], "joints": [0, 0, 0, 0, 0, 0] } }, "steps": [{ "type": "joint", "to": "home" }] }"#;
+    let program = ProgramFile::parse(pose, ProgramFormat::Json).unwrap();
+    assert!(matches!(program.to_queue(1), Err(FrcError::Program { step: Some(0), .. })));
+}
+
+const LS: &str = "/PROG  PICK\tMacro
+/ATTR
+OWNER\t\t= MNEDITOR;
+COMMENT\t\t= \"pick part\";
+PROG_SIZE\t= 636;
+TCD:  STACK_SIZE\t= 0,
+      TASK_PRIORITY\t= 50,
+      PAUSE_REQUEST\t= 0;
+DEFAULT_GROUP\t= 1,*,*,*,*;
+/MN
+   1:  UFRAME_NUM=1 ;
+   2:  UTOOL_NUM=2 ;
+   3:  ! approach ;
+   4:J P[1:home] 50% FINE    ;
+   5:L P[2] 500mm/sec CNT50 ACC80 Offset,PR[3]    ;
+   6:C P[3]    
+    :  P[4] 2000cm/min FINE    ;
+   7:  //L P[9] 100mm/sec FINE    ;
+   8:L P[5] 100mm/sec FINE INC    ;
+   9:  WAIT   1.50(sec) ;
+  10:  WAIT DI[4:part present]=ON    ;
+  11:  DO[2]=OFF ;
+  12:  CALL GRIP    ;
+  13:  PAYLOAD[2] ;
+  14:  ;
+/POS
+P[1:\"home\"]{
+   GP1:
+\tUF : 1, UT : 2,
+\tJ1=     0.000 deg,\tJ2=    10.000 deg,\tJ3=   -20.000 deg,
+\tJ4=     0.000 deg,\tJ5=   -90.000 deg,\tJ6=     0.000 deg
+};
+P[2]{
+   GP1:
+\tUF : 1, UT : 2,\t\tCONFIG : 'F D B, 0, 1, -1',
+\tX =   500.000  mm,\tY =     0.000  mm,\tZ =   300.000  mm,
+\tW =   180.000 deg,\tP =     0.000 deg,\tR =     0.000 deg
+};
+P[3]{
+   GP1:
+\tUF : 1, UT : 2,\t\tCONFIG : 'N U T, 0, 0, 0',
+\tX =   550.000  mm,\tY =    50.000  mm,\tZ =   300.000  mm,
+\tW =   180.000 deg,\tP =     0.000 deg,\tR =     0.000 deg
+};
+P[4]{
+   GP1:
+\tUF : 1, UT : 2,\t\tCONFIG : 'N U T, 0, 0, 0',
+\tX =   600.000  mm,\tY =     0.000  mm,\tZ =   300.000  mm,
+\tW =   180.000 deg,\tP =     0.000 deg,\tR =     0.000 deg,
+\tE1=   250.000  mm,\tE2=     0.000  mm,\tE3=     0.000  mm
+};
+P[5]{
+   GP1:
+\tUF : 1, UT : 2,\t\tCONFIG : 'N U T, 0, 0, 0',
+\tX =     0.000  mm,\tY =     0.000  mm,\tZ =   -50.000  mm,
+\tW =     0.000 deg,\tP =     0.000 deg,\tR =     0.000 deg
+};
+/END
+";
+
+fn queue_values(program: &ProgramFile) -> Vec<Value> {
+    program.to_queue(1).unwrap().iter().map(|p| serde_json::to_value(p).unwrap()).collect()
+}
+
+#[test]
+fn ls_listing_imports_as_instructions() {
+    let program = ProgramFile::parse(LS, ProgramFormat::Ls).unwrap();
+    assert_eq!(program.name.as_deref(), Some("PICK"));
+    let queue = queue_values(&program);
+    assert_eq!(
+        names(&queue),
+        [
+            "FRC_SetUFrame",
+            "FRC_SetUTool",
+            "FRC_JointMotionJRep",
+            "FRC_LinearMotion",
+            "FRC_CircularMotion",
+            "FRC_LinearRelative",
+            "FRC_WaitTime",
+            "FRC_WaitDIN",
+            "FRC_WriteDOUT",
+            "FRC_Call",
+            "FRC_SetPayLoad",
+        ]
+    );
+    assert_eq!(queue[2]["JointAngles"]["J3"], -20.0);
+    assert_eq!(queue[2]["Speed"], 50);
+    assert_eq!(queue[3]["Configuration"], serde_json::json!({
+        "UToolNumber": 2, "UFrameNumber": 1, "Front": 0, "Up": 0, "Left": 0, "Flip": 1,
+        "Turn4": 0, "Turn5": 1, "Turn6": -1
+    }));
+    assert_eq!(queue[3]["TermValue"], 50);
+    assert_eq!(queue[3]["Acc"], 80);
+    assert_eq!(queue[3]["OffsetPRNumber"], 3);
+    assert_eq!(queue[4]["ViaPosition"]["Y"], 50.0);
+    assert_eq!(queue[4]["Position"]["Ext1"], 250.0);
+    assert_eq!(queue[4]["Speed"], 333);
+    assert_eq!(queue[5]["Position"]["Z"], -50.0);
+    assert_eq!(queue[6]["Time"], 1.5);
+    assert_eq!(queue[7]["PortNumber"], 4);
+    assert_eq!(queue[8]["PortValue"], "OFF");
+    assert_eq!(queue[9]["ProgramName"], "GRIP");
+}
+
+#[test]
+fn ls_export_reads_back_the_same_queue() {
+    let program = ProgramFile::parse(LS, ProgramFormat::Ls).unwrap();
+    let text = program.to_text(ProgramFormat::Ls).unwrap();
+    assert!(text.starts_with("/PROG  PICK\n"), "{text}");
+    assert!(text.contains("   5:C P[3]    \n    :  P[4] 333mm/sec FINE    ;\n"), "{text}");
+    let again = ProgramFile::parse(&text, ProgramFormat::Ls).unwrap();
+    assert_eq!(queue_values(&again), queue_values(&program));
+
+    // Motions in another frame get a UFRAME_NUM line first.
+    let json = ProgramFile::parse(PROGRAM, ProgramFormat::Json).unwrap();
+    let ls = fanuc_rmi::program::write_ls("pick", json.to_queue(1).unwrap().iter().skip(2)).unwrap();
+    assert!(ls.contains("UFRAME_NUM=2 ;\n  10:C P[4]"), "{ls}");
+}
+
+#[test]
+fn ls_export_keeps_left_handed_configurations() {
+    let configuration = Configuration { left: LeftRight::Left, turn4: 1, ..Default::default() };
+    let motion = Move::linear().to(Position::new(400.0, -50.0, 300.0, 180.0, 0.0, 0.0)).config(configuration).build(1).unwrap();
+    let queue = [PacketEnum::Instruction(motion.into())];
+    let text = fanuc_rmi::program::write_ls("left", &queue).unwrap();
+    assert!(text.contains("CONFIG : 'N L U T, 1, 0, 0'"), "{text}");
+    let again = ProgramFile::parse(&text, ProgramFormat::Ls).unwrap().to_queue(1).unwrap();
+    let left: Vec<LeftRight> = again
+        .iter()
+        .filter_map(|packet| match packet {
+            PacketEnum::Instruction(Instruction::FrcLinearMotion(motion)) => Some(motion.configuration.left),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(left, [LeftRight::Left]);
+}
+
+#[test]
+fn ls_errors_point_at_the_problem() {
+    let unsupported = LS.replace("CALL GRIP", "JMP LBL[1]");
+    assert!(matches!(ProgramFile::parse(&unsupported, ProgramFormat::Ls), Err(FrcError::Ls { line: 23, .. })));
+    let missing = LS.replace("P[5] 100mm", "P[6] 100mm");
+    assert!(matches!(ProgramFile::parse(&missing, ProgramFormat::Ls), Err(FrcError::Ls { line: 19, .. })));
+    let group = LS.replace("   GP1:\n\tUF : 1, UT : 2,\t\tCONFIG : 'F D B", "   GP2:\n\tUF : 1, UT : 2,\t\tCONFIG : 'F D B");
+    assert!(matches!(ProgramFile::parse(&group, ProgramFormat::Ls), Err(FrcError::Ls { line: 33, .. })));
+
+    let json = ProgramFile::parse(PROGRAM, ProgramFormat::Json).unwrap();
+    assert!(matches!(json.to_text(ProgramFormat::Ls), Err(FrcError::Program { step: Some(0), .. })));
+}
+
+fn packets(instructions: Vec<Instruction>) -> Vec<PacketEnum> {
+    instructions.into_iter().map(PacketEnum::Instruction).collect()
+}
+
+#[test]
+fn lint_flags_sequence_ids_relative_start_and_final_blend() {
+    let queue = packets(vec![
+        Move::linear().by(Position::new(0.0, 0.0, 50.0, 0.0, 0.0, 0.0)).build(1).unwrap().into(),
+        Move::linear().to(Position::new(400.0, 0.0, 300.0, 180.0, 0.0, 0.0)).build(3).unwrap().into(),
+        Move::linear().to(Position::new(450.0, 0.0, 300.0, 180.0, 0.0, 0.0)).speed_mm_s(5000).build(2).unwrap().into(),
+        Move::linear().to(Position::new(500.0, 0.0, 300.0, 180.0, 0.0, 0.0)).cnt(50).build(3).unwrap().into(),
+    ]);
+
+    let diagnostics = Linter::new().lint(&queue);
+    let found: Vec<(usize, &Lint)> = diagnostics.iter().map(|d| (d.step, &d.lint)).collect();
+    assert_eq!(found.len(), 5, "{:#?}", diagnostics);
+    assert_eq!(found[0], (0, &Lint::RelativeWithoutStart));
+    assert_eq!(found[1], (2, &Lint::NonMonotonicSequenceId { sequence_id: 2, previous: 3 }));
+    assert!(matches!(found[2], (2, Lint::InvalidValue(_))));
+    assert_eq!(found[3], (3, &Lint::DuplicateSequenceId { sequence_id: 3, first_step: 1 }));
+    assert_eq!(found[4], (3, &Lint::BlendOnFinalMove(Termination::Cnt(50))));
+
+    assert_eq!(diagnostics[0].severity(), Severity::Warning);
+    assert_eq!(diagnostics[1].lint.error_code(), Some(FanucErrorCode::InvalidSequenceIDNumber));
+    assert_eq!(diagnostics[3].to_string(), "error at step 3: sequence ID 3 is already used at step 1");
+
+    // Starting from a known position makes the relative move fine.
+    let start = Position::new(400.0, 0.0, 250.0, 180.0, 0.0, 0.0);
+    let diagnostics = Linter::new().start(Configuration::default(), &start).lint(&queue);
+    assert!(diagnostics.iter().all(|d| d.lint != Lint::RelativeWithoutStart));
+}
+
+#[test]
+fn lint_checks_arcs_frames_tools_and_reach() {
+    let status: FrcGetStatusResponse = serde_json::from_str(
+        r#"{"ErrorID":0,"ServoReady":1,"TPMode":0,"RMIMotionStatus":0,"ProgramStatus":0,
+            "SingleStepMode":0,"NumberUTool":2,"NumberUFrame":3}"#,
+    )
+    .unwrap();
+    let frame = FrameData { x: 100.0, ..Default::default() };
+    let mut queue = vec![PacketEnum::Command(Command::FrcWriteUFrameData(FrcWriteUFrameData::new(None, 2, frame)))];
+    queue.extend(packets(vec![
+        Move::linear().to(Position::new(300.0, 0.0, 300.0, 180.0, 0.0, 0.0)).frame(2).build(1).unwrap().into(),
+        // In line with the start in world coordinates.
+        Move::circular()
+            .via(Position::new(350.0, 0.0, 300.0, 180.0, 0.0, 0.0))
+            .to(Position::new(400.0, 0.0, 300.0, 180.0, 0.0, 0.0))
+            .frame(2)
+            .build(2)
+            .unwrap()
+            .into(),
+        Move::circular()
+            .via(Position::new(50.0, 50.0, 0.0, 0.0, 0.0, 0.0))
+            .by(Position::new(100.0, 0.0, 0.0, 0.0, 0.0, 0.0))
+            .frame(2)
+            .build(3)
+            .unwrap()
+            .into(),
+        Move::linear().to(Position::new(400.0, 0.0, 300.0, 180.0, 0.0, 0.0)).frame(4).tool(3).build(4).unwrap().into(),
+        Move::linear().to(Position::new(5000.0, 0.0, 300.0, 180.0, 0.0, 0.0)).build(5).unwrap().into(),
+        FrcSetUFrame::new(6, 9).into(),
+    ]));
+
+    let diagnostics = Linter::new()
+        .status(&status)
+        .robot(&RobotModel::M_10IA)
+        .utool(1, FrameData::default())
+        .lint(&queue);
+    let found: Vec<(usize, &Lint)> = diagnostics.iter().map(|d| (d.step, &d.lint)).collect();
+    assert_eq!(
+        found,
+        vec![
+            (2, &Lint::InvalidVia(ViaProblem::Collinear)),
+            (4, &Lint::FrameOutOfRange { frame: 4, available: 3 }),
+            (4, &Lint::ToolOutOfRange { tool: 3, available: 2 }),
+            (5, &Lint::Unreachable),
+            (6, &Lint::FrameOutOfRange { frame: 9, available: 3 }),
+        ],
+        "{:#?}",
+        diagnostics
+    );
+    assert_eq!(diagnostics[0].lint.error_code(), Some(FanucErrorCode::InvalidVIAPosition));
+}
+
+fn seconds(estimate: &CycleTime) -> Vec<f64> {
+    estimate.steps.iter().map(|step| (step.duration.as_secs_f64() * 1000.0).round() / 1000.0).collect()
+}
+
+#[test]
+fn estimate_times_moves_with_trapezoidal_profiles() {
+    let start = Position::new(400.0, 0.0, 300.0, 180.0, 0.0, 0.0);
+    let tool0 = Configuration { u_tool_number: 0, ..Default::default() };
+    let at = |x: f32, y: f32| Position { x, y, ..start };
+    let queue = vec![
+        // 200 mm at 100 mm/sec.
+        PacketEnum::Instruction(Move::linear().to(at(600.0, 0.0)).speed_mm_s(100).tool(0).build(1).unwrap().into()),
+        // Too short to reach speed: ramps straight up and down, 2 * sqrt(10 mm * 0.25 s / 100 mm/sec).
+        PacketEnum::Instruction(Move::linear().to(at(610.0, 0.0)).speed_mm_s(100).tool(0).build(2).unwrap().into()),
+        // Half a circle of radius 100.
+        PacketEnum::Instruction(
+            Move::circular().via(at(510.0, 100.0)).to(at(410.0, 0.0)).speed_mm_s(100).tool(0).build(3).unwrap().into(),
+        ),
+        PacketEnum::Instruction(FrcWaitTime::new(4, 1.5).into()),
+        PacketEnum::Command(Command::FrcSetOverride(FrcSetOverride::new(50))),
+        // Half speed, and twice the ramp time at ACC 50.
+        PacketEnum::Instruction(Move::linear().by(Position::new(0.0, 100.0, 0.0, 0.0, 0.0, 0.0)).speed_mm_s(100).acc(50).tool(0).build(5).unwrap().into()),
+        PacketEnum::Instruction(FrcWaitDIN::new(6, 3, OnOff::ON).into()),
+    ];
+
+    let estimate = CycleTimeEstimator::new(&RobotModel::M_10IA).start(tool0, &start).estimate(&queue);
+    let arc = std::f64::consts::PI;
+    assert_eq!(seconds(&estimate), vec![2.25, 0.316, ((arc + 0.25) * 1000.0).round() / 1000.0, 1.5, 0.0, 2.5, 0.0]);
+    assert!(!estimate.is_exact());
+    assert!(estimate.steps[6].lower_bound);
+    assert!((estimate.total.as_secs_f64() - (2.25 + 0.316 + arc + 0.25 + 1.5 + 2.5)).abs() < 1e-3);
+}
+
+#[test]
+fn estimate_blends_cnt_moves_and_times_joint_moves_by_the_slowest_axis() {
+    let home = JointAngles { j5: -90.0, ..Default::default() };
+    let turned = JointAngles { j1: 90.0, j2: 10.0, ..home };
+    let queue = vec![
+        PacketEnum::Instruction(Move::joint().to_joints(turned).percent(50).cnt(100).build(1).unwrap().into()),
+        PacketEnum::Instruction(Move::joint().to_joints(home).percent(50).build(2).unwrap().into()),
+    ];
+
+    let estimator = CycleTimeEstimator::new(&RobotModel::M_10IA).start_joints(home);
+    // J1 turns 90 degrees at half of 210 degrees/sec; the blend saves one ramp.
+    let expected = 90.0 / 105.0 + 0.25;
+    let estimate = estimator.clone().estimate(&queue);
+    assert!((estimate.steps[0].duration.as_secs_f64() - (expected - 0.25)).abs() < 1e-3, "{:?}", estimate);
+    assert!((estimate.steps[1].duration.as_secs_f64() - expected).abs() < 1e-3, "{:?}", estimate);
+    assert!(estimate.is_exact());
+
+    // Nothing is known about where a relative move starts from.
+    let relative = vec![PacketEnum::Instruction(
+        Move::linear().by(Position::new(0.0, 0.0, 10.0, 0.0, 0.0, 0.0)).build(1).unwrap().into(),
+    )];
+    let estimate = CycleTimeEstimator::new(&RobotModel::M_10IA).estimate(&relative);
+    assert!(estimate.steps[0].lower_bound);
+}
+
+fn moves(queue: &VecDeque<PacketEnum>) -> Vec<&FrcLinearMotion> {
+    queue
+        .iter()
+        .map(|packet| match packet {
+            PacketEnum::Instruction(Instruction::FrcLinearMotion(motion)) => motion,
+            other => panic!("expected a linear move, got {:?}", other),
+        })
+        .collect()
+}
+
+fn xy(queue: &VecDeque<PacketEnum>) -> Vec<[f32; 2]> {
+    moves(queue).iter().map(|m| [(m.position.x * 100.0).round() / 100.0, (m.position.y * 100.0).round() / 100.0]).collect()
+}
+
+#[test]
+fn raster_fills_rectangles_and_polygons_back_and_forth() {
+    let queue = PathGenerator::new()
+        .frame(2)
+        .tool(3)
+        .height(10.0)
+        .speed_mm_s(150)
+        .termination(Termination::Cnt(80))
+        .first_sequence_id(5)
+        .raster_rect([0.0, 0.0], 200.0, 100.0, 25.0)
+        .unwrap();
+    let motions = moves(&queue);
+    assert_eq!(
+        xy(&queue),
+        vec![
+            [0.0, 0.0], [200.0, 0.0], [200.0, 25.0], [0.0, 25.0], [0.0, 50.0],
+            [200.0, 50.0], [200.0, 75.0], [0.0, 75.0], [0.0, 100.0], [200.0, 100.0],
+        ]
+    );
+    assert_eq!(motions.iter().map(|m| m.sequence_id).collect::<Vec<_>>(), (5..15).collect::<Vec<_>>());
+    assert!(motions.iter().all(|m| m.position.z == 10.0 && m.position.w == 180.0));
+    assert!(motions.iter().all(|m| m.configuration.u_frame_number == 2 && m.configuration.u_tool_number == 3));
+    assert!(motions.iter().all(|m| m.speed == Speed::MmSec(150)));
+    assert!(motions[..9].iter().all(|m| m.termination == Termination::Cnt(80)));
+    assert_eq!(motions[9].termination, Termination::Fine);
+    let queue: Vec<PacketEnum> = queue.into_iter().collect();
+    assert!(Linter::new().lint(&queue).is_empty());
+
+    // Passes along Y, centered across the triangle's 100 mm width.
+    let triangle = [[0.0, 0.0], [100.0, 0.0], [0.0, 100.0]];
+    let queue = PathGenerator::new().raster(&triangle, 40.0, 90.0).unwrap();
+    assert_eq!(xy(&queue), vec![[90.0, 0.0], [90.0, 10.0], [50.0, 50.0], [50.0, 0.0], [10.0, 0.0], [10.0, 90.0]]);
+}
+
+#[test]
+fn raster_lifts_over_the_gaps_of_concave_outlines() {
+    let u_shape = [[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [70.0, 100.0], [70.0, 30.0], [30.0, 30.0], [30.0, 100.0], [0.0, 100.0]];
+    let error = PathGenerator::new().raster(&u_shape, 20.0, 0.0).unwrap_err();
+    assert!(error.to_string().contains("travel height"), "{}", error);
+
+    let queue = PathGenerator::new().height(5.0).travel_height(50.0).raster(&u_shape, 20.0, 0.0).unwrap();
+    let xyz: Vec<[f32; 3]> = moves(&queue).iter().map(|m| [m.position.x, m.position.y, m.position.z]).collect();
+    assert_eq!(xyz.len(), 28);
+    assert_eq!(
+        &xyz[..8],
+        &[
+            [0.0, 0.0, 5.0], [100.0, 0.0, 5.0], [100.0, 20.0, 5.0], [0.0, 20.0, 5.0],
+            [0.0, 40.0, 5.0], [30.0, 40.0, 5.0], [30.0, 40.0, 50.0], [70.0, 40.0, 50.0],
+        ]
+    );
+    assert_eq!(&xyz[8..10], &[[70.0, 40.0, 5.0], [100.0, 40.0, 5.0]]);
+    // Only the four gaps are lifted over; moving between passes along an edge is not.
+    assert_eq!(xyz.iter().filter(|p| p[2] == 50.0).count(), 8);
+}
+
+#[test]
+fn loaders_and_paths_share_motion_settings() {
+    let settings = MotionSettings::new().frame(2).tool(3).orientation(90.0, 0.0, 45.0);
+    let paths = PathGenerator::new().settings(settings).raster_rect([0.0, 0.0], 10.0, 10.0, 10.0).unwrap();
+    let gcode = GcodeLoader::new().settings(settings).load_str("G1 X10 F600").unwrap();
+    for queue in [&paths, &gcode] {
+        for motion in moves(queue) {
+            assert_eq!(motion.configuration, settings.configuration);
+            assert_eq!((motion.position.w, motion.position.p, motion.position.r), (90.0, 0.0, 45.0));
+        }
+    }
+}
+
+#[test]
+fn spirals_contours_and_weaves() {
+    let points = xy(&PathGenerator::new().max_segment(4.0).spiral([100.0, 50.0], 0.0, 20.0, 10.0).unwrap());
+    assert_eq!(points[0], [100.0, 50.0]);
+    assert_eq!(points[points.len() - 1], [120.0, 50.0]);
+    let radii: Vec<f32> = points.iter().map(|p| (p[0] - 100.0).hypot(p[1] - 50.0)).collect();
+    assert!(radii.windows(2).all(|r| r[1] > r[0]));
+    assert!(points.windows(2).all(|p| (p[1][0] - p[0][0]).hypot(p[1][1] - p[0][1]) < 4.5));
+    // Inwards, the other way round.
+    let inward = xy(&PathGenerator::new().spiral([0.0, 0.0], 20.0, 0.0, 10.0).unwrap());
+    assert_eq!((inward[0], inward[inward.len() - 1]), ([20.0, 0.0], [0.0, 0.0]));
+
+    // Each contour is closed; the fourth would collapse.
+    let square = [[0.0, 0.0], [100.0, 0.0], [100.0, 100.0], [0.0, 100.0], [0.0, 0.0]];
+    let points = xy(&PathGenerator::new().contours(&square, 20.0, 10).unwrap());
+    assert_eq!(points.len(), 15);
+    assert_eq!(&points[5..10], &[[20.0, 20.0], [80.0, 20.0], [80.0, 80.0], [20.0, 80.0], [20.0, 20.0]]);
+    assert_eq!(&points[10..], &[[40.0, 40.0], [60.0, 40.0], [60.0, 60.0], [40.0, 60.0], [40.0, 40.0]]);
+    let outward = xy(&PathGenerator::new().contours(&square, -10.0, 2).unwrap());
+    assert_eq!(outward[5..7], [[-10.0, -10.0], [110.0, -10.0]]);
+
+    // Three 33.3 mm cycles fit in 100 mm.
+    let zigzag = xy(&PathGenerator::new().weave([0.0, 0.0], [100.0, 0.0], Weave::Zigzag, 5.0, 30.0).unwrap());
+    assert_eq!(
+        zigzag,
+        vec![[0.0, 0.0], [8.33, 5.0], [25.0, -5.0], [41.67, 5.0], [58.33, -5.0], [75.0, 5.0], [91.67, -5.0], [100.0, 0.0]]
+    );
+    let sine = xy(&PathGenerator::new().weave([0.0, 0.0], [0.0, 100.0], Weave::Sine, 5.0, 30.0).unwrap());
+    assert_eq!(sine.len(), 25);
+    assert_eq!((sine[2], sine[6], sine[24]), ([-5.0, 8.33], [5.0, 25.0], [0.0, 100.0]));
+}
+
+#[test]
+fn path_parameters_are_checked() {
+    let paths = PathGenerator::new();
+    let message = |result: Result<VecDeque<PacketEnum>, FrcError>| match result {
+        Err(FrcError::Program { step: None, message }) => message,
+        other => panic!("expected a program error, got {:?}", other),
+    };
+    assert_eq!(message(paths.raster_rect([0.0, 0.0], 10.0, 10.0, 0.0)), "spacing must be positive, got 0");
+    assert!(message(paths.raster(&[[0.0, 0.0], [10.0, 0.0], [20.0, 0.0]], 1.0, 0.0)).contains("enclosing an area"));
+    assert!(message(paths.spiral([0.0, 0.0], 10.0, 10.0, 2.0)).contains("different start and end radii"));
+    assert!(message(paths.contours(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]], 0.0, 2)).contains("cannot be zero"));
+    assert!(message(paths.weave([1.0, 1.0], [1.0, 1.0], Weave::Sine, 1.0, 1.0)).contains("different start and end"));
+    assert!(message(paths.clone().max_segment(0.0).weave([0.0, 0.0], [9.0, 0.0], Weave::Sine, 1.0, 3.0)).contains("max_segment"));
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use fanuc_rmi::{
+    validate_group, Configuration, FlipNoFlip, FrcError, FrontBack, GroupMask, LeftRight, Speed, Termination, UpDown, INCH_MIN_RANGE, MM_SEC_RANGE,
+    GROUP_RANGE, PERCENT_RANGE, TURN_RANGE, UFRAME_RANGE, UTOOL_RANGE,
+};
+use fanuc_rmi::instructions::{ArcPlane, CircularArc, LocalCondition, MotionOptions, Move, PortOutput, PortType, ACC_RANGE};
+use fanuc_rmi::packets::OnOff;
+use serde_json::{json, Value};
+
+fn configuration_error(config: Configuration) -> String {
+    match config.validate() {
+        Err(FrcError::InvalidConfiguration(message)) => message,
+        other => panic!("expected InvalidConfiguration for {:?}, got {:?}", config, other),
+    }
+}
+
+#[test]
+fn configuration_ranges_are_inclusive() {
+    let config = Configuration::default();
+    for u_frame_number in [*UFRAME_RANGE.start(), *UFRAME_RANGE.end()] {
+        assert!(Configuration { u_frame_number, ..config }.validate().is_ok());
+    }
+    for u_tool_number in [*UTOOL_RANGE.start(), *UTOOL_RANGE.end()] {
+        assert!(Configuration { u_tool_number, ..config }.validate().is_ok());
+    }
+    for turn in [*TURN_RANGE.start(), *TURN_RANGE.end()] {
+        assert!(Configuration { turn4: turn, turn5: turn, turn6: turn, ..config }.validate().is_ok());
+    }
+
+    assert_eq!(configuration_error(Configuration { u_frame_number: 10, ..config }), "UFrameNumber 10 is outside 0..=9");
+    assert_eq!(configuration_error(Configuration { u_tool_number: 0, ..config }), "UToolNumber 0 is outside 1..=10");
+    assert_eq!(configuration_error(Configuration { u_tool_number: 11, ..config }), "UToolNumber 11 is outside 1..=10");
+    assert_eq!(configuration_error(Configuration { turn4: -9, ..config }), "Turn4 -9 is outside -8..=7");
+    assert_eq!(configuration_error(Configuration { turn5: 8, ..config }), "Turn5 8 is outside -8..=7");
+    assert_eq!(configuration_error(Configuration { turn6: 8, ..config }), "Turn6 8 is outside -8..=7");
+}
+
+#[test]
+fn configuration_flags_serialize_as_bits() {
+    let config = Configuration {
+        u_tool_number: 2,
+        u_frame_number: 3,
+        front: FrontBack::Back,
+        up: UpDown::Up,
+        left: LeftRight::Left,
+        flip: FlipNoFlip::NoFlip,
+        turn4: -1,
+        turn5: 0,
+        turn6: 1,
+    };
+    let value = json!({
+        "UToolNumber": 2, "UFrameNumber": 3, "Front": 0, "Up": 1, "Left": 1, "Flip": 0,
+        "Turn4": -1, "Turn5": 0, "Turn6": 1
+    });
+    assert_eq!(serde_json::to_value(config).unwrap(), value);
+    assert_eq!(serde_json::from_value::<Configuration>(value).unwrap(), config);
+    assert_eq!(config.to_string(), "NLUB -1, 0, 1");
+
+    for (flag, set) in [("Front", 1), ("Up", 0), ("Left", 0), ("Flip", 1)] {
+        let mut flipped = serde_json::to_value(config).unwrap();
+        flipped[flag] = json!(set);
+        assert!(serde_json::from_value::<Configuration>(flipped.clone()).is_ok());
+        flipped[flag] = json!(2);
+        let error = serde_json::from_value::<Configuration>(flipped).unwrap_err().to_string();
+        assert!(error.contains("must be 0 or 1, got 2"), "{}", error);
+    }
+}
+
+#[test]
+fn terminations_round_trip_as_term_pairs() {
+    let cases = [
+        (Termination::Fine, json!({ "TermType": "FINE", "TermValue": 1 })),
+        (Termination::Cnt(1), json!({ "TermType": "CNT", "TermValue": 1 })),
+        (Termination::Cnt(100), json!({ "TermType": "CNT", "TermValue": 100 })),
+        (Termination::Cr(50), json!({ "TermType": "CR", "TermValue": 50 })),
+    ];
+    for (termination, value) in cases {
+        assert!(termination.validate().is_ok());
+        assert_eq!(serde_json::to_value(termination).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Termination>(value).unwrap(), termination);
+    }
+    // The controller ignores the value of FINE.
+    let fine = json!({ "TermType": "FINE", "TermValue": 0 });
+    assert_eq!(serde_json::from_value::<Termination>(fine).unwrap(), Termination::Fine);
+}
+
+#[test]
+fn out_of_range_blend_values_are_rejected() {
+    for termination in [Termination::Cnt(0), Termination::Cnt(101), Termination::Cr(0), Termination::Cr(255)] {
+        assert!(matches!(termination.validate(), Err(FrcError::InvalidInstruction(_))), "{:?}", termination);
+    }
+    assert_eq!(Termination::Cnt(101).validate().unwrap_err().to_string(), "Invalid instruction: CNT value 101 is outside 1..=100");
+
+    for value in [json!({ "TermType": "CNT", "TermValue": 101 }), json!({ "TermType": "CR", "TermValue": 0 })] {
+        let error = serde_json::from_value::<Termination>(value).unwrap_err().to_string();
+        assert!(error.contains("is outside 1..=100"), "{}", error);
+    }
+}
+
+#[test]
+fn speed_ranges_are_inclusive() {
+    let cases = [(Speed::MmSec as fn(u16) -> Speed, MM_SEC_RANGE), (Speed::InchMin, INCH_MIN_RANGE), (Speed::Percent, PERCENT_RANGE)];
+    for (speed, range) in cases {
+        assert!(speed(*range.start()).validate().is_ok());
+        assert!(speed(*range.end()).validate().is_ok());
+        assert!(matches!(speed(0).validate(), Err(FrcError::InvalidInstruction(_))));
+        assert!(matches!(speed(range.end() + 1).validate(), Err(FrcError::InvalidInstruction(_))));
+    }
+    assert_eq!(Speed::MmSec(2001).validate().unwrap_err().to_string(), "Invalid instruction: MMSec speed 2001 is outside 1..=2000");
+    assert!(Speed::Time(u16::MAX).validate().is_ok() && Speed::Millis(1).validate().is_ok());
+    assert!(Speed::Time(0).validate().is_err() && Speed::Millis(0).validate().is_err());
+
+    let error = serde_json::from_value::<Speed>(json!({ "SpeedType": "Percent", "Speed": 101 })).unwrap_err();
+    assert!(error.to_string().contains("is outside 1..=100"), "{}", error);
+}
+
+#[test]
+fn speeds_convert_between_units() {
+    assert_eq!(Speed::MmSec(254).to_inch_min(), Some(Speed::InchMin(600)));
+    assert_eq!(Speed::InchMin(600).to_mm_sec(), Some(Speed::MmSec(254)));
+    assert_eq!(Speed::InchMin(600).mm_per_sec(), Some(254.0));
+    // The inch/min limit is the mm/sec limit converted and rounded.
+    assert_eq!(Speed::MmSec(*MM_SEC_RANGE.end()).to_inch_min(), Some(Speed::InchMin(*INCH_MIN_RANGE.end())));
+
+    assert_eq!(Speed::Percent(50).mm_per_sec(), None);
+    assert_eq!(Speed::Time(15).to_mm_sec(), None);
+    assert_eq!(Speed::Time(15).duration(), Some(Duration::from_millis(1500)));
+    assert_eq!(Speed::Millis(250).duration(), Some(Duration::from_millis(250)));
+    assert_eq!(Speed::MmSec(100).duration(), None);
+
+    let cases = [
+        (Speed::MmSec(100), json!({ "SpeedType": "mmSec", "Speed": 100 })),
+        (Speed::InchMin(100), json!({ "SpeedType": "InchMin", "Speed": 100 })),
+        (Speed::Time(20), json!({ "SpeedType": "Time", "Speed": 20 })),
+        (Speed::Millis(500), json!({ "SpeedType": "mSec", "Speed": 500 })),
+        (Speed::Percent(30), json!({ "SpeedType": "Percent", "Speed": 30 })),
+    ];
+    for (speed, value) in cases {
+        assert_eq!(serde_json::to_value(speed).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Speed>(value).unwrap(), speed);
+    }
+}
+
+/// The packet fields `options` adds to a linear move.
+fn option_fields(options: MotionOptions) -> Value {
+    let motion = Move::linear().to(Default::default()).options(options).build(1).unwrap();
+    let mut value = serde_json::to_value(&motion).unwrap();
+    let fields = value.as_object_mut().unwrap();
+    for key in ["Instruction", "SequenceID", "Configuration", "Position", "SpeedType", "Speed", "TermType", "TermValue"] {
+        fields.remove(key);
+    }
+    value
+}
+
+#[test]
+fn motion_options_flatten_into_the_instruction() {
+    let cases = [
+        (MotionOptions::default(), json!({})),
+        (MotionOptions { acc: Some(150), ..Default::default() }, json!({ "Acc": 150 })),
+        (MotionOptions { offset_pr_number: Some(3), ..Default::default() }, json!({ "OffsetPRNumber": 3 })),
+        (MotionOptions { vision_pr_number: Some(4), ..Default::default() }, json!({ "VisionPRNumber": 4 })),
+        (MotionOptions { mrot: true, ..Default::default() }, json!({ "MROT": 1 })),
+        (MotionOptions { no_blend: true, ..Default::default() }, json!({ "NoBlend": 1 })),
+        (
+            MotionOptions { lcb: Some(LocalCondition { port_type: PortType::DO, value: 5 }), ..Default::default() },
+            json!({ "LCBType": "DO", "LCBValue": 5 }),
+        ),
+        (
+            MotionOptions {
+                port_output: Some(PortOutput { port_type: PortType::RO, port_number: 2, port_value: OnOff::ON }),
+                ..Default::default()
+            },
+            json!({ "PortType": "RO", "PortNumber": 2, "PortValue": "ON" }),
+        ),
+    ];
+    for (options, fields) in cases {
+        assert_eq!(option_fields(options), fields);
+        assert_eq!(serde_json::from_value::<MotionOptions>(fields).unwrap(), options);
+    }
+
+    // Flags the controller echoes as 0 read back as unset.
+    let off = serde_json::from_value::<MotionOptions>(json!({ "MROT": 0, "NoBlend": 0 })).unwrap();
+    assert_eq!(off, MotionOptions::default());
+}
+
+#[test]
+fn motion_options_reject_values_the_controller_would() {
+    assert!(MotionOptions { acc: Some(*ACC_RANGE.start()), ..Default::default() }.validate().is_ok());
+    assert!(MotionOptions { acc: Some(*ACC_RANGE.end()), ..Default::default() }.validate().is_ok());
+    let port = |port_number| Some(PortOutput { port_type: PortType::DO, port_number, port_value: OnOff::OFF });
+    assert!(MotionOptions { port_output: port(1), ..Default::default() }.validate().is_ok());
+
+    let cases = [
+        (MotionOptions { acc: Some(151), ..Default::default() }, "Acc 151 is outside 0..=150"),
+        (MotionOptions { offset_pr_number: Some(0), ..Default::default() }, "OffsetPRNumber must be at least 1"),
+        (MotionOptions { vision_pr_number: Some(0), ..Default::default() }, "VisionPRNumber must be at least 1"),
+        (MotionOptions { port_output: port(0), ..Default::default() }, "PortNumber must be at least 1"),
+    ];
+    for (options, message) in cases {
+        match options.validate() {
+            Err(FrcError::InvalidInstruction(error)) => assert_eq!(error, message),
+            other => panic!("expected {:?}, got {:?}", message, other),
+        }
+    }
+}
+
+#[test]
+fn only_joint_moves_take_a_percent_speed() {
+    let linear = Move::linear().speed(Speed::Percent(50)).to(Default::default()).build(1);
+    assert!(matches!(linear, Err(FrcError::InvalidInstruction(_))));
+    let circular = Move::circular().via(Default::default()).to(Default::default()).speed(Speed::Percent(50)).build(1);
+    assert!(matches!(circular, Err(FrcError::InvalidInstruction(_))));
+    let arc = CircularArc::full_circle(ArcPlane::XY, &Default::default(), 50.0, 0.0).unwrap();
+    assert!(matches!(Move::circular().arc(&arc).speed(Speed::Percent(50)).build(1), Err(FrcError::InvalidInstruction(_))));
+
+    let linear = Move::linear().speed(Speed::InchMin(600)).to(Default::default()).build(1).unwrap();
+    assert_eq!(linear.speed, Speed::InchMin(600));
+    let joint = Move::joint().speed(Speed::Percent(50)).to(Default::default()).build(2).unwrap();
+    assert_eq!(joint.speed, Speed::Percent(50));
+}
+
+#[test]
+fn group_numbers_run_from_one_to_eight() {
+    assert!(validate_group(*GROUP_RANGE.start()).is_ok());
+    assert!(validate_group(*GROUP_RANGE.end()).is_ok());
+    for group in [0, 9] {
+        match validate_group(group) {
+            Err(FrcError::InvalidGroup(message)) => assert_eq!(message, format!("group {} is outside 1..=8", group)),
+            other => panic!("expected InvalidGroup for {}, got {:?}", group, other),
+        }
+    }
+}
+
+#[test]
+fn group_masks_set_one_bit_per_group() {
+    assert_eq!(GroupMask::default(), GroupMask::GROUP_1);
+    assert_eq!(GroupMask::GROUP_1.bits(), 1);
+
+    let mask = GroupMask::from_groups(&[1, 3, 8]).unwrap();
+    assert_eq!(mask.bits(), 0b1000_0101);
+    assert_eq!(mask.groups().collect::<Vec<_>>(), vec![1, 3, 8]);
+    assert!(mask.contains(3) && !mask.contains(2));
+    assert!(!mask.contains(0) && !mask.contains(9));
+    assert_eq!(GroupMask::from_groups(&[2, 2]).unwrap().bits(), 0b10);
+    assert_eq!(GroupMask::from_bits(0xff).unwrap().groups().count(), 8);
+
+    assert!(matches!(GroupMask::from_groups(&[1, 9]), Err(FrcError::InvalidGroup(_))));
+    assert!(matches!(GroupMask::from_groups(&[]), Err(FrcError::InvalidGroup(_))));
+    assert!(matches!(GroupMask::from_bits(0), Err(FrcError::InvalidGroup(_))));
+
+    assert_eq!(serde_json::to_value(mask).unwrap(), json!(133));
+    assert_eq!(serde_json::from_value::<GroupMask>(json!(5)).unwrap(), GroupMask::from_groups(&[1, 3]).unwrap());
+}
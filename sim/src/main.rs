@@ -4,7 +4,28 @@ use serde_json::json;
 use std::error::Error;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+#[cfg(feature = "tracing")]
+use tracing::Instrument;
+
+/// Routes sim output through `tracing` when the feature is enabled, otherwise stdout.
+macro_rules! sim_log {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::info!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        println!($($arg)*);
+    }};
+}
 
+/// Same as `sim_log!` but for failures, which go to stderr without `tracing`.
+macro_rules! sim_error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::error!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        eprintln!($($arg)*);
+    }};
+}
 
 // #[derive(Serialize, Deserialize, Debug)]
 // struct ConnectResponse {
@@ -19,7 +40,7 @@ async fn handle_client(mut socket: TcpStream, new_port: Arc<Mutex<u16>>) -> Resu
     let n = match socket.read(&mut buffer).await {
         Ok(n) => n,
         Err(e) => {
-            eprintln!("Failed to read from socket: {}", e);
+            sim_error!("Failed to read from socket: {}", e);
             return Err(Box::new(e));
         }
     };
@@ -29,7 +50,7 @@ async fn handle_client(mut socket: TcpStream, new_port: Arc<Mutex<u16>>) -> Resu
     }
 
     let request = String::from_utf8_lossy(&buffer[..n]);
-    println!("Received on primary : {}", request);
+    sim_log!("Received on primary : {}", request);
 
     let request_json: serde_json::Value = serde_json::from_str(&request)?;
 
@@ -43,7 +64,7 @@ async fn handle_client(mut socket: TcpStream, new_port: Arc<Mutex<u16>>) -> Resu
 
             json!({
                 "Communication": "FRC_Connect",
-                "ErrorID": 1,
+                "ErrorID": 0,
                 "PortNumber": port,
                 "MajorVersion": 1,
                 "MinorVersion": 0,
@@ -56,10 +77,10 @@ async fn handle_client(mut socket: TcpStream, new_port: Arc<Mutex<u16>>) -> Resu
 
     let response = serde_json::to_string(&response_json)? + "\r\n";
     socket.write_all(response.as_bytes()).await?;
-    println!("Sent: {}", response);
+    sim_log!("Sent: {}", response);
 
     if let Some(port) = response_json["PortNumber"].as_u64() {
-        println!("Port number for new connection: {}", port);
+        sim_log!("Port number for new connection: {}", port);
         return Ok(port as u16);
     }
 
@@ -67,7 +88,6 @@ async fn handle_client(mut socket: TcpStream, new_port: Arc<Mutex<u16>>) -> Resu
 }
 
 async fn handle_secondary_client(mut socket: TcpStream) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let mut seq: u32 = 0;
     let mut buffer = vec![0; 1024];
     let mut temp_buffer = Vec::new();
 
@@ -75,13 +95,13 @@ async fn handle_secondary_client(mut socket: TcpStream) -> Result<(), Box<dyn Er
         let n = match socket.read(&mut buffer).await {
             Ok(n) => n,
             Err(e) => {
-                eprintln!("Failed to read from socket: {}", e);
+                sim_error!("Failed to read from socket: {}", e);
                 return Err(Box::new(e));
             }
         };
 
         if n == 0 {
-            println!("Client disconnected");
+            sim_log!("Client disconnected");
             break;
         }
 
@@ -95,21 +115,26 @@ async fn handle_secondary_client(mut socket: TcpStream) -> Result<(), Box<dyn Er
             let request = &request[..request.len() - 1];
 
             let request_str = String::from_utf8_lossy(request);
-            println!("Received on secondary port: {}", request_str);
+            #[cfg(feature = "tracing")]
+            let received_at = std::time::Instant::now();
+            sim_log!("Received on secondary port: {}", request_str);
 
             let request_json: serde_json::Value = match serde_json::from_str(&request_str) {
                 Ok(json) => json,
                 Err(e) => {
-                    eprintln!("Failed to parse JSON: {}", e);
+                    sim_error!("Failed to parse JSON: {}", e);
                     continue;
                 }
             };
 
+            // Instructions are acknowledged with the sequence id they were sent with
+            let seq = request_json["SequenceID"].as_u64().unwrap_or(0);
+
             let mut response_json = match request_json["Command"].as_str() {
                 Some("FRC_Initialize") => json!({
                     "Command": "FRC_Initialize",
                     "ErrorID": 0,
-                    "GroupMask": 1
+                    "GroupMask": request_json["GroupMask"].as_u64().unwrap_or(1),
                 }),
                 Some("FRC_LinearMotion") => json!({
                     "Status": "Motion started"
@@ -118,6 +143,77 @@ async fn handle_secondary_client(mut socket: TcpStream) -> Result<(), Box<dyn Er
                     "Command": "FRC_Abort",
                     "ErrorID": 0,
                 }),
+                Some("FRC_Pause") => json!({
+                    "Command": "FRC_Pause",
+                    "ErrorID": 0,
+                }),
+                Some("FRC_Continue") => json!({
+                    "Command": "FRC_Continue",
+                    "ErrorID": 0,
+                }),
+                Some("FRC_Reset") => json!({
+                    "Command": "FRC_Reset",
+                    "ErrorID": 0,
+                }),
+                Some("FRC_ReadError") => json!({
+                    "Command": "FRC_ReadError",
+                    "ErrorID": 0,
+                    "Count": 1,
+                    "ErrorData": "",
+                }),
+                Some("FRC_SetUFrameUTool") => json!({
+                    "Command": "FRC_SetUFrameUTool",
+                    "ErrorID": 0,
+                    "Group": request_json["Group"],
+                }),
+                Some("FRC_SetOverride") => json!({
+                    "Command": "FRC_SetOverride",
+                    "ErrorID": 0,
+                }),
+                Some("FRC_GetUFrameUTool") => json!({
+                    "Command": "FRC_GetUFrameUTool",
+                    "ErrorID": 0,
+                    "UFrameNumber": 1,
+                    "UToolNumber": 1,
+                    "Group": request_json["Group"],
+                }),
+                Some("FRC_ReadJointAngles") => json!({
+                    "Command": "FRC_ReadJointAngles",
+                    "ErrorID": 0,
+                    "TimeTag": 0,
+                    "JointAngles": joint_angles(),
+                    "Group": request_json["Group"],
+                }),
+                Some("FRC_ReadCartesianPosition") => json!({
+                    "Command": "FRC_ReadCartesianPosition",
+                    "ErrorID": 0,
+                    "TimeTag": 0,
+                    "Configuration": configuration(),
+                    "Position": position(),
+                    "Group": request_json["Group"],
+                }),
+                Some("FRC_ReadPositionRegister") => json!({
+                    "Command": "FRC_ReadPositionRegister",
+                    "ErrorID": 0,
+                    "RegisterNumber": request_json["RegisterNumber"],
+                    "Configuration": configuration(),
+                    "Position": position(),
+                    "Group": request_json["Group"],
+                }),
+                Some("FRC_WritePositionRegister") => json!({
+                    "Command": "FRC_WritePositionRegister",
+                    "ErrorID": 0,
+                }),
+                Some("FRC_WriteDOUT") => json!({
+                    "Command": "FRC_WriteDOUT",
+                    "ErrorID": 0,
+                }),
+                Some("FRC_ReadDIN") => json!({
+                    "Command": "FRC_ReadDIN",
+                    "ErrorID": 0,
+                    "PortNumber": request_json["PortNumber"],
+                    "PortValue": "ON",
+                }),
                 _ => json!({}),
             };
 
@@ -130,13 +226,8 @@ async fn handle_secondary_client(mut socket: TcpStream) -> Result<(), Box<dyn Er
             };
 
             response_json = match request_json["Instruction"].as_str() {
-                Some("FRC_LinearMotion") => json!({
-                    "Instruction": "FRC_LinearMotion",
-                    "ErrorID": 0,
-                    "SequenceID": seq,
-                }),
-                Some("FRC_LinearRelative") => json!({
-                    "Instruction": "FRC_LinearRelative",
+                Some(name) => json!({
+                    "Instruction": name,
                     "ErrorID": 0,
                     "SequenceID": seq,
                 }),
@@ -145,8 +236,21 @@ async fn handle_secondary_client(mut socket: TcpStream) -> Result<(), Box<dyn Er
             // let delimiter: String = "\r\n".to_string();
             let response = serde_json::to_string(&response_json)? + "\r\n";
             socket.write_all(response.as_bytes()).await?;
-            println!("Sent: {}", response);
-            seq += 1;
+            sim_log!("Sent: {}", response);
+
+            #[cfg(feature = "tracing")]
+            {
+                let name = ["Communication", "Command", "Instruction"]
+                    .iter()
+                    .find_map(|key| request_json[*key].as_str())
+                    .unwrap_or("unknown");
+                tracing::debug!(
+                    packet = name,
+                    sequence_id = seq,
+                    latency_ms = received_at.elapsed().as_secs_f64() * 1000.0,
+                    "handled request"
+                );
+            }
         }
     }
 
@@ -159,34 +263,39 @@ async fn start_secondary_server(port: u16) -> Result<(), Box<dyn Error + Send +
     let listener = match TcpListener::bind(&addr).await {
         Ok(listener) => listener,
         Err(e) => {
-            eprintln!("Failed to bind to address {}: {}", addr, e);
+            sim_error!("Failed to bind to address {}: {}", addr, e);
             return Err(Box::new(e));
         }
     };
 
-    println!("Secondary server listening on port {}", port);
+    sim_log!("Secondary server listening on port {}", port);
 
     loop {
         let (socket, _) = match listener.accept().await {
             Ok((socket, addr)) => (socket, addr),
             Err(e) => {
-                eprintln!("Failed to accept connection: {}", e);
+                sim_error!("Failed to accept connection: {}", e);
                 continue;
             }
         };
 
-        tokio::spawn(async move {
+        let client = async move {
             if let Err(e) = handle_secondary_client(socket).await {
-                eprintln!("Error handling secondary client: {:?}", e);
+                sim_error!("Error handling secondary client: {:?}", e);
             }
-        });
+        };
+
+        #[cfg(feature = "tracing")]
+        let client = client.instrument(tracing::info_span!("connection", connection_id = port));
+
+        tokio::spawn(client);
     }
 }
 
 async fn start_server(port: u16) -> Result<(), Box<dyn Error + Send + Sync>> {
     let addr = format!("0.0.0.0:{}", port);
     let listener = TcpListener::bind(&addr).await?;
-    println!("Server listening on port {}", port);
+    sim_log!("Server listening on port {}", port);
 
     let new_port = Arc::new(Mutex::new(port + 1));
 
@@ -194,7 +303,7 @@ async fn start_server(port: u16) -> Result<(), Box<dyn Error + Send + Sync>> {
         let (socket, _) = match listener.accept().await {
             Ok((socket, addr)) => (socket, addr),
             Err(e) => {
-                eprintln!("Failed to accept connection: {}", e);
+                sim_error!("Failed to accept connection: {}", e);
                 continue;
             }
         };
@@ -203,19 +312,44 @@ async fn start_server(port: u16) -> Result<(), Box<dyn Error + Send + Sync>> {
 
         match handle_client(socket, new_port).await {
             Ok(port) if port != 0 => {
-                println!("Starting secondary server on port {}", port);
+                sim_log!("Starting secondary server on port {}", port);
                 tokio::spawn(start_secondary_server(port));
 
             },
             Ok(_) => {},
-            Err(e) => eprintln!("Failed to handle client: {:?}", e),
+            Err(e) => sim_error!("Failed to handle client: {:?}", e),
         };
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .init();
+
     start_server(16001).await?;
     Ok(())
 }
 
+fn configuration() -> serde_json::Value {
+    json!({
+        "UToolNumber": 1, "UFrameNumber": 1, "Front": 1, "Up": 1, "Left": 0, "Flip": 0,
+        "Turn4": 0, "Turn5": 0, "Turn6": 0,
+    })
+}
+
+fn position() -> serde_json::Value {
+    json!({
+        "X": 0.0, "Y": 0.0, "Z": 0.0, "W": 0.0, "P": 0.0, "R": 0.0,
+        "Ext1": 0.0, "Ext2": 0.0, "Ext3": 0.0,
+    })
+}
+
+fn joint_angles() -> serde_json::Value {
+    json!({
+        "J1": 0.0, "J2": 0.0, "J3": 0.0, "J4": 0.0, "J5": 0.0, "J6": 0.0,
+        "J7": 0.0, "J8": 0.0, "J9": 0.0,
+    })
+}